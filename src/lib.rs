@@ -0,0 +1,5 @@
+pub mod ast;
+#[allow(dead_code)]
+mod client;
+//mod parser;
+pub mod parser_update;
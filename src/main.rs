@@ -1,6 +1,541 @@
-mod ast;
-mod client;
-//mod parser;
-mod parser_update;
+use std::{
+    io::{IsTerminal, Read},
+    process::ExitCode,
+};
 
-fn main() {}
+use jtool::parser_update::{
+    canonical::CanonicalPrint,
+    diagnostic::Diagnostic,
+    diff::{self, Differ},
+    error::ErrorCode,
+    explain::explain,
+    infer::Inferrer,
+    jq::Program,
+    lexer::Dialect,
+    merge::{merge_text, MergeError},
+    ndjson::{DocumentReader, Framing},
+    nodes::{parse_str, parse_str_recovering, parse_str_with, Literal, Node},
+    printer::CompactPrint,
+    report::{emit, FileReport, Format},
+    rustgen::RustGenerator,
+    schema::Schema,
+    tsgen::TypeScriptGenerator,
+    typegen::Model,
+};
+
+const USAGE: &str = "usage:
+    jtool canonical [--dialect json|jsonc|json5] [<file|->]
+    jtool check [--dialect json|jsonc|json5] [--format text|json|sarif] [--color|--no-color]
+                [--ignore <code>,...] <file|->...
+    jtool codegen [--lang rust|typescript] [--name <name>] [--schema <file> | <file|->...]
+    jtool explain [<code>]
+    jtool infer [--enum-limit <n>] [<file|->...]
+    jtool merge [--dialect json|jsonc|json5] [-o <file>] <base> <ours> <theirs>
+    jtool diff [--sets] [--id <field>] [--format text|json] [--color|--no-color] <old> <new>
+    jtool jq [--raw] <filter> [<file|->...]
+    jtool validate [--dialect json|jsonc|json5] [--format text|json|sarif] [--color|--no-color]
+                   <schema> <file|->...";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    let result = match args.first().map(String::as_str) {
+        Some("canonical") => canonicalize(&args[1..]),
+        Some("check") => check(&args[1..]),
+        Some("codegen") => codegen(&args[1..]),
+        Some("explain") => explain_code(&args[1..]),
+        Some("infer") => infer(&args[1..]),
+        Some("jq") => filter(&args[1..]),
+        Some("diff") => compare(&args[1..]),
+        Some("merge") => merge_files(&args[1..]),
+        Some("validate") => validate(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the RFC 8785 canonical form of one file, with no trailing
+/// newline so the output can be hashed or signed as is.
+fn canonicalize(args: &[String]) -> Result<ExitCode, String> {
+    let mut dialect = Dialect::Json;
+    let mut files = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dialect" => dialect = parse_dialect(args.next())?,
+            file => files.push(file.to_string()),
+        }
+    }
+
+    let file = match files.as_slice() {
+        [] => "-",
+        [file] => file.as_str(),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let (root, _) = parse_str_with(&read_source(file)?, dialect).map_err(|error| {
+        format!(
+            "{}:{}:{}: {}",
+            file, error.span.line, error.span.column, error.message
+        )
+    })?;
+    let output = CanonicalPrint
+        .print(&root)
+        .map_err(|error| format!("{}: {}", file, error))?;
+
+    print!("{}", output);
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Parses every file and prints all diagnostics. Exits with 1 if any were found.
+fn check(args: &[String]) -> Result<ExitCode, String> {
+    let mut dialect = Dialect::Json;
+    let mut format = Format::Text;
+    let mut color = std::io::stdout().is_terminal();
+    let mut ignored = vec![];
+    let mut files = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dialect" => dialect = parse_dialect(args.next())?,
+            "--format" => {
+                format = args
+                    .next()
+                    .and_then(|name| Format::from_name(name))
+                    .ok_or_else(|| USAGE.to_string())?
+            }
+            "--ignore" => {
+                for code in args.next().ok_or_else(|| USAGE.to_string())?.split(',') {
+                    ignored.push(
+                        ErrorCode::from_code(code.trim())
+                            .ok_or_else(|| format!("unknown error code {}", code))?,
+                    );
+                }
+            }
+            "--color" => color = true,
+            "--no-color" => color = false,
+            file => files.push(file.to_string()),
+        }
+    }
+
+    if files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let mut reports = vec![];
+
+    for file in files {
+        let source = read_source(&file)?;
+        let (_, errors) = parse_str_recovering(&source, dialect);
+
+        reports.push(FileReport {
+            file,
+            source,
+            diagnostics: errors
+                .iter()
+                .filter(|error| !ignored.contains(&error.code))
+                .map(Diagnostic::from)
+                .collect(),
+        });
+    }
+
+    let clean = reports.iter().all(|report| report.diagnostics.is_empty());
+    let output = emit(&reports, format, color);
+
+    if !output.is_empty() {
+        println!("{}", output.trim_end());
+    }
+
+    Ok(if clean {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Prints type declarations for a JSON Schema, or for the schema inferred
+/// from every document in the inputs.
+fn codegen(args: &[String]) -> Result<ExitCode, String> {
+    let mut typescript = false;
+    let mut name = "Root".to_string();
+    let mut schema = None;
+    let mut files = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                typescript = match args.next().map(String::as_str) {
+                    Some("rust") => false,
+                    Some("typescript") => true,
+                    _ => return Err(USAGE.to_string()),
+                }
+            }
+            "--name" => name = args.next().ok_or_else(|| USAGE.to_string())?.clone(),
+            "--schema" => schema = Some(args.next().ok_or_else(|| USAGE.to_string())?.clone()),
+            file => files.push(file.to_string()),
+        }
+    }
+
+    let model = match schema {
+        Some(_) if !files.is_empty() => return Err(USAGE.to_string()),
+        Some(file) => {
+            let root = parse_str(&read_source(&file)?).map_err(|error| {
+                format!(
+                    "{}:{}:{}: {}",
+                    file, error.span.line, error.span.column, error.message
+                )
+            })?;
+
+            Model::from_schema(&root, &name)
+                .map_err(|error| format!("{}: invalid schema, {}", file, error))?
+        }
+        None => {
+            if files.is_empty() {
+                files.push("-".to_string());
+            }
+
+            let mut inferrer = Inferrer::new();
+
+            for file in files {
+                let source = read_source(&file)?;
+
+                for node in DocumentReader::new(source.as_bytes(), Framing::Concatenated) {
+                    inferrer.add(&node.map_err(|error| format!("{}: {}", file, error))?);
+                }
+            }
+
+            Model::from_schema(&inferrer.schema(), &name)
+                .map_err(|error| format!("invalid inferred schema, {}", error))?
+        }
+    };
+
+    if typescript {
+        print!("{}", TypeScriptGenerator.generate(&model));
+    } else {
+        print!("{}", RustGenerator.generate(&model));
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints the explanation for one code, or lists every code.
+fn explain_code(args: &[String]) -> Result<ExitCode, String> {
+    match args {
+        [] => {
+            for code in ErrorCode::ALL {
+                println!("{}  {}", code, explain(code).title);
+            }
+        }
+        [code] => {
+            let code =
+                ErrorCode::from_code(code).ok_or_else(|| format!("unknown error code {}", code))?;
+
+            println!("{}", explain(code));
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints a JSON Schema inferred from every document in the inputs. Files
+/// may hold several documents, one per line or concatenated.
+fn infer(args: &[String]) -> Result<ExitCode, String> {
+    let mut inferrer = Inferrer::new();
+    let mut files = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--enum-limit" => {
+                let limit = args
+                    .next()
+                    .and_then(|limit| limit.parse().ok())
+                    .ok_or_else(|| USAGE.to_string())?;
+                inferrer = inferrer.enum_limit(limit);
+            }
+            file => files.push(file.to_string()),
+        }
+    }
+
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+
+    for file in files {
+        let source = read_source(&file)?;
+
+        for node in DocumentReader::new(source.as_bytes(), Framing::Concatenated) {
+            inferrer.add(&node.map_err(|error| format!("{}: {}", file, error))?);
+        }
+    }
+
+    println!("{}", CompactPrint.print(&inferrer.schema()));
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Runs a jq filter over every value in the inputs, printing one compact
+/// result per line. With `--raw` strings are printed without quotes.
+fn filter(args: &[String]) -> Result<ExitCode, String> {
+    let raw = args.first().is_some_and(|arg| arg == "--raw");
+    let args = if raw { &args[1..] } else { args };
+
+    let (program, files) = args.split_first().ok_or_else(|| USAGE.to_string())?;
+    let program = Program::parse(program).map_err(|error| format!("jq: {}", error))?;
+
+    let files = if files.is_empty() {
+        vec!["-".to_string()]
+    } else {
+        files.to_vec()
+    };
+
+    for file in files {
+        let source = read_source(&file)?;
+
+        for node in DocumentReader::new(source.as_bytes(), Framing::Concatenated) {
+            let node = node.map_err(|error| format!("{}: {}", file, error))?;
+
+            for output in program
+                .run(&node)
+                .map_err(|error| format!("jq: {}", error))?
+            {
+                match output {
+                    Node::Primary(Literal::String(value)) if raw => println!("{}", value),
+                    output => println!("{}", CompactPrint.print(&output)),
+                }
+            }
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints the structural differences between two files. Exits with 1 if
+/// there are any.
+fn compare(args: &[String]) -> Result<ExitCode, String> {
+    let mut differ = Differ::new();
+    let mut json = false;
+    let mut color = std::io::stdout().is_terminal();
+    let mut files = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sets" => differ = differ.arrays_as_sets(true),
+            "--id" => differ = differ.match_by(args.next().ok_or_else(|| USAGE.to_string())?),
+            "--format" => {
+                json = match args.next().map(String::as_str) {
+                    Some("text") => false,
+                    Some("json") => true,
+                    _ => return Err(USAGE.to_string()),
+                }
+            }
+            "--color" => color = true,
+            "--no-color" => color = false,
+            file => files.push(file.to_string()),
+        }
+    }
+
+    let [old, new] = files.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+
+    let parse = |file: &str| {
+        parse_str(&read_source(file)?).map_err(|error| {
+            format!(
+                "{}:{}:{}: {}",
+                file, error.span.line, error.span.column, error.message
+            )
+        })
+    };
+
+    let changes = differ.diff(&parse(old)?, &parse(new)?);
+
+    if json {
+        println!("{}", CompactPrint.print(&diff::to_json(&changes)));
+    } else {
+        print!("{}", diff::render(&changes, color));
+    }
+
+    Ok(if changes.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Three-way merges JSON files, writing the result to `-o` or stdout and
+/// listing conflicts on stderr. Exits with 1 on conflicts, so it can serve
+/// as a git merge driver:
+///
+/// ```text
+/// # .gitattributes
+/// *.json merge=jtool
+/// # .git/config
+/// [merge "jtool"]
+///     driver = jtool merge -o %A %O %A %B
+/// ```
+fn merge_files(args: &[String]) -> Result<ExitCode, String> {
+    let mut dialect = Dialect::Json;
+    let mut output = None;
+    let mut files = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dialect" => dialect = parse_dialect(args.next())?,
+            "-o" => output = Some(args.next().ok_or_else(|| USAGE.to_string())?.clone()),
+            file => files.push(file.to_string()),
+        }
+    }
+
+    let [base, ours, theirs] = files.as_slice() else {
+        return Err(USAGE.to_string());
+    };
+
+    let (merged, conflicts) = merge_text(
+        &read_source(base)?,
+        &read_source(ours)?,
+        &read_source(theirs)?,
+        dialect,
+    )
+    .map_err(|error| {
+        let (file, error) = match &error {
+            MergeError::Base(parse) => (base, parse),
+            MergeError::Ours(parse) => (ours, parse),
+            MergeError::Theirs(parse) => (theirs, parse),
+            MergeError::Edit(..) => return format!("cannot merge, {}", error),
+        };
+
+        format!(
+            "cannot merge, {}:{}:{}: {}",
+            file, error.span.line, error.span.column, error.message
+        )
+    })?;
+
+    match output {
+        Some(file) => {
+            std::fs::write(&file, merged).map_err(|error| format!("{}: {}", file, error))?
+        }
+        None => print!("{}", merged),
+    }
+
+    for conflict in &conflicts {
+        let path = match conflict.path.as_str() {
+            "" => "(root)",
+            path => path,
+        };
+
+        eprintln!("conflict at {}", path);
+    }
+
+    Ok(if conflicts.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Checks every file against a JSON Schema and prints every violation in
+/// the same formats as `check`. Exits with 1 if any file is invalid.
+fn validate(args: &[String]) -> Result<ExitCode, String> {
+    let mut dialect = Dialect::Json;
+    let mut format = Format::Text;
+    let mut color = std::io::stdout().is_terminal();
+    let mut files = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dialect" => dialect = parse_dialect(args.next())?,
+            "--format" => {
+                format = args
+                    .next()
+                    .and_then(|name| Format::from_name(name))
+                    .ok_or_else(|| USAGE.to_string())?
+            }
+            "--color" => color = true,
+            "--no-color" => color = false,
+            file => files.push(file.to_string()),
+        }
+    }
+
+    let Some((schema, files)) = files.split_first().filter(|(_, files)| !files.is_empty()) else {
+        return Err(USAGE.to_string());
+    };
+
+    let parse = |file: &str, source: &str| {
+        parse_str_with(source, dialect)
+            .map(|(root, _)| root)
+            .map_err(|error| {
+                format!(
+                    "{}:{}:{}: {}",
+                    file, error.span.line, error.span.column, error.message
+                )
+            })
+    };
+
+    let schema = Schema::compile(&parse(schema, &read_source(schema)?)?)
+        .map_err(|error| format!("{}: invalid schema, {}", schema, error))?;
+    let mut reports = vec![];
+
+    for file in files {
+        let source = read_source(file)?;
+        let errors = schema.validate(&parse(file, &source)?);
+
+        reports.push(FileReport {
+            file: file.to_string(),
+            source,
+            diagnostics: errors.iter().map(Diagnostic::from).collect(),
+        });
+    }
+
+    let valid = reports.iter().all(|report| report.diagnostics.is_empty());
+    let output = emit(&reports, format, color);
+
+    if !output.is_empty() {
+        println!("{}", output.trim_end());
+    }
+
+    Ok(if valid {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// The dialect named by the value of a `--dialect` option.
+fn parse_dialect(name: Option<&String>) -> Result<Dialect, String> {
+    match name.map(String::as_str) {
+        Some("json") => Ok(Dialect::Json),
+        Some("jsonc") => Ok(Dialect::Jsonc),
+        Some("json5") => Ok(Dialect::Json5),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn read_source(file: &str) -> Result<String, String> {
+    let mut source = String::new();
+
+    let result = if file == "-" {
+        std::io::stdin().read_to_string(&mut source).map(|_| ())
+    } else {
+        std::fs::read_to_string(file).map(|contents| source = contents)
+    };
+
+    result.map_err(|error| format!("{}: {}", file, error))?;
+
+    Ok(source)
+}
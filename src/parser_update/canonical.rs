@@ -0,0 +1,235 @@
+use std::fmt;
+
+use super::{
+    nodes::{Literal, Node, Token, Visitor},
+    printer::escape_string,
+};
+
+/// Why a [`Node`] has no canonical form.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanonicalError {
+    /// `NaN` and the infinities, which JSON5 accepts but I-JSON does not.
+    NonFiniteNumber(f64),
+    /// A placeholder left by error recovery.
+    ErrorNode,
+}
+
+impl fmt::Display for CanonicalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CanonicalError::NonFiniteNumber(value) => {
+                let name = if value.is_nan() {
+                    "NaN"
+                } else if *value > 0.0 {
+                    "Infinity"
+                } else {
+                    "-Infinity"
+                };
+
+                write!(f, "{} has no canonical JSON form", name)
+            }
+            CanonicalError::ErrorNode => write!(f, "cannot serialize a document with errors"),
+        }
+    }
+}
+
+impl std::error::Error for CanonicalError {}
+
+/// Serializes a [`Node`] with the JSON Canonicalization Scheme (RFC 8785):
+/// no whitespace, object keys sorted by UTF-16 code units, numbers in
+/// ECMAScript notation and strings with minimal escaping. Equal documents
+/// always produce the same bytes, so the output can be hashed or signed.
+pub struct CanonicalPrint;
+
+impl CanonicalPrint {
+    pub fn print(&self, root: &Node) -> Result<String, CanonicalError> {
+        root.accept(self)
+    }
+}
+
+impl Visitor<Result<String, CanonicalError>> for CanonicalPrint {
+    fn visit_primary(&self, value: &Literal) -> Result<String, CanonicalError> {
+        match value {
+            Literal::String(value) => Ok(escape_string(value)),
+            Literal::Number(value) => format_number(*value),
+            _ => Ok(value.to_string()),
+        }
+    }
+
+    fn visit_object(
+        &self,
+        _left: &Token,
+        properties: &[Node],
+        _right: &Token,
+    ) -> Result<String, CanonicalError> {
+        let mut properties = properties
+            .iter()
+            .map(|node| {
+                let key = match node {
+                    Node::Property(key, _, _) => key.literal.to_string().encode_utf16().collect(),
+                    _ => vec![],
+                };
+
+                Ok((key, node.accept(self)?))
+            })
+            .collect::<Result<Vec<(Vec<u16>, String)>, CanonicalError>>()?;
+
+        properties.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        let properties = properties
+            .into_iter()
+            .map(|(_, property)| property)
+            .collect::<Vec<String>>();
+
+        Ok(format!("{{{}}}", properties.join(",")))
+    }
+
+    fn visit_property(
+        &self,
+        key: &Token,
+        _colon: &Token,
+        value: &Node,
+    ) -> Result<String, CanonicalError> {
+        Ok(format!(
+            "{}:{}",
+            escape_string(&key.literal.to_string()),
+            value.accept(self)?
+        ))
+    }
+
+    fn visit_list(
+        &self,
+        _left: &Token,
+        nodes: &[Node],
+        _right: &Token,
+    ) -> Result<String, CanonicalError> {
+        let nodes = nodes
+            .iter()
+            .map(|node| node.accept(self))
+            .collect::<Result<Vec<String>, CanonicalError>>()?;
+
+        Ok(format!("[{}]", nodes.join(",")))
+    }
+
+    fn visit_error(&self, _token: &Token) -> Result<String, CanonicalError> {
+        Err(CanonicalError::ErrorNode)
+    }
+}
+
+/// Formats a number the way ECMAScript's `Number.prototype.toString` does:
+/// the shortest digits that round-trip, in plain notation for exponents
+/// from -7 to 20 and scientific notation otherwise.
+pub fn format_number(value: f64) -> Result<String, CanonicalError> {
+    if !value.is_finite() {
+        return Err(CanonicalError::NonFiniteNumber(value));
+    }
+
+    if value == 0.0 {
+        return Ok("0".to_string());
+    }
+
+    if value < 0.0 {
+        return format_number(-value).map(|number| format!("-{}", number));
+    }
+
+    // `{:e}` gives the shortest round-trip digits, e.g. `1.2345e-7`.
+    let scientific = format!("{:e}", value);
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("scientific notation has an exponent");
+    let digits = mantissa.replace('.', "");
+    let exponent = exponent
+        .parse::<i32>()
+        .expect("scientific notation has an integer exponent");
+
+    // The value is 0.digits × 10^point.
+    let length = digits.len() as i32;
+    let point = exponent + 1;
+
+    let number = if length <= point && point <= 21 {
+        format!("{}{}", digits, "0".repeat((point - length) as usize))
+    } else if 0 < point && point <= 21 {
+        let (whole, fraction) = digits.split_at(point as usize);
+        format!("{}.{}", whole, fraction)
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let sign = if exponent < 0 { '-' } else { '+' };
+
+        if rest.is_empty() {
+            format!("{}e{}{}", first, sign, exponent.abs())
+        } else {
+            format!("{}.{}e{}{}", first, rest, sign, exponent.abs())
+        }
+    };
+
+    Ok(number)
+}
+
+#[cfg(test)]
+mod canonical_tests {
+    use super::*;
+    use crate::parser_update::{
+        lexer::Dialect,
+        nodes::{parse_str, parse_str_with},
+    };
+
+    #[test]
+    fn rfc_example() {
+        let root = parse_str(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            CanonicalPrint.print(&root).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn key_order() {
+        let root = parse_str(
+            r#"{"\u20ac": 0, "\r": 1, "\ufb33": 2, "1": 3, "\ud83d\ude00": 4, "\u0080": 5, "\u00f6": 6}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            CanonicalPrint.print(&root).unwrap(),
+            "{\"\\r\":1,\"1\":3,\"\u{80}\":5,\"ö\":6,\"€\":0,\"😀\":4,\"\u{fb33}\":2}"
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        for (value, expected) in [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (0.1, "0.1"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (123e18, "123000000000000000000"),
+            (1e21, "1e+21"),
+            (9007199254740992.0, "9007199254740992"),
+            (295147905179352830000.0, "295147905179352830000"),
+            (5e-324, "5e-324"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (-1.25e-10, "-1.25e-10"),
+        ] {
+            assert_eq!(format_number(value).unwrap(), expected);
+        }
+
+        let (root, _) = parse_str_with("[NaN]", Dialect::Json5).unwrap();
+        assert!(matches!(
+            CanonicalPrint.print(&root),
+            Err(CanonicalError::NonFiniteNumber(_))
+        ));
+    }
+}
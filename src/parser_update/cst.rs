@@ -0,0 +1,633 @@
+use std::fmt::Display;
+
+use super::{
+    error::{ErrorCode, ParseError, Span},
+    lexer::{Dialect, Lexer},
+    limits::Limits,
+    nodes::{Literal, Node, Parser, Token, TokenType},
+    printer::{escape_string, CompactPrint},
+};
+
+/// A token with its exact source text and the whitespace and comments
+/// (trivia) in front of it.
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    pub leading: String,
+    pub text: String,
+    pub token: Token,
+}
+
+impl SyntaxToken {
+    fn punctuation(token_type: TokenType, text: &str) -> Self {
+        Self {
+            leading: String::new(),
+            text: text.to_string(),
+            token: Token::new(token_type, Literal::String(text.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub key: SyntaxToken,
+    pub colon: SyntaxToken,
+    pub value: SyntaxNode,
+    pub comma: Option<SyntaxToken>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Element {
+    pub value: SyntaxNode,
+    pub comma: Option<SyntaxToken>,
+}
+
+/// Concrete syntax tree node, every token of the source is kept.
+#[derive(Debug, Clone)]
+pub enum SyntaxNode {
+    Scalar(SyntaxToken),
+    Object {
+        open: SyntaxToken,
+        members: Vec<Member>,
+        close: SyntaxToken,
+    },
+    Array {
+        open: SyntaxToken,
+        elements: Vec<Element>,
+        close: SyntaxToken,
+    },
+}
+
+impl SyntaxNode {
+    fn first_token_mut(&mut self) -> &mut SyntaxToken {
+        match self {
+            Self::Scalar(token) => token,
+            Self::Object { open, .. } | Self::Array { open, .. } => open,
+        }
+    }
+
+    fn tokens<'a>(&'a self, tokens: &mut Vec<&'a SyntaxToken>) {
+        match self {
+            Self::Scalar(token) => tokens.push(token),
+            Self::Object {
+                open,
+                members,
+                close,
+            } => {
+                tokens.push(open);
+
+                for member in members {
+                    tokens.push(&member.key);
+                    tokens.push(&member.colon);
+                    member.value.tokens(tokens);
+                    tokens.extend(member.comma.iter());
+                }
+
+                tokens.push(close);
+            }
+            Self::Array {
+                open,
+                elements,
+                close,
+            } => {
+                tokens.push(open);
+
+                for element in elements {
+                    element.value.tokens(tokens);
+                    tokens.extend(element.comma.iter());
+                }
+
+                tokens.push(close);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    NotFound(String),
+    NotAContainer(String),
+    InvalidIndex(String),
+    /// The new value could not be turned into syntax, with the reason.
+    InvalidValue(String),
+}
+
+impl std::error::Error for EditError {}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(segment) => write!(f, "No value found at '{}'", segment),
+            Self::NotAContainer(segment) => {
+                write!(
+                    f,
+                    "Cannot descend into '{}', not an object or array",
+                    segment
+                )
+            }
+            Self::InvalidIndex(segment) => write!(f, "Invalid array index '{}'", segment),
+            Self::InvalidValue(message) => write!(f, "Invalid value: {}", message),
+        }
+    }
+}
+
+/// Lossless syntax tree. Printing it reproduces the source byte for byte,
+/// and edits only rewrite the tokens they touch.
+#[derive(Debug, Clone)]
+pub struct SyntaxTree {
+    pub root: SyntaxNode,
+    pub trailing: String,
+    dialect: Dialect,
+}
+
+impl SyntaxTree {
+    pub fn parse(input: &str, dialect: Dialect) -> Result<Self, ParseError> {
+        Self::parse_with_limits(input, dialect, Limits::default())
+    }
+
+    fn parse_with_limits(
+        input: &str,
+        dialect: Dialect,
+        limits: Limits,
+    ) -> Result<Self, ParseError> {
+        let tokens = Lexer::with_dialect(input.as_bytes(), dialect)
+            .limits(limits)
+            .tokenize()?;
+
+        Parser::with_dialect(tokens.clone(), dialect)
+            .limits(limits)
+            .parse_document()?;
+
+        let mut previous = 0;
+        let mut syntax_tokens = vec![];
+
+        for token in tokens {
+            syntax_tokens.push(SyntaxToken {
+                leading: input[previous..token.span.start].to_string(),
+                text: input[token.span.start..token.span.end].to_string(),
+                token: token.clone(),
+            });
+            previous = token.span.end;
+        }
+
+        let eof = syntax_tokens.pop().map(|token| token.leading);
+        let mut builder = Builder {
+            tokens: syntax_tokens.into_iter(),
+        };
+
+        Ok(Self {
+            root: builder.node()?,
+            trailing: eof.unwrap_or_default(),
+            dialect,
+        })
+    }
+
+    /// Parses the current text into a plain [`Node`]. The source was
+    /// checked against the limits when it was parsed, and values set since
+    /// come from the caller, so only nesting is limited.
+    pub fn to_node(&self) -> Result<Node, ParseError> {
+        let mut tokens = vec![];
+        self.root.tokens(&mut tokens);
+
+        let mut tokens = tokens
+            .into_iter()
+            .map(|token| token.token.clone())
+            .collect::<Vec<_>>();
+        tokens.push(Token::new(TokenType::Eof, Literal::String(String::new())));
+
+        Parser::with_dialect(tokens, self.dialect)
+            .limits(trusted_limits())
+            .parse_document()
+    }
+
+    pub fn get(&self, path: &[&str]) -> Result<&SyntaxNode, EditError> {
+        let mut node = &self.root;
+
+        for segment in path {
+            node = match node {
+                SyntaxNode::Object { members, .. } => {
+                    &members[find_member(members, segment)
+                        .ok_or_else(|| EditError::NotFound(segment.to_string()))?]
+                    .value
+                }
+                SyntaxNode::Array { elements, .. } => {
+                    &elements[parse_index(segment, elements.len())?].value
+                }
+                SyntaxNode::Scalar(_) => return Err(EditError::NotAContainer(segment.to_string())),
+            };
+        }
+
+        Ok(node)
+    }
+
+    /// Replaces the value at `path`, or appends it when the last segment
+    /// names a missing key or the end of an array (`-`). The replaced
+    /// value keeps its leading trivia, everything else is left untouched.
+    pub fn set(&mut self, path: &[&str], value: &Node) -> Result<(), EditError> {
+        let mut replacement = value_syntax(value)?;
+
+        let Some((last, parent)) = path.split_last() else {
+            replacement.first_token_mut().leading = self.root.first_token_mut().leading.clone();
+            self.root = replacement;
+            return Ok(());
+        };
+
+        match self.get_mut(parent)? {
+            SyntaxNode::Object { members, .. } => match find_member(members, last) {
+                Some(index) => replace(&mut members[index].value, replacement),
+                None => {
+                    let key = SyntaxToken {
+                        leading: members
+                            .last()
+                            .map(|member| indentation(&member.key.leading))
+                            .unwrap_or_default(),
+                        text: escape_string(last),
+                        token: Token::new(TokenType::String, Literal::String(last.to_string())),
+                    };
+                    replacement.first_token_mut().leading = " ".to_string();
+
+                    let comma = append_comma(members.last_mut().map(|member| &mut member.comma));
+                    members.push(Member {
+                        key,
+                        colon: SyntaxToken::punctuation(TokenType::Colon, ":"),
+                        value: replacement,
+                        comma,
+                    });
+                }
+            },
+            SyntaxNode::Array { elements, .. } => {
+                if *last == "-" || *last == elements.len().to_string() {
+                    replacement.first_token_mut().leading = match elements.last_mut() {
+                        Some(element) => indentation(&element.value.first_token_mut().leading),
+                        None => String::new(),
+                    };
+
+                    let comma = append_comma(elements.last_mut().map(|element| &mut element.comma));
+                    elements.push(Element {
+                        value: replacement,
+                        comma,
+                    });
+                } else {
+                    let index = parse_index(last, elements.len())?;
+                    replace(&mut elements[index].value, replacement);
+                }
+            }
+            SyntaxNode::Scalar(_) => return Err(EditError::NotAContainer(last.to_string())),
+        }
+
+        Ok(())
+    }
+
+    /// Removes the member or element at `path` together with its leading
+    /// trivia, fixing up the separating comma.
+    pub fn remove(&mut self, path: &[&str]) -> Result<(), EditError> {
+        let Some((last, parent)) = path.split_last() else {
+            return Err(EditError::NotFound(String::new()));
+        };
+
+        match self.get_mut(parent)? {
+            SyntaxNode::Object { members, .. } => {
+                let index = find_member(members, last)
+                    .ok_or_else(|| EditError::NotFound(last.to_string()))?;
+                let removed = members.remove(index);
+
+                if removed.comma.is_none() && index > 0 {
+                    members[index - 1].comma = None;
+                }
+            }
+            SyntaxNode::Array { elements, .. } => {
+                let index = parse_index(last, elements.len())?;
+                let removed = elements.remove(index);
+
+                if removed.comma.is_none() && index > 0 {
+                    elements[index - 1].comma = None;
+                }
+            }
+            SyntaxNode::Scalar(_) => return Err(EditError::NotAContainer(last.to_string())),
+        }
+
+        Ok(())
+    }
+
+    fn get_mut(&mut self, path: &[&str]) -> Result<&mut SyntaxNode, EditError> {
+        let mut node = &mut self.root;
+
+        for segment in path {
+            node = match node {
+                SyntaxNode::Object { members, .. } => {
+                    let index = find_member(members, segment)
+                        .ok_or_else(|| EditError::NotFound(segment.to_string()))?;
+                    &mut members[index].value
+                }
+                SyntaxNode::Array { elements, .. } => {
+                    let index = parse_index(segment, elements.len())?;
+                    &mut elements[index].value
+                }
+                SyntaxNode::Scalar(_) => return Err(EditError::NotAContainer(segment.to_string())),
+            };
+        }
+
+        Ok(node)
+    }
+
+    /// Lexical equality: the same tokens spelled the same way, so `1` and
+    /// `1.0` differ, as do objects with their members in another order.
+    /// Whitespace and comments are ignored.
+    pub fn lexical_eq(&self, other: &SyntaxTree) -> bool {
+        let (mut left, mut right) = (vec![], vec![]);
+        self.root.tokens(&mut left);
+        other.root.tokens(&mut right);
+
+        left.len() == right.len()
+            && left
+                .iter()
+                .zip(&right)
+                .all(|(left, right)| left.text == right.text)
+    }
+}
+
+impl Display for SyntaxTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut tokens = vec![];
+        self.root.tokens(&mut tokens);
+
+        for token in tokens {
+            write!(f, "{}{}", token.leading, token.text)?;
+        }
+
+        write!(f, "{}", self.trailing)
+    }
+}
+
+struct Builder {
+    tokens: std::vec::IntoIter<SyntaxToken>,
+}
+
+impl Builder {
+    fn node(&mut self) -> Result<SyntaxNode, ParseError> {
+        let open = self.next()?;
+
+        match open.token.token_type {
+            TokenType::LeftBrace => {
+                let mut members = vec![];
+
+                loop {
+                    let key = self.next()?;
+
+                    if key.token.token_type == TokenType::RightBrace {
+                        return Ok(SyntaxNode::Object {
+                            open,
+                            members,
+                            close: key,
+                        });
+                    }
+
+                    let colon = self.next()?;
+                    let value = self.node()?;
+                    let (comma, close) = self.separator(TokenType::RightBrace)?;
+
+                    members.push(Member {
+                        key,
+                        colon,
+                        value,
+                        comma,
+                    });
+
+                    if let Some(close) = close {
+                        return Ok(SyntaxNode::Object {
+                            open,
+                            members,
+                            close,
+                        });
+                    }
+                }
+            }
+            TokenType::LeftBracket => {
+                let mut elements = vec![];
+
+                loop {
+                    if self.peek_is(TokenType::RightBracket) {
+                        return Ok(SyntaxNode::Array {
+                            open,
+                            elements,
+                            close: self.next()?,
+                        });
+                    }
+
+                    let value = self.node()?;
+                    let (comma, close) = self.separator(TokenType::RightBracket)?;
+
+                    elements.push(Element { value, comma });
+
+                    if let Some(close) = close {
+                        return Ok(SyntaxNode::Array {
+                            open,
+                            elements,
+                            close,
+                        });
+                    }
+                }
+            }
+            _ => Ok(SyntaxNode::Scalar(open)),
+        }
+    }
+
+    /// Reads either a comma, or the closing token when there is none.
+    fn separator(
+        &mut self,
+        closing: TokenType,
+    ) -> Result<(Option<SyntaxToken>, Option<SyntaxToken>), ParseError> {
+        let token = self.next()?;
+
+        if token.token.token_type == closing {
+            return Ok((None, Some(token)));
+        }
+
+        Ok((Some(token), None))
+    }
+
+    fn peek_is(&self, token_type: TokenType) -> bool {
+        self.tokens
+            .as_slice()
+            .first()
+            .map(|token| &token.token.token_type)
+            == Some(&token_type)
+    }
+
+    fn next(&mut self) -> Result<SyntaxToken, ParseError> {
+        self.tokens.next().ok_or_else(|| {
+            ParseError::new(
+                ErrorCode::UnexpectedEndOfInput,
+                "Unexpected end of input",
+                Span::default(),
+            )
+        })
+    }
+}
+
+/// The syntax for `value`, from its compact form.
+fn value_syntax(value: &Node) -> Result<SyntaxNode, EditError> {
+    let text = CompactPrint.print(value);
+
+    SyntaxTree::parse_with_limits(&text, Dialect::Json, trusted_limits())
+        .map(|tree| tree.root)
+        .map_err(|error| EditError::InvalidValue(error.to_string()))
+}
+
+/// Limits for text that did not come from untrusted input: no size caps,
+/// but the default nesting limit, since parsing recurses per level.
+fn trusted_limits() -> Limits {
+    Limits {
+        max_depth: Limits::default().max_depth,
+        ..Limits::unlimited()
+    }
+}
+
+fn replace(node: &mut SyntaxNode, mut replacement: SyntaxNode) {
+    replacement.first_token_mut().leading = node.first_token_mut().leading.clone();
+    *node = replacement;
+}
+
+/// Gives the current last entry a comma if it lacks one and returns the
+/// comma for a newly appended entry, which only gets one when the
+/// container already uses trailing commas.
+fn append_comma(last: Option<&mut Option<SyntaxToken>>) -> Option<SyntaxToken> {
+    match last {
+        Some(comma @ None) => {
+            *comma = Some(SyntaxToken::punctuation(TokenType::Comma, ","));
+            None
+        }
+        Some(Some(_)) => Some(SyntaxToken::punctuation(TokenType::Comma, ",")),
+        None => None,
+    }
+}
+
+/// Whitespace that starts the line of an entry, without any comments, so
+/// appended entries line up with their siblings.
+fn indentation(leading: &str) -> String {
+    let line = match leading.rfind('\n') {
+        Some(newline) => &leading[newline..],
+        None => leading,
+    };
+
+    line.chars()
+        .take_while(|character| character.is_whitespace())
+        .collect()
+}
+
+fn find_member(members: &[Member], key: &str) -> Option<usize> {
+    members
+        .iter()
+        .position(|member| member.key.token.literal.to_string() == key)
+}
+
+fn parse_index(segment: &str, len: usize) -> Result<usize, EditError> {
+    let index = segment
+        .parse::<usize>()
+        .map_err(|_| EditError::InvalidIndex(segment.to_string()))?;
+
+    if index >= len {
+        return Err(EditError::NotFound(segment.to_string()));
+    }
+
+    Ok(index)
+}
+
+#[cfg(test)]
+mod cst_tests {
+    use super::*;
+
+    const CONFIG: &str = "// package manifest\n{\n  \"name\":    \"jtool\", // keep\n  \"version\": 1.0e0,\n  \"tags\": [ 'a',\n            'b' ],\n}\n";
+
+    #[test]
+    fn round_trip() {
+        let tree = SyntaxTree::parse(CONFIG, Dialect::Json5).unwrap();
+
+        assert_eq!(tree.to_string(), CONFIG);
+        assert_eq!(
+            CompactPrint.print(&tree.to_node().unwrap()),
+            r#"{"name":"jtool","tags":["a","b"],"version":1}"#
+        );
+    }
+
+    #[test]
+    fn set_only_touches_value() {
+        let mut tree = SyntaxTree::parse(CONFIG, Dialect::Json5).unwrap();
+
+        tree.set(&["version"], &Node::Primary(Literal::Number(2.0)))
+            .unwrap();
+        tree.set(
+            &["tags", "-"],
+            &Node::Primary(Literal::String("c".to_string())),
+        )
+        .unwrap();
+        tree.set(
+            &["license"],
+            &Node::Primary(Literal::String("MIT".to_string())),
+        )
+        .unwrap();
+
+        assert_eq!(
+            tree.to_string(),
+            "// package manifest\n{\n  \"name\":    \"jtool\", // keep\n  \"version\": 2,\n  \"tags\": [ 'a',\n            'b',\n            \"c\" ],\n  \"license\": \"MIT\",\n}\n"
+        );
+        assert!(tree.to_node().is_ok());
+    }
+
+    #[test]
+    fn set_past_limits() {
+        let mut tree = SyntaxTree::parse("{\"a\": 1}", Dialect::Json).unwrap();
+        let long = "x".repeat(Limits::default().max_string_length + 1);
+
+        tree.set(&["a"], &Node::Primary(Literal::Number(1e300)))
+            .unwrap();
+        tree.set(&["b"], &Node::string(&long)).unwrap();
+
+        let node = tree.to_node().unwrap();
+        assert_eq!(
+            node.get_member("a"),
+            Some(&Node::Primary(Literal::Number(1e300)))
+        );
+        assert_eq!(node.get_member("b"), Some(&Node::string(&long)));
+
+        let deep = (0..200).fold(Node::Primary(Literal::Null), |node, _| {
+            Node::list(vec![node])
+        });
+        assert!(matches!(
+            tree.set(&["c"], &deep),
+            Err(EditError::InvalidValue(_))
+        ));
+        assert!(tree.get(&["c"]).is_err());
+    }
+
+    #[test]
+    fn lexical() {
+        let parse = |input: &str| SyntaxTree::parse(input, Dialect::Json5).unwrap();
+        let tree = parse("{\"a\": 1, \"b\": [true]}");
+
+        assert!(tree.lexical_eq(&parse("// comment\n{ \"a\":1,\n  \"b\": [ true ] }")));
+        assert!(!tree.lexical_eq(&parse("{\"a\": 1.0, \"b\": [true]}")));
+        assert!(!tree.lexical_eq(&parse("{\"b\": [true], \"a\": 1}")));
+        assert!(!tree.lexical_eq(&parse("{a: 1, \"b\": [true]}")));
+        assert!(!tree.lexical_eq(&parse("{\"a\": 1, \"b\": [true],}")));
+    }
+
+    #[test]
+    fn remove() {
+        let mut tree = SyntaxTree::parse("{\"a\": 1, \"b\": [1, 2]}", Dialect::Json).unwrap();
+
+        tree.remove(&["b", "1"]).unwrap();
+        assert_eq!(tree.to_string(), "{\"a\": 1, \"b\": [1]}");
+
+        tree.remove(&["b"]).unwrap();
+        assert_eq!(tree.to_string(), "{\"a\": 1}");
+
+        assert_eq!(
+            tree.remove(&["missing"]),
+            Err(EditError::NotFound("missing".to_string()))
+        );
+        assert_eq!(
+            tree.set(&["a", "b"], &Node::Primary(Literal::Null)),
+            Err(EditError::NotAContainer("b".to_string()))
+        );
+    }
+}
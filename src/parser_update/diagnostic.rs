@@ -0,0 +1,212 @@
+use std::fmt::Display;
+
+use super::error::{ErrorCode, ParseError, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found in a document, ready to be rendered against its source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: ErrorCode,
+    pub severity: Severity,
+    pub message: String,
+    /// Short text printed next to the caret underline.
+    pub label: Option<String>,
+    pub span: Span,
+    /// Suggested fix, printed below the snippet.
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: ErrorCode, message: &str, span: Span) -> Self {
+        Self {
+            code,
+            severity: Severity::Error,
+            message: message.to_string(),
+            label: None,
+            span,
+            hint: None,
+        }
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        Self {
+            hint: error.hint.clone(),
+            ..Diagnostic::error(error.code, &error.message, error.span)
+        }
+    }
+}
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders diagnostics as the offending source line with a caret underline:
+///
+/// ```text
+/// error[J0013]: Expected colon
+///  --> config.json:2:10
+///   |
+/// 2 |   "name" "jtool"
+///   |          ^^^^^^^ missing `:`
+///   = help: add `:` after the key
+/// ```
+///
+/// Plain mode is the default so output stays readable in CI logs.
+pub struct Renderer {
+    file_name: String,
+    color: bool,
+}
+
+impl Renderer {
+    pub fn new(file_name: &str) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            color: false,
+        }
+    }
+
+    /// Turns on ANSI colours for terminals.
+    pub fn color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
+
+    pub fn render(&self, source: &str, diagnostic: &Diagnostic) -> String {
+        let span = diagnostic.span;
+        let start = floor_char_boundary(source, span.start.min(source.len()));
+        let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |index| start + index);
+        let text = source[line_start..line_end].trim_end_matches('\r');
+
+        let end = floor_char_boundary(source, span.end.clamp(start, line_start + text.len()));
+        let width = source[start..end].chars().count().max(1);
+
+        // Keep tabs so the caret lines up however the terminal expands them.
+        let indent = source[line_start..start]
+            .chars()
+            .map(|character| if character == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        let number = span.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let accent = match diagnostic.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+        };
+
+        let mut underline = "^".repeat(width);
+        if let Some(label) = &diagnostic.label {
+            underline = format!("{} {}", underline, label);
+        }
+
+        let (accent, blue, bold, reset) = (
+            self.paint(accent),
+            self.paint(BLUE),
+            self.paint(BOLD),
+            self.paint(RESET),
+        );
+
+        let mut rendered = format!(
+            "{accent}{}[{}]{bold}: {}{reset}\n",
+            diagnostic.severity, diagnostic.code, diagnostic.message
+        );
+        rendered += &format!(
+            "{gutter}{blue}-->{reset} {}:{}:{}\n",
+            self.file_name, span.line, span.column
+        );
+        rendered += &format!("{gutter} {blue}|{reset}\n");
+        rendered += &format!("{number} {blue}|{reset} {text}\n");
+        rendered += &format!("{gutter} {blue}|{reset} {indent}{accent}{underline}{reset}\n");
+
+        if let Some(hint) = &diagnostic.hint {
+            rendered += &format!("{gutter} {blue}={reset} {bold}help:{reset} {hint}\n");
+        }
+
+        rendered
+    }
+
+    fn paint(&self, code: &'static str) -> &'static str {
+        if self.color {
+            code
+        } else {
+            ""
+        }
+    }
+}
+
+fn floor_char_boundary(source: &str, mut index: usize) -> usize {
+    while !source.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    index
+}
+
+#[cfg(test)]
+mod diagnostic_tests {
+    use super::*;
+    use crate::parser_update::nodes::parse_str;
+
+    #[test]
+    fn render_plain() {
+        let source = "{\n\t\"name\" \"jtool\"\n}";
+        let error = parse_str(source).unwrap_err();
+        let diagnostic = Diagnostic::from(&error).with_label("missing `:`");
+
+        assert_eq!(
+            Renderer::new("config.json").render(source, &diagnostic),
+            "error[J0013]: Expected colon\n --> config.json:2:9\n  |\n2 | \t\"name\" \"jtool\"\n  | \t       ^^^^^^^ missing `:`\n  = help: add `:` after the key\n"
+        );
+    }
+
+    #[test]
+    fn render_end_of_input() {
+        let source = "[1, 2";
+        let error = parse_str(source).unwrap_err();
+        let rendered = Renderer::new("a.json").render(source, &Diagnostic::from(&error));
+
+        assert!(
+            rendered.ends_with("1 | [1, 2\n  |      ^\n"),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn render_color() {
+        let diagnostic = Diagnostic::error(
+            ErrorCode::ExpectedValue,
+            "Unknown literal",
+            Span::new(0, 1, 1, 1),
+        );
+        let rendered = Renderer::new("a.json").color(true).render("}", &diagnostic);
+
+        assert!(rendered.starts_with("\x1b[1;31merror[J0011]\x1b[1m: Unknown literal\x1b[0m"));
+        assert!(rendered.ends_with("\x1b[1;31m^\x1b[0m\n"));
+    }
+}
@@ -0,0 +1,436 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use super::{nodes::Node, pointer::escape_token, printer::CompactPrint};
+
+/// One difference between two documents. Paths are JSON Pointers: a
+/// removal points into the old document, everything else into the new one.
+#[derive(Debug, Clone)]
+pub enum Change {
+    Added { path: String, value: Node },
+    Removed { path: String, value: Node },
+    Changed { path: String, old: Node, new: Node },
+}
+
+impl Change {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// Compares two [`Node`] trees by structure rather than by text.
+///
+/// Objects are compared by key, so member order never matters, and
+/// arrays by position unless set otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct Differ {
+    arrays_as_sets: bool,
+    id_field: Option<String>,
+}
+
+impl Differ {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compares arrays as multisets, so moving an element is not a change.
+    pub fn arrays_as_sets(mut self, enabled: bool) -> Self {
+        self.arrays_as_sets = enabled;
+        self
+    }
+
+    /// Pairs up array elements that are objects with the same `field`
+    /// value and diffs them member by member. Elements without the field
+    /// are compared as a set.
+    pub fn match_by(mut self, field: &str) -> Self {
+        self.id_field = Some(field.to_string());
+        self
+    }
+
+    pub fn diff(&self, old: &Node, new: &Node) -> Vec<Change> {
+        let mut changes = vec![];
+        self.diff_into(old, new, "", &mut changes);
+        changes
+    }
+
+    fn diff_into(&self, old: &Node, new: &Node, path: &str, changes: &mut Vec<Change>) {
+        match (old, new) {
+            (Node::Object(..), Node::Object(..)) => {
+                let old_members = old.member_map();
+                let new_members = new.member_map();
+
+                for (key, value) in &old_members {
+                    if !new_members.contains_key(key) {
+                        changes.push(Change::Removed {
+                            path: child_path(path, key),
+                            value: (*value).clone(),
+                        });
+                    }
+                }
+
+                for (key, value) in &new_members {
+                    let path = child_path(path, key);
+
+                    match old_members.get(key) {
+                        Some(current) => self.diff_into(current, value, &path, changes),
+                        None => changes.push(Change::Added {
+                            path,
+                            value: (*value).clone(),
+                        }),
+                    }
+                }
+            }
+            (Node::List(_, old, _), Node::List(_, new, _)) => {
+                if self.unordered() {
+                    self.diff_unordered(old, new, path, changes);
+                } else {
+                    self.diff_positional(old, new, path, changes);
+                }
+            }
+            (Node::Primary(left), Node::Primary(right)) if left == right => {}
+            _ => changes.push(Change::Changed {
+                path: path.to_string(),
+                old: old.clone(),
+                new: new.clone(),
+            }),
+        }
+    }
+
+    fn diff_positional(&self, old: &[Node], new: &[Node], path: &str, changes: &mut Vec<Change>) {
+        for index in 0..old.len().max(new.len()) {
+            let path = format!("{}/{}", path, index);
+
+            match (old.get(index), new.get(index)) {
+                (Some(old), Some(new)) => self.diff_into(old, new, &path, changes),
+                (Some(old), None) => changes.push(Change::Removed {
+                    path,
+                    value: old.clone(),
+                }),
+                (None, Some(new)) => changes.push(Change::Added {
+                    path,
+                    value: new.clone(),
+                }),
+                (None, None) => {}
+            }
+        }
+    }
+
+    fn diff_unordered(&self, old: &[Node], new: &[Node], path: &str, changes: &mut Vec<Change>) {
+        let pairs = self.pair(old, new);
+        let mut matched = vec![false; new.len()];
+
+        for (index, (element, pair)) in old.iter().zip(pairs).enumerate() {
+            match pair {
+                Some(other) => {
+                    matched[other] = true;
+
+                    let path = format!("{}/{}", path, other);
+                    self.diff_into(element, &new[other], &path, changes);
+                }
+                None => changes.push(Change::Removed {
+                    path: format!("{}/{}", path, index),
+                    value: element.clone(),
+                }),
+            }
+        }
+
+        for (index, element) in new.iter().enumerate() {
+            if !matched[index] {
+                changes.push(Change::Added {
+                    path: format!("{}/{}", path, index),
+                    value: element.clone(),
+                });
+            }
+        }
+    }
+
+    /// The element of `new` each element of `old` pairs up with: the first
+    /// one left with an equal id, or that is equal itself when neither has
+    /// an id. Candidates are bucketed by hash so only likely matches are
+    /// compared.
+    fn pair(&self, old: &[Node], new: &[Node]) -> Vec<Option<usize>> {
+        let mut buckets = HashMap::<_, Vec<usize>>::new();
+
+        for (index, element) in new.iter().enumerate() {
+            buckets.entry(self.bucket(element)).or_default().push(index);
+        }
+
+        old.iter()
+            .map(|element| {
+                let bucket = buckets.get_mut(&self.bucket(element))?;
+                let found = bucket.iter().position(|&other| {
+                    match (self.id(element), self.id(&new[other])) {
+                        (Some(key), Some(other_key)) => self.equal(key, other_key),
+                        (None, None) => self.equal(element, &new[other]),
+                        _ => false,
+                    }
+                })?;
+
+                Some(bucket.remove(found))
+            })
+            .collect()
+    }
+
+    fn bucket(&self, element: &Node) -> (bool, u64) {
+        match self.id(element) {
+            Some(key) => (true, self.hash(key)),
+            None => (false, self.hash(element)),
+        }
+    }
+
+    fn id<'a>(&self, node: &'a Node) -> Option<&'a Node> {
+        match (node, &self.id_field) {
+            (Node::Object(..), Some(field)) => node.get_member(field),
+            _ => None,
+        }
+    }
+
+    /// Whether diffing `old` and `new` would find no changes.
+    fn equal(&self, old: &Node, new: &Node) -> bool {
+        match (old, new) {
+            (Node::Object(..), Node::Object(..)) => {
+                let new_members = new.member_map();
+                let old_members = old.member_map();
+
+                old_members.len() == new_members.len()
+                    && old_members.iter().all(|(key, value)| {
+                        new_members
+                            .get(key)
+                            .is_some_and(|other| self.equal(value, other))
+                    })
+            }
+            (Node::List(_, old, _), Node::List(_, new, _)) if self.unordered() => {
+                old.len() == new.len()
+                    && self
+                        .pair(old, new)
+                        .into_iter()
+                        .zip(old)
+                        .all(|(pair, element)| {
+                            pair.is_some_and(|other| self.equal(element, &new[other]))
+                        })
+            }
+            (Node::List(_, old, _), Node::List(_, new, _)) => {
+                old.len() == new.len() && old.iter().zip(new).all(|(old, new)| self.equal(old, new))
+            }
+            (Node::Primary(left), Node::Primary(right)) => left == right,
+            _ => false,
+        }
+    }
+
+    /// A hash that agrees with [`Differ::equal`], so arrays compared as
+    /// sets hash the same in any order.
+    fn hash(&self, node: &Node) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        std::mem::discriminant(node).hash(&mut hasher);
+
+        match node {
+            Node::Primary(literal) => literal.hash(&mut hasher),
+            Node::Object(..) => {
+                for (key, value) in node.member_map() {
+                    key.hash(&mut hasher);
+                    self.hash(value).hash(&mut hasher);
+                }
+            }
+            Node::List(_, nodes, _) if self.unordered() => {
+                nodes
+                    .iter()
+                    .fold(0u64, |sum, node| sum.wrapping_add(self.hash(node)))
+                    .hash(&mut hasher);
+            }
+            Node::List(_, nodes, _) => {
+                for node in nodes {
+                    self.hash(node).hash(&mut hasher);
+                }
+            }
+            Node::Property(..) | Node::Error(_) => {}
+        }
+
+        hasher.finish()
+    }
+
+    fn unordered(&self) -> bool {
+        self.arrays_as_sets || self.id_field.is_some()
+    }
+}
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// One line per change, `+` added, `-` removed and `~` changed:
+///
+/// ```text
+/// ~ /name: "jtool" -> "jtool-cli"
+/// + /tags/1: "json"
+/// ```
+pub fn render(changes: &[Change], color: bool) -> String {
+    let paint = |code: &'static str| if color { code } else { "" };
+    let reset = paint(RESET);
+
+    changes
+        .iter()
+        .map(|change| {
+            let path = match change.path() {
+                "" => "(root)",
+                path => path,
+            };
+
+            match change {
+                Change::Added { value, .. } => format!(
+                    "{}+ {}: {}{}\n",
+                    paint(GREEN),
+                    path,
+                    CompactPrint.print(value),
+                    reset
+                ),
+                Change::Removed { value, .. } => format!(
+                    "{}- {}: {}{}\n",
+                    paint(RED),
+                    path,
+                    CompactPrint.print(value),
+                    reset
+                ),
+                Change::Changed { old, new, .. } => format!(
+                    "{}~ {}: {} -> {}{}\n",
+                    paint(YELLOW),
+                    path,
+                    CompactPrint.print(old),
+                    CompactPrint.print(new),
+                    reset
+                ),
+            }
+        })
+        .collect()
+}
+
+/// `[{"kind", "path", "value"?, "old"?, "new"?}]` with `kind` one of
+/// `added`, `removed` and `changed`.
+pub fn to_json(changes: &[Change]) -> Node {
+    Node::list(
+        changes
+            .iter()
+            .map(|change| {
+                let (kind, values) = match change {
+                    Change::Added { value, .. } => ("added", vec![("value", value)]),
+                    Change::Removed { value, .. } => ("removed", vec![("value", value)]),
+                    Change::Changed { old, new, .. } => {
+                        ("changed", vec![("new", new), ("old", old)])
+                    }
+                };
+
+                let mut properties = vec![Node::property("kind", Node::string(kind))];
+
+                for (name, value) in values {
+                    properties.push(Node::property(name, value.clone()));
+                }
+
+                properties.push(Node::property("path", Node::string(change.path())));
+                properties.sort_by_key(|property| match property {
+                    Node::Property(key, _, _) => key.literal.to_string(),
+                    _ => String::new(),
+                });
+
+                Node::object(properties)
+            })
+            .collect(),
+    )
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    format!("{}/{}", path, escape_token(key))
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::*;
+    use crate::parser_update::nodes::{parse_str, Literal};
+
+    fn lines(differ: &Differ, old: &str, new: &str) -> String {
+        render(
+            &differ.diff(&parse_str(old).unwrap(), &parse_str(new).unwrap()),
+            false,
+        )
+    }
+
+    #[test]
+    fn positional() {
+        assert_eq!(
+            lines(
+                &Differ::new(),
+                r#"{"name": "jtool", "old": 1, "tags": ["a", "b", "c"], "n": {"x": [1]}}"#,
+                r#"{"name": "jtool-cli", "tags": ["a", "c"], "n": {"x": {}}, "new/key": null}"#,
+            ),
+            "- /old: 1\n~ /n/x: [1] -> {}\n~ /name: \"jtool\" -> \"jtool-cli\"\n+ /new~1key: null\n~ /tags/1: \"b\" -> \"c\"\n- /tags/2: \"c\"\n"
+        );
+        assert_eq!(lines(&Differ::new(), "[1, {}]", "[1, {}]"), "");
+        assert_eq!(
+            lines(&Differ::new(), "1", "\"1\""),
+            "~ (root): 1 -> \"1\"\n"
+        );
+    }
+
+    #[test]
+    fn key_order() {
+        let old = Node::object(vec![
+            Node::property("a", Node::Primary(Literal::Null)),
+            Node::property("b", Node::Primary(Literal::Null)),
+        ]);
+        let new = Node::object(vec![
+            Node::property("b", Node::Primary(Literal::Null)),
+            Node::property("a", Node::Primary(Literal::Null)),
+        ]);
+
+        assert!(Differ::new().diff(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn unordered_arrays() {
+        let sets = Differ::new().arrays_as_sets(true);
+        assert_eq!(
+            lines(&sets, "[1, 2, 2, 3]", "[3, 2, 1, 4]"),
+            "- /2: 2\n+ /3: 4\n"
+        );
+        assert_eq!(
+            lines(
+                &sets,
+                r#"[[1, 2], {"a": [3, 4]}]"#,
+                r#"[{"a": [4, 3]}, [2, 1]]"#
+            ),
+            ""
+        );
+
+        let by_id = Differ::new().match_by("id");
+        assert_eq!(
+            lines(
+                &by_id,
+                r#"[{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, "loose"]"#,
+                r#"["loose", {"id": 2, "v": "c"}, {"id": 3}]"#,
+            ),
+            "- /0: {\"id\":1,\"v\":\"a\"}\n~ /1/v: \"b\" -> \"c\"\n+ /2: {\"id\":3}\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let changes = Differ::new().diff(
+            &parse_str(r#"{"a": 1, "b": 2}"#).unwrap(),
+            &parse_str(r#"{"a": 3}"#).unwrap(),
+        );
+
+        assert_eq!(
+            CompactPrint.print(&to_json(&changes)),
+            r#"[{"kind":"removed","path":"/b","value":2},{"kind":"changed","new":3,"old":1,"path":"/a"}]"#
+        );
+        assert_eq!(
+            render(&changes, true),
+            "\x1b[31m- /b: 2\x1b[0m\n\x1b[33m~ /a: 1 -> 3\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::hash::{Hash, Hasher};
+
+use super::nodes::{Literal, Node};
+
+/// Numbers compare by value, so `0` equals `-0`. `NaN` equals itself to
+/// keep `Eq` reflexive.
+impl PartialEq for Literal {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Literal::String(left), Literal::String(right)) => left == right,
+            (Literal::Number(left), Literal::Number(right)) => {
+                left == right || (left.is_nan() && right.is_nan())
+            }
+            (Literal::Bool(left), Literal::Bool(right)) => left == right,
+            (Literal::Null, Literal::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Literal {}
+
+impl Hash for Literal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(literal_hash(self));
+    }
+}
+
+/// Semantic equality: objects are equal when they have the same members in
+/// any order, arrays when their elements are equal in order, and numbers
+/// when their values are. Spans and token spelling are ignored. See
+/// [`Node::strict_eq`] for a stricter comparison.
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Node::Primary(left), Node::Primary(right)) => left == right,
+            (Node::Object(_, left, _), Node::Object(_, right, _)) => {
+                left.len() == right.len()
+                    && sorted_members(left)
+                        .into_iter()
+                        .zip(sorted_members(right))
+                        .all(|(left, right)| left == right)
+            }
+            (Node::Property(key, _, value), Node::Property(other_key, _, other_value)) => {
+                key.literal == other_key.literal && value == other_value
+            }
+            (Node::List(_, left, _), Node::List(_, right, _)) => left == right,
+            (Node::Error(_), Node::Error(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Node {}
+
+impl Hash for Node {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.stable_hash());
+    }
+}
+
+impl Node {
+    /// Equality that also requires object members in the same order and
+    /// numbers with the same bits, so `0` and `-0` differ.
+    ///
+    /// This is not a comparison of source text: `Node` keeps neither the
+    /// spelling of numbers and strings nor the member order of a parsed
+    /// object, so `1` and `1.0`, `"\u0041"` and `"A"`, and `{"b":1,"a":2}`
+    /// and `{"a":2,"b":1}` are all strictly equal once parsed. Use
+    /// [`SyntaxTree::lexical_eq`](super::cst::SyntaxTree::lexical_eq) to
+    /// compare the source text.
+    pub fn strict_eq(&self, other: &Node) -> bool {
+        match (self, other) {
+            (Node::Primary(Literal::Number(left)), Node::Primary(Literal::Number(right))) => {
+                left.to_bits() == right.to_bits()
+            }
+            (Node::Primary(left), Node::Primary(right)) => left == right,
+            (Node::Object(_, left, _), Node::Object(_, right, _))
+            | (Node::List(_, left, _), Node::List(_, right, _)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right)
+                        .all(|(left, right)| left.strict_eq(right))
+            }
+            (Node::Property(key, _, value), Node::Property(other_key, _, other_value)) => {
+                key.literal == other_key.literal && value.strict_eq(other_value)
+            }
+            (Node::Error(_), Node::Error(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// A 64-bit FNV-1a hash that agrees with `==`: equal nodes hash the
+    /// same. It does not depend on the platform or Rust version, so it can
+    /// be stored.
+    pub fn stable_hash(&self) -> u64 {
+        match self {
+            Node::Primary(literal) => literal_hash(literal),
+            Node::Object(_, properties, _) => {
+                // Adding member hashes makes the result independent of order.
+                let members = properties.iter().fold(0u64, |sum, property| {
+                    sum.wrapping_add(property.stable_hash())
+                });
+
+                Fnv::new(5)
+                    .write(&(properties.len() as u64).to_le_bytes())
+                    .write(&members.to_le_bytes())
+                    .finish()
+            }
+            Node::Property(key, _, value) => Fnv::new(6)
+                .write(&literal_hash(&key.literal).to_le_bytes())
+                .write(&value.stable_hash().to_le_bytes())
+                .finish(),
+            Node::List(_, nodes, _) => nodes
+                .iter()
+                .fold(
+                    Fnv::new(4).write(&(nodes.len() as u64).to_le_bytes()),
+                    |fnv, node| fnv.write(&node.stable_hash().to_le_bytes()),
+                )
+                .finish(),
+            Node::Error(_) => Fnv::new(7).finish(),
+        }
+    }
+}
+
+/// Object members in an order that does not depend on the source: by key,
+/// then by hash so that duplicate keys line up too.
+fn sorted_members(members: &[Node]) -> Vec<&Node> {
+    let mut members = members.iter().collect::<Vec<_>>();
+    members.sort_by_cached_key(|member| {
+        let key = match member {
+            Node::Property(key, _, _) => Some(key.literal.to_string()),
+            _ => None,
+        };
+
+        (key, member.stable_hash())
+    });
+
+    members
+}
+
+fn literal_hash(literal: &Literal) -> u64 {
+    match literal {
+        Literal::Null => Fnv::new(0).finish(),
+        Literal::Bool(value) => Fnv::new(1).write(&[*value as u8]).finish(),
+        Literal::Number(value) => {
+            // Values that compare equal must hash the same.
+            let value = if *value == 0.0 {
+                0.0
+            } else if value.is_nan() {
+                f64::NAN
+            } else {
+                *value
+            };
+
+            Fnv::new(2).write(&value.to_bits().to_le_bytes()).finish()
+        }
+        Literal::String(value) => Fnv::new(3)
+            .write(&(value.len() as u64).to_le_bytes())
+            .write(value.as_bytes())
+            .finish(),
+    }
+}
+
+struct Fnv(u64);
+
+impl Fnv {
+    /// Starts a hash with a tag byte that keeps different kinds of value
+    /// apart.
+    fn new(tag: u8) -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325).write(&[tag])
+    }
+
+    fn write(mut self, bytes: &[u8]) -> Self {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        self
+    }
+
+    fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod equality_tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::parser_update::{
+        lexer::Dialect,
+        nodes::{parse_str, parse_str_with},
+    };
+
+    #[test]
+    fn semantic() {
+        let parse = |input: &str| parse_str_with(input, Dialect::Json5).unwrap().0;
+
+        assert_eq!(
+            parse_str(r#"{"a": [1, {"b": null}], "c": "x"}"#).unwrap(),
+            parse("{c: 'x', a: [1.0, {b: null},],}")
+        );
+        assert_eq!(parse("-0"), parse("0"));
+        assert_ne!(parse("[1, 2]"), parse("[2, 1]"));
+        assert_ne!(parse(r#"{"a": 1}"#), parse(r#"{"a": 1, "b": 1}"#));
+        assert_ne!(parse("1"), parse("\"1\""));
+
+        let duplicated = |first: f64, second: f64| {
+            Node::object(vec![
+                Node::property("a", Node::Primary(Literal::Number(first))),
+                Node::property("a", Node::Primary(Literal::Number(second))),
+            ])
+        };
+        assert_eq!(duplicated(1.0, 2.0), duplicated(2.0, 1.0));
+        assert_ne!(duplicated(1.0, 1.0), duplicated(1.0, 2.0));
+        assert_ne!(duplicated(1.0, 2.0), duplicated(1.0, 1.0));
+
+        let built = Node::object(vec![
+            Node::property("b", Node::Primary(Literal::Bool(true))),
+            Node::property("a", Node::list(vec![])),
+        ]);
+        assert_eq!(built, parse_str(r#"{"a": [], "b": true}"#).unwrap());
+    }
+
+    #[test]
+    fn strict() {
+        let ordered = parse_str(r#"{"a": 1, "b": 2}"#).unwrap();
+        let reversed = Node::object(vec![
+            Node::property("b", Node::Primary(Literal::Number(2.0))),
+            Node::property("a", Node::Primary(Literal::Number(1.0))),
+        ]);
+
+        assert_eq!(ordered, reversed);
+        assert!(!ordered.strict_eq(&reversed));
+        assert!(!parse_str("-0").unwrap().strict_eq(&parse_str("0").unwrap()));
+
+        // Spelling is gone after parsing.
+        assert!(ordered.strict_eq(&parse_str(r#"{"b": 2, "a": 1.0}"#).unwrap()));
+        assert!(parse_str(r#""\u0041""#)
+            .unwrap()
+            .strict_eq(&parse_str(r#""A""#).unwrap()));
+    }
+
+    #[test]
+    fn hash() {
+        let documents = [
+            r#"{"id": 1, "tags": ["x"]}"#,
+            r#"{"tags": ["x"], "id": 1.0}"#,
+            r#"{"id": 1, "tags": ["y"]}"#,
+            "[1, 2]",
+            "[2, 1]",
+            "0",
+            "-0",
+        ];
+
+        let unique = documents
+            .iter()
+            .map(|document| parse_str(document).unwrap())
+            .collect::<HashSet<_>>();
+        assert_eq!(unique.len(), 5);
+
+        assert_eq!(
+            parse_str("[true, null]").unwrap().stable_hash(),
+            0x0a46_b98f_f881_e405
+        );
+    }
+}
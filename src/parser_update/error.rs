@@ -0,0 +1,151 @@
+use std::fmt::Display;
+
+/// Location of a token in the source text.
+///
+/// `start` and `end` are byte offsets, `line` and `column` are 1-based and
+/// point at `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+}
+
+/// Stable identifier for each kind of error, printed as `J0001` and so on.
+///
+/// Codes are never renumbered or reused, new kinds get the next number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorCode {
+    UnexpectedCharacter,
+    UnterminatedString,
+    InvalidEscape,
+    InvalidUnicodeEscape,
+    ControlCharacter,
+    InvalidUtf8,
+    InvalidNumber,
+    UnexpectedIdentifier,
+    UnterminatedComment,
+    UnexpectedEndOfInput,
+    ExpectedValue,
+    ExpectedKey,
+    ExpectedColon,
+    UnclosedObject,
+    UnclosedArray,
+    TrailingContent,
+    DuplicateKey,
+    Io,
+    LimitExceeded,
+    SchemaViolation,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 20] = [
+        ErrorCode::UnexpectedCharacter,
+        ErrorCode::UnterminatedString,
+        ErrorCode::InvalidEscape,
+        ErrorCode::InvalidUnicodeEscape,
+        ErrorCode::ControlCharacter,
+        ErrorCode::InvalidUtf8,
+        ErrorCode::InvalidNumber,
+        ErrorCode::UnexpectedIdentifier,
+        ErrorCode::UnterminatedComment,
+        ErrorCode::UnexpectedEndOfInput,
+        ErrorCode::ExpectedValue,
+        ErrorCode::ExpectedKey,
+        ErrorCode::ExpectedColon,
+        ErrorCode::UnclosedObject,
+        ErrorCode::UnclosedArray,
+        ErrorCode::TrailingContent,
+        ErrorCode::DuplicateKey,
+        ErrorCode::Io,
+        ErrorCode::LimitExceeded,
+        ErrorCode::SchemaViolation,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedCharacter => "J0001",
+            ErrorCode::UnterminatedString => "J0002",
+            ErrorCode::InvalidEscape => "J0003",
+            ErrorCode::InvalidUnicodeEscape => "J0004",
+            ErrorCode::ControlCharacter => "J0005",
+            ErrorCode::InvalidUtf8 => "J0006",
+            ErrorCode::InvalidNumber => "J0007",
+            ErrorCode::UnexpectedIdentifier => "J0008",
+            ErrorCode::UnterminatedComment => "J0009",
+            ErrorCode::UnexpectedEndOfInput => "J0010",
+            ErrorCode::ExpectedValue => "J0011",
+            ErrorCode::ExpectedKey => "J0012",
+            ErrorCode::ExpectedColon => "J0013",
+            ErrorCode::UnclosedObject => "J0014",
+            ErrorCode::UnclosedArray => "J0015",
+            ErrorCode::TrailingContent => "J0016",
+            ErrorCode::DuplicateKey => "J0017",
+            ErrorCode::Io => "J0018",
+            ErrorCode::LimitExceeded => "J0019",
+            ErrorCode::SchemaViolation => "J0020",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<ErrorCode> {
+        ErrorCode::ALL
+            .into_iter()
+            .find(|error_code| error_code.as_str().eq_ignore_ascii_case(code))
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Error shared by the lexer, the tree parser and the event reader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub span: Span,
+    /// Suggested fix for a common mistake, when one was recognised.
+    pub hint: Option<String>,
+}
+
+impl ParseError {
+    pub fn new(code: ErrorCode, message: &str, span: Span) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            span,
+            hint: None,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.span.line, self.span.column
+        )
+    }
+}
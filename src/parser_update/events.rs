@@ -0,0 +1,469 @@
+use std::{collections::BTreeMap, io::Read};
+
+use super::{
+    error::{ErrorCode, ParseError, Span},
+    lexer::{Comment, Dialect, Lexer},
+    limits::Limits,
+    nodes::{Literal, Node, Token, TokenType},
+};
+
+/// A single step of a document, as produced by [`EventReader`].
+#[derive(Debug, Clone)]
+pub enum Event {
+    StartObject,
+    Key(String),
+    EndObject,
+    StartArray,
+    EndArray,
+    Value(Literal),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Value,
+    ValueOrEnd,
+    Key,
+    KeyOrEnd,
+    CommaOrEnd,
+    Done,
+}
+
+/// Pull parser that yields [`Event`]s straight from a reader.
+///
+/// Only the stack of open containers is kept, so memory use does not grow
+/// with the size of the input. Tokens come from the same [`Lexer`] as the
+/// tree parser and errors are the same [`ParseError`].
+pub struct EventReader<R: Read> {
+    lexer: Lexer<R>,
+    dialect: Dialect,
+    limits: Limits,
+    stack: Vec<Container>,
+    state: State,
+    span: Span,
+    multiple_values: bool,
+    finished: bool,
+}
+
+impl<R: Read> EventReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_dialect(reader, Dialect::Json)
+    }
+
+    pub fn with_dialect(reader: R, dialect: Dialect) -> Self {
+        Self {
+            lexer: Lexer::with_dialect(reader, dialect).limits(Limits::streaming()),
+            dialect,
+            limits: Limits::streaming(),
+            stack: vec![],
+            state: State::Value,
+            span: Span::default(),
+            multiple_values: false,
+            finished: false,
+        }
+    }
+
+    /// Accepts any number of root values one after another, as in
+    /// concatenated JSON streams.
+    pub fn multiple_values(mut self, enabled: bool) -> Self {
+        self.multiple_values = enabled;
+        self
+    }
+
+    /// Nothing is collected while streaming, so only the depth limit and
+    /// the lexer's limits apply. The default is [`Limits::streaming`],
+    /// which does not cap the input size. A cap set here counts the whole
+    /// stream, across values with [`EventReader::multiple_values`].
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.lexer = self.lexer.limits(limits);
+        self.limits = limits;
+        self
+    }
+
+    /// Hands over the comments read so far. Drain these regularly to keep
+    /// memory constant on large commented inputs.
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        self.lexer.take_comments()
+    }
+
+    /// Span of the token behind the most recent event.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Current nesting depth, `0` outside of any container.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Returns the next event, or `None` once the root value is complete
+    /// and the input is exhausted.
+    pub fn next_event(&mut self) -> Result<Option<Event>, ParseError> {
+        loop {
+            let token = self.lexer.next_token()?;
+            self.span = token.span;
+
+            match self.state {
+                State::Done => {
+                    return match token.token_type {
+                        TokenType::Eof => Ok(None),
+                        _ if self.multiple_values => self.value(token).map(Some),
+                        _ => Err(ParseError::new(
+                            ErrorCode::TrailingContent,
+                            "Expected end of input",
+                            token.span,
+                        )),
+                    }
+                }
+                // With multiple values, input holding none at all is an
+                // empty stream rather than a missing value.
+                State::Value
+                    if self.multiple_values
+                        && self.stack.is_empty()
+                        && token.token_type == TokenType::Eof =>
+                {
+                    return Ok(None)
+                }
+                State::Value => return self.value(token).map(Some),
+                State::ValueOrEnd => {
+                    if token.token_type == TokenType::RightBracket {
+                        return Ok(Some(self.close(Event::EndArray)));
+                    }
+
+                    return self.value(token).map(Some);
+                }
+                State::Key => return self.key(token).map(Some),
+                State::KeyOrEnd => {
+                    if token.token_type == TokenType::RightBrace {
+                        return Ok(Some(self.close(Event::EndObject)));
+                    }
+
+                    return self.key(token).map(Some);
+                }
+                State::CommaOrEnd => match (self.stack.last(), &token.token_type) {
+                    (Some(Container::Object), TokenType::Comma) => {
+                        self.state = if self.dialect.allows_trailing_commas() {
+                            State::KeyOrEnd
+                        } else {
+                            State::Key
+                        }
+                    }
+                    (Some(Container::Object), TokenType::RightBrace) => {
+                        return Ok(Some(self.close(Event::EndObject)))
+                    }
+                    (Some(Container::Object), _) => {
+                        return Err(ParseError::new(
+                            ErrorCode::UnclosedObject,
+                            "Expected }",
+                            token.span,
+                        ))
+                    }
+                    (_, TokenType::Comma) => {
+                        self.state = if self.dialect.allows_trailing_commas() {
+                            State::ValueOrEnd
+                        } else {
+                            State::Value
+                        }
+                    }
+                    (_, TokenType::RightBracket) => return Ok(Some(self.close(Event::EndArray))),
+                    (_, _) => {
+                        return Err(ParseError::new(
+                            ErrorCode::UnclosedArray,
+                            "Expected ]",
+                            token.span,
+                        ))
+                    }
+                },
+            }
+        }
+    }
+
+    /// Reads the next complete value into a [`Node`], following the same
+    /// rules as the tree parser: keys are sorted and duplicates rejected.
+    pub fn read_node(&mut self) -> Result<Option<Node>, ParseError> {
+        match self.next_event()? {
+            Some(event) => self.build(event).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn build(&mut self, event: Event) -> Result<Node, ParseError> {
+        match event {
+            Event::StartObject => {
+                let mut properties = BTreeMap::new();
+
+                while let Event::Key(key) = self.required_event()? {
+                    let span = self.span;
+                    let event = self.required_event()?;
+                    let value = self.build(event)?;
+
+                    if properties.contains_key(&key) {
+                        return Err(ParseError::new(
+                            ErrorCode::DuplicateKey,
+                            &format!("Duplicate property key {} found", key),
+                            span,
+                        ));
+                    }
+
+                    properties.insert(key.clone(), Node::property(&key, value));
+                }
+
+                Ok(Node::object(properties.into_values().collect()))
+            }
+            Event::StartArray => {
+                let mut nodes = vec![];
+
+                loop {
+                    match self.required_event()? {
+                        Event::EndArray => break,
+                        event => nodes.push(self.build(event)?),
+                    }
+                }
+
+                Ok(Node::list(nodes))
+            }
+            Event::Value(literal) => Ok(Node::Primary(literal)),
+            _ => Err(ParseError::new(
+                ErrorCode::ExpectedValue,
+                "Unknown literal",
+                self.span,
+            )),
+        }
+    }
+
+    fn required_event(&mut self) -> Result<Event, ParseError> {
+        self.next_event()?.ok_or_else(|| {
+            ParseError::new(
+                ErrorCode::UnexpectedEndOfInput,
+                "Unexpected end of input",
+                self.span,
+            )
+        })
+    }
+
+    fn value(&mut self, token: Token) -> Result<Event, ParseError> {
+        if matches!(
+            token.token_type,
+            TokenType::LeftBrace | TokenType::LeftBracket
+        ) && self.stack.len() >= self.limits.max_depth
+        {
+            return Err(ParseError::new(
+                ErrorCode::LimitExceeded,
+                &format!("Nesting deeper than {} levels", self.limits.max_depth),
+                token.span,
+            ));
+        }
+
+        match token.token_type {
+            TokenType::LeftBrace => {
+                self.stack.push(Container::Object);
+                self.state = State::KeyOrEnd;
+                Ok(Event::StartObject)
+            }
+            TokenType::LeftBracket => {
+                self.stack.push(Container::Array);
+                self.state = State::ValueOrEnd;
+                Ok(Event::StartArray)
+            }
+            TokenType::String
+            | TokenType::Number
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Null => {
+                self.after_value();
+                Ok(Event::Value(token.literal))
+            }
+            _ => match token.named_number() {
+                Some(literal) => {
+                    self.after_value();
+                    Ok(Event::Value(literal))
+                }
+                None => Err(ParseError::new(
+                    ErrorCode::ExpectedValue,
+                    "Unknown literal",
+                    token.span,
+                )),
+            },
+        }
+    }
+
+    fn key(&mut self, token: Token) -> Result<Event, ParseError> {
+        let Some(token) = token.key(self.dialect) else {
+            return Err(ParseError::new(
+                ErrorCode::ExpectedKey,
+                "Expected identifier",
+                token.span,
+            ));
+        };
+
+        let colon = self.lexer.next_token()?;
+
+        if colon.token_type != TokenType::Colon {
+            return Err(ParseError::new(
+                ErrorCode::ExpectedColon,
+                "Expected colon",
+                colon.span,
+            ));
+        }
+
+        self.state = State::Value;
+
+        Ok(Event::Key(token.literal.to_string()))
+    }
+
+    fn close(&mut self, event: Event) -> Event {
+        self.stack.pop();
+        self.after_value();
+        event
+    }
+
+    fn after_value(&mut self) {
+        self.state = if self.stack.is_empty() {
+            State::Done
+        } else {
+            State::CommaOrEnd
+        };
+    }
+}
+
+impl<R: Read> Iterator for EventReader<R> {
+    type Item = Result<Event, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let event = self.next_event().transpose();
+
+        if !matches!(event, Some(Ok(_))) {
+            self.finished = true;
+        }
+
+        event
+    }
+}
+
+#[cfg(test)]
+mod event_tests {
+    use super::*;
+    use crate::parser_update::printer::CompactPrint;
+
+    fn events(input: &str) -> Result<Vec<Event>, ParseError> {
+        EventReader::new(input.as_bytes()).collect()
+    }
+
+    #[test]
+    fn read_events() {
+        let events = events(r#"{"name": "jtool", "tags": [1, true, null], "empty": {}}"#).unwrap();
+
+        assert_eq!(
+            format!("{:?}", events),
+            "[StartObject, Key(\"name\"), Value(String(\"jtool\")), Key(\"tags\"), StartArray, \
+             Value(Number(1.0)), Value(Bool(true)), Value(Null), EndArray, Key(\"empty\"), \
+             StartObject, EndObject, EndObject]"
+        );
+    }
+
+    #[test]
+    fn read_scalar_root() {
+        assert_eq!(
+            format!("{:?}", events("42").unwrap()),
+            "[Value(Number(42.0))]"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(events("[1 2]").unwrap_err().message, "Expected ]");
+        assert_eq!(events(r#"{"a" 1}"#).unwrap_err().message, "Expected colon");
+        assert_eq!(events("[1,]").unwrap_err().message, "Unknown literal");
+        assert_eq!(
+            events("[1] [2]").unwrap_err().message,
+            "Expected end of input"
+        );
+    }
+
+    #[test]
+    fn read_jsonc() {
+        let mut reader = EventReader::with_dialect(
+            "{\"a\": [1, 2,], // two\n \"b\": {},}".as_bytes(),
+            Dialect::Jsonc,
+        );
+        let node = reader.read_node().unwrap().unwrap();
+
+        assert_eq!(CompactPrint.print(&node), r#"{"a":[1,2],"b":{}}"#);
+        assert_eq!(reader.take_comments()[0].text, " two");
+        assert!(reader.take_comments().is_empty());
+    }
+
+    #[test]
+    fn read_json5_keyword_keys() {
+        let events = EventReader::with_dialect(
+            "{null: true, Infinity: Infinity}".as_bytes(),
+            Dialect::Json5,
+        )
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+        assert_eq!(
+            format!("{:?}", events),
+            "[StartObject, Key(\"null\"), Value(Bool(true)), Key(\"Infinity\"), \
+             Value(Number(inf)), EndObject]"
+        );
+
+        let error = EventReader::new("{null: 1}".as_bytes())
+            .read_node()
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::ExpectedKey);
+    }
+
+    #[test]
+    fn read_nodes() {
+        let mut reader =
+            EventReader::new(r#"{"b": [1, {}], "a": null} "x" 3"#.as_bytes()).multiple_values(true);
+        let mut nodes = vec![];
+
+        while let Some(node) = reader.read_node().unwrap() {
+            nodes.push(CompactPrint.print(&node));
+        }
+
+        assert_eq!(nodes, vec![r#"{"a":null,"b":[1,{}]}"#, r#""x""#, "3"]);
+
+        let error = EventReader::new(r#"{"a": 1, "a": 2}"#.as_bytes())
+            .read_node()
+            .unwrap_err();
+        assert_eq!(error.message, "Duplicate property key a found");
+        assert_eq!(error.span.column, 10);
+    }
+
+    #[test]
+    fn input_size() {
+        let reader = EventReader::new("[1, 2, 3]".as_bytes());
+        assert_eq!(reader.limits.max_input_size, usize::MAX);
+        assert_eq!(reader.collect::<Result<Vec<_>, _>>().unwrap().len(), 5);
+
+        let capped = EventReader::new("[1, 2, 3]".as_bytes()).limits(Limits {
+            max_input_size: 4,
+            ..Limits::streaming()
+        });
+        assert_eq!(
+            capped.collect::<Result<Vec<_>, _>>().unwrap_err().code,
+            ErrorCode::LimitExceeded
+        );
+    }
+
+    #[test]
+    fn stops_after_error() {
+        let mut reader = EventReader::new("[}".as_bytes());
+
+        assert!(matches!(reader.next(), Some(Ok(Event::StartArray))));
+        assert!(matches!(reader.next(), Some(Err(_))));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt::Display;
+
+use super::error::ErrorCode;
+
+/// Long-form documentation for an [`ErrorCode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Explanation {
+    pub code: ErrorCode,
+    pub title: &'static str,
+    pub description: &'static str,
+    /// A document that triggers the error.
+    pub example: &'static str,
+    pub fix: &'static str,
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {}", self.code, self.title)?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.description)?;
+        writeln!(f)?;
+        writeln!(f, "Example:")?;
+        writeln!(f)?;
+
+        for line in self.example.lines() {
+            writeln!(f, "    {}", line)?;
+        }
+
+        writeln!(f)?;
+        write!(f, "Fix: {}", self.fix)
+    }
+}
+
+/// Looks up the explanation for `code`. Every code has one.
+pub fn explain(code: ErrorCode) -> Explanation {
+    let (title, description, example, fix) = match code {
+        ErrorCode::UnexpectedCharacter => (
+            "Unexpected character",
+            "The lexer found a character that cannot start any JSON token. Only \
+             `{ } [ ] : ,`, strings, numbers, `true`, `false` and `null` may appear \
+             outside of strings.",
+            "{\"a\": 1; \"b\": 2}",
+            "Remove the character or replace it with the intended punctuation, here `,`.",
+        ),
+        ErrorCode::UnterminatedString => (
+            "Unterminated string",
+            "A string was opened with `\"` but the input ended before the closing quote.",
+            "{\"name\": \"jtool}",
+            "Add the missing closing `\"`.",
+        ),
+        ErrorCode::InvalidEscape => (
+            "Invalid escape sequence",
+            "A backslash in a string was followed by a character that is not a JSON \
+             escape. JSON allows `\\\" \\\\ \\/ \\b \\f \\n \\r \\t` and `\\uXXXX`.",
+            "{\"path\": \"C:\\temp\\x\"}",
+            "Escape the backslash itself as `\\\\`.",
+        ),
+        ErrorCode::InvalidUnicodeEscape => (
+            "Invalid unicode escape",
+            "A `\\u` escape was not followed by four hexadecimal digits, or a UTF-16 \
+             surrogate was not part of a valid pair.",
+            "\"\\u12\"",
+            "Write exactly four hex digits, and encode characters outside the BMP as a \
+             high and low surrogate pair.",
+        ),
+        ErrorCode::ControlCharacter => (
+            "Unescaped control character in string",
+            "Characters below U+0020, including raw tabs and line breaks, must be escaped \
+             inside strings.",
+            "{\"text\": \"line one\nline two\"}",
+            "Use `\\n`, `\\t` or `\\u00XX` instead of the raw character.",
+        ),
+        ErrorCode::InvalidUtf8 => (
+            "Invalid UTF-8",
+            "The bytes of a string are not valid UTF-8. JSON text must be encoded as UTF-8.",
+            "\"caf\\xe9\" (saved as Latin-1)",
+            "Re-encode the file as UTF-8.",
+        ),
+        ErrorCode::InvalidNumber => (
+            "Invalid number",
+            "A number does not follow the JSON grammar. Leading zeros, a leading `+`, a \
+             leading or trailing `.`, hex literals and `NaN`/`Infinity` are not allowed \
+             in plain JSON.",
+            "{\"port\": 08080}",
+            "Write the number in decimal without leading zeros, or quote it as a string.",
+        ),
+        ErrorCode::UnexpectedIdentifier => (
+            "Unexpected identifier",
+            "A bare word appeared where a value was expected. The only bare words in JSON \
+             are `true`, `false` and `null`, all lowercase.",
+            "{\"enabled\": True}",
+            "Use the lowercase literal, or quote the word if it is a string.",
+        ),
+        ErrorCode::UnterminatedComment => (
+            "Unterminated comment",
+            "A `/*` block comment was never closed with `*/`.",
+            "{\"a\": 1 /* note }",
+            "Close the comment with `*/`.",
+        ),
+        ErrorCode::UnexpectedEndOfInput => (
+            "Unexpected end of input",
+            "The streaming reader ran out of input in the middle of a value. The tree \
+             parser reports the unclosed `{` or `[` instead.",
+            "{\"a\": [1, 2",
+            "Complete the value and close every open `[` and `{`.",
+        ),
+        ErrorCode::ExpectedValue => (
+            "Expected a value",
+            "A string, number, object, array, `true`, `false` or `null` was expected here. \
+             This usually means a stray or doubled comma, or a missing value after `:`.",
+            "{\"a\": , \"b\": 2}",
+            "Insert the missing value or remove the extra comma.",
+        ),
+        ErrorCode::ExpectedKey => (
+            "Expected a property key",
+            "Object members must start with a double-quoted string key.",
+            "{1: \"one\"}",
+            "Quote the key with double quotes: `\"1\"`.",
+        ),
+        ErrorCode::ExpectedColon => (
+            "Expected colon",
+            "A property key must be followed by `:` and then its value.",
+            "{\"name\" \"jtool\"}",
+            "Insert `:` between the key and the value.",
+        ),
+        ErrorCode::UnclosedObject => (
+            "Expected `}`",
+            "An object was not closed where expected. Either a `,` between members is \
+             missing or the closing `}` is.",
+            "{\"a\": 1 \"b\": 2}",
+            "Separate members with `,` and close the object with `}`.",
+        ),
+        ErrorCode::UnclosedArray => (
+            "Expected `]`",
+            "An array was not closed where expected. Either a `,` between elements is \
+             missing or the closing `]` is.",
+            "[1, 2 3]",
+            "Separate elements with `,` and close the array with `]`.",
+        ),
+        ErrorCode::TrailingContent => (
+            "Expected end of input",
+            "The document contains more than one top-level value.",
+            "{\"a\": 1} {\"b\": 2}",
+            "Wrap the values in an array, or read the input as JSON Lines.",
+        ),
+        ErrorCode::DuplicateKey => (
+            "Duplicate property key",
+            "The same key appears twice in one object. Parsers disagree on which value \
+             wins, so jtool rejects the document.",
+            "{\"a\": 1, \"a\": 2}",
+            "Remove or rename one of the members.",
+        ),
+        ErrorCode::Io => (
+            "I/O error",
+            "The input could not be read.",
+            "jtool check missing.json",
+            "Check that the file exists and is readable.",
+        ),
+        ErrorCode::LimitExceeded => (
+            "Resource limit exceeded",
+            "The document is larger or deeper than the configured limits allow: nesting \
+             depth, string or number length, members per object, elements per array or \
+             total input size. The defaults protect against hostile input. Schema \
+             validation also reports it when a `pattern` takes too long to match or \
+             `$ref`s nest too deeply.",
+            "[[[[[[ ... 129 levels deep ... ]]]]]]",
+            "Flatten or split the document, or raise the limit with `Limits` if the \
+             input is trusted.",
+        ),
+        ErrorCode::SchemaViolation => (
+            "Schema violation",
+            "The document is well-formed but does not satisfy the JSON Schema it was \
+             validated against. The message names the failing value by JSON Pointer and \
+             the schema keyword it failed after `#`.",
+            "{\"port\": \"8080\"} against {\"properties\": {\"port\": {\"type\": \"integer\"}}}",
+            "Change the value to satisfy the keyword, or relax the schema.",
+        ),
+    };
+
+    Explanation {
+        code,
+        title,
+        description,
+        example,
+        fix,
+    }
+}
+
+#[cfg(test)]
+mod explain_tests {
+    use super::*;
+    use crate::parser_update::{lexer::Dialect, nodes::parse_str_with};
+
+    #[test]
+    fn examples_trigger_their_code() {
+        // These cannot be written as a short &str, or only come from the event
+        // reader or the schema validator.
+        let skipped = [
+            ErrorCode::InvalidUtf8,
+            ErrorCode::UnexpectedEndOfInput,
+            ErrorCode::Io,
+            ErrorCode::LimitExceeded,
+            ErrorCode::SchemaViolation,
+        ];
+
+        for code in ErrorCode::ALL
+            .into_iter()
+            .filter(|code| !skipped.contains(code))
+        {
+            let example = explain(code).example;
+            let error = parse_str_with(example, Dialect::Jsonc).unwrap_err();
+
+            assert_eq!(error.code, code, "{}", example);
+        }
+    }
+
+    #[test]
+    fn render() {
+        let text = explain(ErrorCode::from_code("j0013").unwrap()).to_string();
+
+        assert!(text.starts_with("J0013: Expected colon\n\n"));
+        assert!(text.contains("\n    {\"name\" \"jtool\"}\n"));
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::nodes::{Literal, Node, Token, Visitor};
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Distinct strings tracked per place before giving up on an enum.
+const TRACKED_VALUES: usize = 64;
+
+/// The distinct strings seen at one place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Values {
+    Few(BTreeSet<String>),
+    /// More than can ever become an enum.
+    Many,
+}
+
+impl Default for Values {
+    fn default() -> Self {
+        Values::Few(BTreeSet::new())
+    }
+}
+
+impl Values {
+    fn insert(&mut self, value: &str) {
+        if let Values::Few(values) = self {
+            values.insert(value.to_string());
+
+            if values.len() > TRACKED_VALUES {
+                *self = Values::Many;
+            }
+        }
+    }
+}
+
+/// Everything the samples say about the values at one place, such as
+/// `/users/*/name`. Counts are per kind, so a place that holds strings in
+/// some samples and `null` in others has both.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Shape {
+    pub nulls: usize,
+    pub booleans: usize,
+    pub integers: usize,
+    /// Numbers with a fractional part, or too large for a 64-bit integer
+    /// so that generated code does not use one.
+    pub numbers: usize,
+    pub strings: usize,
+    pub arrays: usize,
+    pub objects: usize,
+    pub values: Values,
+    /// All elements of all arrays merged, `None` if every array was empty.
+    pub items: Option<Box<Shape>>,
+    /// Object members by key. A member present in fewer than `objects`
+    /// objects is optional.
+    pub properties: BTreeMap<String, Shape>,
+}
+
+impl Shape {
+    /// How many values were observed here.
+    pub fn total(&self) -> usize {
+        self.nulls
+            + self.booleans
+            + self.integers
+            + self.numbers
+            + self.strings
+            + self.arrays
+            + self.objects
+    }
+
+    /// Whether every object observed here has the member `name`.
+    pub fn is_required(&self, name: &str) -> bool {
+        self.properties
+            .get(name)
+            .is_some_and(|property| property.total() == self.objects)
+    }
+
+    /// The strings seen here if there are between two and `limit` of them
+    /// and some repeat, which suggests a fixed set rather than free text or
+    /// a constant.
+    pub fn enum_candidates(&self, limit: usize) -> Option<Vec<&str>> {
+        match &self.values {
+            Values::Few(values)
+                if (2..=limit).contains(&values.len()) && self.strings > values.len() =>
+            {
+                Some(values.iter().map(String::as_str).collect())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn merge(&mut self, other: Shape) {
+        self.nulls += other.nulls;
+        self.booleans += other.booleans;
+        self.integers += other.integers;
+        self.numbers += other.numbers;
+        self.strings += other.strings;
+        self.arrays += other.arrays;
+        self.objects += other.objects;
+
+        match other.values {
+            Values::Few(values) => {
+                for value in values {
+                    self.values.insert(&value);
+                }
+            }
+            Values::Many => self.values = Values::Many,
+        }
+
+        self.items = match (self.items.take(), other.items) {
+            (Some(mut items), Some(other)) => {
+                items.merge(*other);
+                Some(items)
+            }
+            (items, other) => items.or(other),
+        };
+
+        for (name, shape) in other.properties {
+            self.properties.entry(name).or_default().merge(shape);
+        }
+    }
+}
+
+/// Turns one value into the [`Shape`] of a single observation.
+pub struct Observe;
+
+impl Visitor<Shape> for Observe {
+    fn visit_primary(&self, value: &Literal) -> Shape {
+        let mut shape = Shape::default();
+
+        match value {
+            Literal::Null => shape.nulls = 1,
+            Literal::Bool(_) => shape.booleans = 1,
+            Literal::Number(value) if value.fract() == 0.0 && fits_i64(*value) => {
+                shape.integers = 1
+            }
+            Literal::Number(_) => shape.numbers = 1,
+            Literal::String(value) => {
+                shape.strings = 1;
+                shape.values.insert(value);
+            }
+        }
+
+        shape
+    }
+
+    fn visit_object(&self, _left: &Token, properties: &[Node], _right: &Token) -> Shape {
+        let mut shape = Shape {
+            objects: 1,
+            ..Shape::default()
+        };
+
+        for property in properties {
+            shape.merge(property.accept(self));
+        }
+
+        shape
+    }
+
+    /// A shape holding just the member, for [`Observe::visit_object`] to
+    /// merge.
+    fn visit_property(&self, key: &Token, _colon: &Token, value: &Node) -> Shape {
+        Shape {
+            properties: BTreeMap::from([(key.literal.to_string(), value.accept(self))]),
+            ..Shape::default()
+        }
+    }
+
+    fn visit_list(&self, _left: &Token, nodes: &[Node], _right: &Token) -> Shape {
+        let items = nodes.iter().fold(None, |items: Option<Shape>, node| {
+            let mut items = items.unwrap_or_default();
+            items.merge(node.accept(self));
+            Some(items)
+        });
+
+        Shape {
+            arrays: 1,
+            items: items.map(Box::new),
+            ..Shape::default()
+        }
+    }
+
+    fn visit_error(&self, _token: &Token) -> Shape {
+        Shape::default()
+    }
+}
+
+/// Infers a JSON Schema from sample documents.
+///
+/// Each sample is merged into one [`Shape`]: members missing from some
+/// samples become optional, places holding several kinds of value get a
+/// union `type`, and strings drawn from a small repeating set become an
+/// `enum`.
+#[derive(Debug, Clone)]
+pub struct Inferrer {
+    shape: Shape,
+    enum_limit: usize,
+}
+
+impl Default for Inferrer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inferrer {
+    pub fn new() -> Self {
+        Inferrer {
+            shape: Shape::default(),
+            enum_limit: 8,
+        }
+    }
+
+    /// The most distinct strings turned into an `enum`, `0` for none.
+    pub fn enum_limit(mut self, limit: usize) -> Self {
+        self.enum_limit = limit;
+        self
+    }
+
+    pub fn add(&mut self, sample: &Node) {
+        self.shape.merge(sample.accept(&Observe));
+    }
+
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    /// The schema for every sample added so far.
+    pub fn schema(&self) -> Node {
+        let mut schema = self.schema_of(&self.shape);
+
+        if let Node::Object(_, properties, _) = &mut schema {
+            properties.insert(0, Node::property("$schema", Node::string(DRAFT)));
+        }
+
+        schema
+    }
+
+    fn schema_of(&self, shape: &Shape) -> Node {
+        // Any fractional number widens the integers to `number`.
+        let integers = if shape.numbers == 0 {
+            shape.integers
+        } else {
+            0
+        };
+
+        let types = [
+            ("null", shape.nulls),
+            ("boolean", shape.booleans),
+            ("integer", integers),
+            ("number", shape.numbers),
+            ("string", shape.strings),
+            ("array", shape.arrays),
+            ("object", shape.objects),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+
+        let mut properties = vec![];
+
+        match types.as_slice() {
+            [] => {}
+            [name] => properties.push(Node::property("type", Node::string(name))),
+            names => properties.push(Node::property(
+                "type",
+                Node::list(names.iter().map(|name| Node::string(name)).collect()),
+            )),
+        }
+
+        // `enum` constrains every type, so only use it when the others
+        // could not be rejected by it.
+        let only_strings = types.iter().all(|name| matches!(*name, "string" | "null"));
+
+        if let Some(values) = shape
+            .enum_candidates(self.enum_limit)
+            .filter(|_| only_strings)
+        {
+            let mut values = values.into_iter().map(Node::string).collect::<Vec<_>>();
+
+            if shape.nulls > 0 {
+                values.push(Node::Primary(Literal::Null));
+            }
+
+            properties.push(Node::property("enum", Node::list(values)));
+        }
+
+        if let Some(items) = &shape.items {
+            properties.push(Node::property("items", self.schema_of(items)));
+        }
+
+        if shape.objects > 0 {
+            let members = shape
+                .properties
+                .iter()
+                .map(|(name, property)| Node::property(name, self.schema_of(property)))
+                .collect();
+            properties.push(Node::property("properties", Node::object(members)));
+
+            let required = shape
+                .properties
+                .keys()
+                .filter(|name| shape.is_required(name))
+                .map(|name| Node::string(name))
+                .collect::<Vec<_>>();
+
+            if !required.is_empty() {
+                properties.push(Node::property("required", Node::list(required)));
+            }
+        }
+
+        properties.sort_by_key(|property| match property {
+            Node::Property(key, _, _) => key.literal.to_string(),
+            _ => String::new(),
+        });
+
+        Node::object(properties)
+    }
+}
+
+/// Infers a schema from `samples` with the default settings.
+pub fn infer_schema(samples: &[Node]) -> Node {
+    let mut inferrer = Inferrer::new();
+
+    for sample in samples {
+        inferrer.add(sample);
+    }
+
+    inferrer.schema()
+}
+
+/// Whether `value` is within the range of `i64`.
+pub fn fits_i64(value: f64) -> bool {
+    (i64::MIN as f64..-(i64::MIN as f64)).contains(&value)
+}
+
+#[cfg(test)]
+mod infer_tests {
+    use super::*;
+    use crate::parser_update::{nodes::parse_str, printer::CompactPrint, schema::Schema};
+
+    const SAMPLES: [&str; 3] = [
+        r#"{"id": 1, "name": "a", "mode": "dev", "tags": ["x"], "owner": {"id": 7}}"#,
+        r#"{"id": 2, "name": "b", "mode": "prod", "tags": [], "owner": null, "score": 1.5}"#,
+        r#"{"id": 3, "name": "c", "mode": "dev", "tags": ["y", 2], "score": 2}"#,
+    ];
+
+    fn samples() -> Vec<Node> {
+        SAMPLES
+            .iter()
+            .map(|sample| parse_str(sample).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn schema() {
+        assert_eq!(
+            CompactPrint.print(&infer_schema(&samples())),
+            concat!(
+                r#"{"$schema":"https://json-schema.org/draft/2020-12/schema","#,
+                r#""properties":{"id":{"type":"integer"},"mode":{"enum":["dev","prod"],"type":"string"},"#,
+                r#""name":{"type":"string"},"owner":{"properties":{"id":{"type":"integer"}},"#,
+                r#""required":["id"],"type":["null","object"]},"score":{"type":"number"},"#,
+                r#""tags":{"items":{"type":["integer","string"]},"type":"array"}},"#,
+                r#""required":["id","mode","name","tags"],"type":"object"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn shape() {
+        let mut inferrer = Inferrer::new().enum_limit(0);
+        for sample in samples() {
+            inferrer.add(&sample);
+        }
+
+        let shape = inferrer.shape();
+        assert_eq!(shape.objects, 3);
+        assert!(shape.is_required("tags"));
+        assert!(!shape.is_required("owner"));
+        assert_eq!(
+            shape.properties["mode"].enum_candidates(8),
+            Some(vec!["dev", "prod"])
+        );
+        assert_eq!(shape.properties["name"].enum_candidates(8), None);
+        assert!(!CompactPrint.print(&inferrer.schema()).contains("enum"));
+    }
+
+    #[test]
+    fn samples_validate() {
+        let schema = Schema::compile(&infer_schema(&samples())).unwrap();
+
+        for sample in samples() {
+            assert!(schema.is_valid(&sample));
+        }
+        assert!(!schema.is_valid(
+            &parse_str(r#"{"id": 4, "name": "d", "mode": "test", "tags": []}"#).unwrap()
+        ));
+    }
+}
@@ -0,0 +1,1305 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use super::{
+    nodes::{Literal, Node},
+    printer::CompactPrint,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterError {
+    /// The program could not be parsed. Holds the character offset.
+    Syntax(String, usize),
+    /// Evaluation failed, for example indexing a number with a string.
+    Runtime(String),
+}
+
+impl std::error::Error for FilterError {}
+
+impl Display for FilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Syntax(message, position) => write!(f, "{} at position {}", message, position),
+            Self::Runtime(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Identity,
+    Recurse,
+    Literal(Literal),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    Iterate(Box<Expr>),
+    /// `expr?`, errors produce no output.
+    Optional(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Alternative(Box<Expr>, Box<Expr>),
+    Call(Builtin, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Builtin {
+    Select,
+    Keys,
+    Length,
+    Not,
+    Empty,
+    Add,
+    Type,
+    Has,
+}
+
+/// A compiled filter in a subset of the jq language.
+///
+/// Supports `.`, `..`, field, index and slice access, `.[]`, `?`, pipes,
+/// commas, array and object construction, arithmetic, comparisons, `and`,
+/// `or`, `//` and the builtins `select`, `map`, `keys`, `length`, `not`,
+/// `empty`, `add`, `type` and `has`.
+#[derive(Debug, Clone)]
+pub struct Program {
+    expr: Expr,
+}
+
+impl Program {
+    pub fn parse(source: &str) -> Result<Program, FilterError> {
+        let mut parser = FilterParser {
+            chars: source.chars().collect(),
+            position: 0,
+        };
+
+        let expr = parser.pipe()?;
+        parser.skip_whitespace();
+
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("Unexpected character"));
+        }
+
+        Ok(Program { expr })
+    }
+
+    /// Runs the filter, returning every output in order.
+    pub fn run(&self, input: &Node) -> Result<Vec<Node>, FilterError> {
+        eval(&self.expr, input)
+    }
+}
+
+/// Parses `program` and runs it against `input`.
+pub fn run(input: &Node, program: &str) -> Result<Vec<Node>, FilterError> {
+    Program::parse(program)?.run(input)
+}
+
+fn eval(expr: &Expr, input: &Node) -> Result<Vec<Node>, FilterError> {
+    let mut outputs = vec![];
+    eval_into(expr, input, &mut outputs)?;
+    Ok(outputs)
+}
+
+/// Runs `expr`, adding its outputs to `outputs` as they are produced, so the
+/// ones before an error are kept, as jq streams them.
+fn eval_into(expr: &Expr, input: &Node, outputs: &mut Vec<Node>) -> Result<(), FilterError> {
+    match expr {
+        Expr::Identity => outputs.push(input.clone()),
+        Expr::Recurse => recurse(input, outputs),
+        Expr::Literal(literal) => outputs.push(Node::Primary(literal.clone())),
+        Expr::Field(target, name) => each(target, input, |node| {
+            outputs.push(index(&node, &Node::string(name))?);
+            Ok(())
+        })?,
+        Expr::Index(target, key) => each(target, input, |node| {
+            each(key, input, |key| {
+                outputs.push(index(&node, &key)?);
+                Ok(())
+            })
+        })?,
+        Expr::Slice(target, from, to) => {
+            let bounds = |bound: &Option<Box<Expr>>| match bound {
+                Some(bound) => eval(bound, input),
+                None => Ok(vec![null()]),
+            };
+
+            each(target, input, |node| {
+                for from in bounds(from)? {
+                    for to in bounds(to)? {
+                        outputs.push(slice(&node, &from, &to)?);
+                    }
+                }
+
+                Ok(())
+            })?;
+        }
+        Expr::Iterate(target) => each(target, input, |node| {
+            outputs.extend(iterate(&node)?);
+            Ok(())
+        })?,
+        Expr::Optional(inner) => {
+            // The error is dropped, the outputs before it are not.
+            let _ = eval_into(inner, input, outputs);
+        }
+        Expr::Pipe(left, right) => each(left, input, |node| eval_into(right, &node, outputs))?,
+        Expr::Comma(left, right) => {
+            eval_into(left, input, outputs)?;
+            eval_into(right, input, outputs)?;
+        }
+        Expr::Array(None) => outputs.push(Node::list(vec![])),
+        Expr::Array(Some(inner)) => outputs.push(Node::list(eval(inner, input)?)),
+        Expr::Object(entries) => {
+            let mut objects = vec![vec![]];
+
+            for (key, value) in entries {
+                let keys = eval(key, input)?;
+                let values = eval(value, input)?;
+                let mut extended = vec![];
+
+                for properties in &objects {
+                    for key in &keys {
+                        let Node::Primary(Literal::String(key)) = key else {
+                            return Err(runtime(format!(
+                                "Object keys must be strings, not {}",
+                                describe(key)
+                            )));
+                        };
+
+                        for value in &values {
+                            let mut properties = properties.clone();
+                            set_member(&mut properties, key, value.clone());
+                            extended.push(properties);
+                        }
+                    }
+                }
+
+                objects = extended;
+            }
+
+            outputs.extend(objects.into_iter().map(Node::object));
+        }
+        Expr::Negate(inner) => each(inner, input, |node| match node {
+            Node::Primary(Literal::Number(value)) => {
+                outputs.push(Node::number(-value));
+                Ok(())
+            }
+            node => Err(runtime(format!("{} cannot be negated", describe(&node)))),
+        })?,
+        Expr::Binary(left, op, right) => {
+            let rights = eval(right, input)?;
+            let lefts = eval(left, input)?;
+
+            for right in &rights {
+                for left in &lefts {
+                    outputs.push(binary(left, *op, right)?);
+                }
+            }
+        }
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            let is_and = matches!(expr, Expr::And(..));
+
+            each(left, input, |left| {
+                if truthy(&left) != is_and {
+                    outputs.push(boolean(!is_and));
+                    return Ok(());
+                }
+
+                each(right, input, |right| {
+                    outputs.push(boolean(truthy(&right)));
+                    Ok(())
+                })
+            })?;
+        }
+        Expr::Alternative(left, right) => {
+            let mut lefts = vec![];
+            let _ = eval_into(left, input, &mut lefts);
+            lefts.retain(truthy);
+
+            if lefts.is_empty() {
+                eval_into(right, input, outputs)?;
+            } else {
+                outputs.extend(lefts);
+            }
+        }
+        Expr::Call(builtin, arguments) => outputs.extend(call(*builtin, arguments, input)?),
+    }
+
+    Ok(())
+}
+
+/// Passes each output of `expr` to `next`. Outputs before an error in
+/// `expr` are still passed on, then the error is returned.
+fn each(
+    expr: &Expr,
+    input: &Node,
+    mut next: impl FnMut(Node) -> Result<(), FilterError>,
+) -> Result<(), FilterError> {
+    let mut nodes = vec![];
+    let result = eval_into(expr, input, &mut nodes);
+
+    for node in nodes {
+        next(node)?;
+    }
+
+    result
+}
+
+fn call(builtin: Builtin, arguments: &[Expr], input: &Node) -> Result<Vec<Node>, FilterError> {
+    let output = match builtin {
+        Builtin::Select => {
+            let mut outputs = vec![];
+
+            for condition in eval(&arguments[0], input)? {
+                if truthy(&condition) {
+                    outputs.push(input.clone());
+                }
+            }
+
+            return Ok(outputs);
+        }
+        Builtin::Empty => return Ok(vec![]),
+        Builtin::Has => {
+            let mut outputs = vec![];
+
+            for key in eval(&arguments[0], input)? {
+                let found = match (input, &key) {
+                    (Node::Object(..), Node::Primary(Literal::String(key))) => {
+                        input.get_member(key).is_some()
+                    }
+                    (Node::List(_, nodes, _), Node::Primary(Literal::Number(index))) => {
+                        *index >= 0.0 && (*index as usize) < nodes.len()
+                    }
+                    _ => {
+                        return Err(runtime(format!(
+                            "Cannot check whether {} has a {} key",
+                            type_name(input),
+                            type_name(&key)
+                        )))
+                    }
+                };
+
+                outputs.push(boolean(found));
+            }
+
+            return Ok(outputs);
+        }
+        Builtin::Keys => match input {
+            Node::Object(_, properties, _) => {
+                let mut keys = properties
+                    .iter()
+                    .filter_map(|property| match property {
+                        Node::Property(key, _, _) => Some(key.literal.to_string()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                keys.sort();
+
+                Node::list(keys.iter().map(|key| Node::string(key)).collect())
+            }
+            Node::List(_, nodes, _) => Node::list(
+                (0..nodes.len())
+                    .map(|index| Node::number(index as f64))
+                    .collect(),
+            ),
+            _ => return Err(runtime(format!("{} has no keys", describe(input)))),
+        },
+        Builtin::Length => match input {
+            Node::Primary(Literal::Null) => Node::number(0.0),
+            Node::Primary(Literal::Number(value)) => Node::number(value.abs()),
+            Node::Primary(Literal::String(value)) => Node::number(value.chars().count() as f64),
+            Node::List(_, nodes, _) => Node::number(nodes.len() as f64),
+            Node::Object(_, properties, _) => Node::number(properties.len() as f64),
+            _ => return Err(runtime(format!("{} has no length", describe(input)))),
+        },
+        Builtin::Not => boolean(!truthy(input)),
+        Builtin::Add => iterate(input)?
+            .iter()
+            .try_fold(null(), |sum, node| binary(&sum, BinaryOp::Add, node))?,
+        Builtin::Type => Node::string(type_name(input)),
+    };
+
+    Ok(vec![output])
+}
+
+/// The node followed by all of its descendants, like jq's `..`.
+fn recurse(node: &Node, outputs: &mut Vec<Node>) {
+    outputs.push(node.clone());
+
+    if let Ok(children) = iterate(node) {
+        for child in &children {
+            recurse(child, outputs);
+        }
+    }
+}
+
+fn index(node: &Node, key: &Node) -> Result<Node, FilterError> {
+    match (node, key) {
+        (Node::Primary(Literal::Null), Node::Primary(Literal::String(_) | Literal::Number(_))) => {
+            Ok(null())
+        }
+        (Node::Object(..), Node::Primary(Literal::String(key))) => {
+            Ok(node.get_member(key).cloned().unwrap_or_else(null))
+        }
+        (Node::List(_, nodes, _), Node::Primary(Literal::Number(index))) => {
+            let index = index.floor() as i64;
+            let index = if index < 0 {
+                nodes.len() as i64 + index
+            } else {
+                index
+            };
+
+            Ok(usize::try_from(index)
+                .ok()
+                .and_then(|index| nodes.get(index))
+                .cloned()
+                .unwrap_or_else(null))
+        }
+        _ => Err(runtime(format!(
+            "Cannot index {} with {}",
+            type_name(node),
+            match key {
+                Node::Primary(Literal::String(_)) => CompactPrint.print(key),
+                _ => type_name(key).to_string(),
+            }
+        ))),
+    }
+}
+
+/// `.[from:to]` on an array or string. Negative bounds count from the end.
+fn slice(node: &Node, from: &Node, to: &Node) -> Result<Node, FilterError> {
+    let len = match node {
+        Node::Primary(Literal::Null) => return Ok(null()),
+        Node::Primary(Literal::String(value)) => value.chars().count(),
+        Node::List(_, nodes, _) => nodes.len(),
+        _ => return Err(runtime(format!("Cannot slice {}", type_name(node)))),
+    } as i64;
+
+    let bound = |bound: &Node, default: i64| match bound {
+        Node::Primary(Literal::Null) => Ok(default),
+        Node::Primary(Literal::Number(value)) => {
+            let value = value.floor() as i64;
+            Ok(if value < 0 { len + value } else { value }.clamp(0, len))
+        }
+        _ => Err(runtime(format!(
+            "Slice bounds must be numbers, not {}",
+            type_name(bound)
+        ))),
+    };
+
+    let from = bound(from, 0)? as usize;
+    let to = (bound(to, len)? as usize).max(from);
+
+    Ok(match node {
+        Node::Primary(Literal::String(value)) => {
+            Node::string(&value.chars().skip(from).take(to - from).collect::<String>())
+        }
+        Node::List(_, nodes, _) => Node::list(nodes[from..to].to_vec()),
+        _ => null(),
+    })
+}
+
+fn iterate(node: &Node) -> Result<Vec<Node>, FilterError> {
+    match node {
+        Node::List(_, nodes, _) => Ok(nodes.clone()),
+        Node::Object(_, properties, _) => Ok(properties
+            .iter()
+            .filter_map(|property| match property {
+                Node::Property(_, _, value) => Some((**value).clone()),
+                _ => None,
+            })
+            .collect()),
+        _ => Err(runtime(format!("Cannot iterate over {}", describe(node)))),
+    }
+}
+
+fn binary(left: &Node, op: BinaryOp, right: &Node) -> Result<Node, FilterError> {
+    use Literal::{Null, Number, String};
+
+    let ordering = order(left, right);
+
+    let result = match op {
+        BinaryOp::Eq => Some(boolean(ordering == Ordering::Equal)),
+        BinaryOp::Ne => Some(boolean(ordering != Ordering::Equal)),
+        BinaryOp::Lt => Some(boolean(ordering == Ordering::Less)),
+        BinaryOp::Le => Some(boolean(ordering != Ordering::Greater)),
+        BinaryOp::Gt => Some(boolean(ordering == Ordering::Greater)),
+        BinaryOp::Ge => Some(boolean(ordering != Ordering::Less)),
+        BinaryOp::Add => match (left, right) {
+            (Node::Primary(Null), other) | (other, Node::Primary(Null)) => Some(other.clone()),
+            (Node::Primary(Number(left)), Node::Primary(Number(right))) => {
+                Some(Node::number(left + right))
+            }
+            (Node::Primary(String(left)), Node::Primary(String(right))) => {
+                Some(Node::string(&format!("{}{}", left, right)))
+            }
+            (Node::List(_, left, _), Node::List(_, right, _)) => {
+                Some(Node::list(left.iter().chain(right).cloned().collect()))
+            }
+            (Node::Object(_, left, _), Node::Object(_, right, _)) => {
+                let mut properties = left.clone();
+
+                for property in right {
+                    if let Node::Property(key, _, value) = property {
+                        set_member(&mut properties, &key.literal.to_string(), (**value).clone());
+                    }
+                }
+
+                Some(Node::object(properties))
+            }
+            _ => None,
+        },
+        BinaryOp::Subtract => match (left, right) {
+            (Node::Primary(Number(left)), Node::Primary(Number(right))) => {
+                Some(Node::number(left - right))
+            }
+            (Node::List(_, left, _), Node::List(_, right, _)) => Some(Node::list(
+                left.iter()
+                    .filter(|node| {
+                        !right
+                            .iter()
+                            .any(|other| order(node, other) == Ordering::Equal)
+                    })
+                    .cloned()
+                    .collect(),
+            )),
+            _ => None,
+        },
+        BinaryOp::Multiply => match (left, right) {
+            (Node::Primary(Number(left)), Node::Primary(Number(right))) => {
+                Some(Node::number(left * right))
+            }
+            _ => None,
+        },
+        BinaryOp::Divide => match (left, right) {
+            (Node::Primary(Number(_)), Node::Primary(Number(divisor))) if *divisor == 0.0 => {
+                return Err(runtime(format!(
+                    "{} and {} cannot be divided because the divisor is zero",
+                    describe(left),
+                    describe(right)
+                )))
+            }
+            (Node::Primary(Number(left)), Node::Primary(Number(right))) => {
+                Some(Node::number(left / right))
+            }
+            (Node::Primary(String(left)), Node::Primary(String(right))) => Some(Node::list(
+                left.split(right.as_str()).map(Node::string).collect(),
+            )),
+            _ => None,
+        },
+        BinaryOp::Modulo => match (left, right) {
+            (Node::Primary(Number(_)), Node::Primary(Number(divisor))) if *divisor as i64 == 0 => {
+                return Err(runtime(format!(
+                    "{} and {} cannot be divided because the divisor is zero",
+                    describe(left),
+                    describe(right)
+                )))
+            }
+            (Node::Primary(Number(dividend)), Node::Primary(Number(divisor))) => {
+                match (*dividend as i64).checked_rem(*divisor as i64) {
+                    Some(remainder) => Some(Node::number(remainder as f64)),
+                    None => {
+                        return Err(runtime(format!(
+                            "{} and {} cannot be divided because the remainder overflows",
+                            describe(left),
+                            describe(right)
+                        )))
+                    }
+                }
+            }
+            _ => None,
+        },
+    };
+
+    result.ok_or_else(|| {
+        let verb = match op {
+            BinaryOp::Add => "added",
+            BinaryOp::Subtract => "subtracted",
+            BinaryOp::Multiply => "multiplied",
+            _ => "divided",
+        };
+
+        runtime(format!(
+            "{} and {} cannot be {}",
+            describe(left),
+            describe(right),
+            verb
+        ))
+    })
+}
+
+/// jq's total order: null < false < true < numbers < strings < arrays <
+/// objects. Arrays compare element by element, objects by their sorted
+/// keys and then by the values under those keys.
+fn order(left: &Node, right: &Node) -> Ordering {
+    match (left, right) {
+        (Node::Primary(Literal::Number(left)), Node::Primary(Literal::Number(right))) => {
+            left.partial_cmp(right).unwrap_or(Ordering::Equal)
+        }
+        (Node::Primary(Literal::String(left)), Node::Primary(Literal::String(right))) => {
+            left.cmp(right)
+        }
+        (Node::List(_, left, _), Node::List(_, right, _)) => left
+            .iter()
+            .zip(right)
+            .map(|(left, right)| order(left, right))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(left.len().cmp(&right.len())),
+        (Node::Object(_, left_members, _), Node::Object(_, right_members, _)) => {
+            let keys = |properties: &[Node]| {
+                let mut keys = properties
+                    .iter()
+                    .filter_map(|property| match property {
+                        Node::Property(key, _, _) => Some(key.literal.to_string()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                keys.sort();
+                keys
+            };
+
+            let left_keys = keys(left_members);
+
+            left_keys.cmp(&keys(right_members)).then_with(|| {
+                left_keys
+                    .iter()
+                    .filter_map(|key| Some(order(left.get_member(key)?, right.get_member(key)?)))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(left).cmp(&rank(right)),
+    }
+}
+
+fn rank(node: &Node) -> u8 {
+    match node {
+        Node::Primary(Literal::Bool(false)) => 1,
+        Node::Primary(Literal::Bool(true)) => 2,
+        Node::Primary(Literal::Number(_)) => 3,
+        Node::Primary(Literal::String(_)) => 4,
+        Node::List(..) => 5,
+        Node::Object(..) => 6,
+        _ => 0,
+    }
+}
+
+/// Replaces the member under `key`, or inserts it in key order.
+fn set_member(properties: &mut Vec<Node>, key: &str, value: Node) {
+    for property in properties.iter_mut() {
+        if let Node::Property(name, _, current) = property {
+            if name.literal.to_string() == key {
+                **current = value;
+                return;
+            }
+        }
+    }
+
+    let index = properties.partition_point(|property| {
+        matches!(property, Node::Property(name, _, _) if name.literal.to_string().as_str() < key)
+    });
+    properties.insert(index, Node::property(key, value));
+}
+
+fn truthy(node: &Node) -> bool {
+    !matches!(
+        node,
+        Node::Primary(Literal::Null | Literal::Bool(false)) | Node::Error(_)
+    )
+}
+
+fn type_name(node: &Node) -> &'static str {
+    match node {
+        Node::Primary(Literal::Bool(_)) => "boolean",
+        Node::Primary(Literal::Number(_)) => "number",
+        Node::Primary(Literal::String(_)) => "string",
+        Node::List(..) => "array",
+        Node::Object(..) => "object",
+        _ => "null",
+    }
+}
+
+/// The type and value for error messages, e.g. `number (1)`.
+fn describe(node: &Node) -> String {
+    format!("{} ({})", type_name(node), CompactPrint.print(node))
+}
+
+fn runtime(message: String) -> FilterError {
+    FilterError::Runtime(message)
+}
+
+fn null() -> Node {
+    Node::Primary(Literal::Null)
+}
+
+fn boolean(value: bool) -> Node {
+    Node::Primary(Literal::Bool(value))
+}
+
+struct FilterParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl FilterParser {
+    fn pipe(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.comma()?;
+
+        while self.eat_symbol("|") {
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.comma()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn comma(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.alternative()?;
+
+        while self.eat_symbol(",") {
+            expr = Expr::Comma(Box::new(expr), Box::new(self.alternative()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn alternative(&mut self) -> Result<Expr, FilterError> {
+        let expr = self.or()?;
+
+        if self.eat_symbol("//") {
+            return Ok(Expr::Alternative(
+                Box::new(expr),
+                Box::new(self.alternative()?),
+            ));
+        }
+
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.and()?;
+
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.comparison()?;
+
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.comparison()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, FilterError> {
+        let expr = self.additive()?;
+
+        let op = [
+            ("==", BinaryOp::Eq),
+            ("!=", BinaryOp::Ne),
+            ("<=", BinaryOp::Le),
+            (">=", BinaryOp::Ge),
+            ("<", BinaryOp::Lt),
+            (">", BinaryOp::Gt),
+        ]
+        .into_iter()
+        .find(|(symbol, _)| self.eat_symbol(symbol));
+
+        match op {
+            Some((_, op)) => Ok(Expr::Binary(Box::new(expr), op, Box::new(self.additive()?))),
+            None => Ok(expr),
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.multiplicative()?;
+
+        loop {
+            let op = if self.eat_symbol("+") {
+                BinaryOp::Add
+            } else if self.eat_symbol("-") {
+                BinaryOp::Subtract
+            } else {
+                return Ok(expr);
+            };
+
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.unary()?;
+
+        loop {
+            self.skip_whitespace();
+
+            let op = match (self.peek(), self.chars.get(self.position + 1)) {
+                (Some('*'), _) => BinaryOp::Multiply,
+                (Some('/'), Some('/')) => return Ok(expr),
+                (Some('/'), _) => BinaryOp::Divide,
+                (Some('%'), _) => BinaryOp::Modulo,
+                _ => return Ok(expr),
+            };
+
+            self.position += 1;
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+        if self.eat_symbol("-") {
+            return Ok(Expr::Negate(Box::new(self.postfix()?)));
+        }
+
+        self.postfix()
+    }
+
+    /// A term followed by any number of `.name`, `[...]` and `?`.
+    fn postfix(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.term()?;
+
+        loop {
+            match (self.peek(), self.chars.get(self.position + 1)) {
+                (Some('.'), Some('[')) => {
+                    self.position += 1;
+                    expr = self.brackets(expr)?;
+                }
+                (Some('.'), Some(next)) if *next == '"' || is_name_start(*next) => {
+                    self.position += 1;
+                    expr = Expr::Field(Box::new(expr), self.field_name()?);
+                }
+                (Some('['), _) => expr = self.brackets(expr)?,
+                (Some('?'), _) => {
+                    self.position += 1;
+                    expr = Expr::Optional(Box::new(expr));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, FilterError> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('.') => {
+                self.position += 1;
+
+                match self.peek() {
+                    Some('.') => {
+                        self.position += 1;
+                        Ok(Expr::Recurse)
+                    }
+                    Some('[') => self.brackets(Expr::Identity),
+                    Some(next) if next == '"' || is_name_start(next) => {
+                        Ok(Expr::Field(Box::new(Expr::Identity), self.field_name()?))
+                    }
+                    _ => Ok(Expr::Identity),
+                }
+            }
+            Some('0'..='9') => Ok(Expr::Literal(Literal::Number(self.number()?))),
+            Some('"') => Ok(Expr::Literal(Literal::String(self.string()?))),
+            Some('[') => {
+                self.position += 1;
+
+                if self.eat_symbol("]") {
+                    return Ok(Expr::Array(None));
+                }
+
+                let inner = self.pipe()?;
+                self.expect(']')?;
+
+                Ok(Expr::Array(Some(Box::new(inner))))
+            }
+            Some('{') => self.object(),
+            Some('(') => {
+                self.position += 1;
+                let inner = self.pipe()?;
+                self.expect(')')?;
+
+                Ok(inner)
+            }
+            Some(next) if is_name_start(next) => self.call(),
+            _ => Err(self.error("Expected an expression")),
+        }
+    }
+
+    /// `[]`, `[index]`, `[from:to]` applied to `target`.
+    fn brackets(&mut self, target: Expr) -> Result<Expr, FilterError> {
+        self.expect('[')?;
+        let target = Box::new(target);
+
+        if self.eat_symbol("]") {
+            return Ok(Expr::Iterate(target));
+        }
+
+        let from = if self.eat_symbol(":") {
+            None
+        } else {
+            let from = self.pipe()?;
+
+            if !self.eat_symbol(":") {
+                self.expect(']')?;
+                return Ok(Expr::Index(target, Box::new(from)));
+            }
+
+            Some(Box::new(from))
+        };
+
+        let to = if self.eat_symbol("]") {
+            None
+        } else {
+            let to = self.pipe()?;
+            self.expect(']')?;
+
+            Some(Box::new(to))
+        };
+
+        if from.is_none() && to.is_none() {
+            return Err(self.error("Expected a slice bound"));
+        }
+
+        Ok(Expr::Slice(target, from, to))
+    }
+
+    fn object(&mut self) -> Result<Expr, FilterError> {
+        self.expect('{')?;
+        let mut entries = vec![];
+
+        if self.eat_symbol("}") {
+            return Ok(Expr::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+
+            let (key, shorthand) = match self.peek() {
+                Some('(') => {
+                    self.position += 1;
+                    let key = self.pipe()?;
+                    self.expect(')')?;
+
+                    (key, None)
+                }
+                Some('"') => {
+                    let key = self.string()?;
+                    (Expr::Literal(Literal::String(key.clone())), Some(key))
+                }
+                Some(next) if is_name_start(next) => {
+                    let key = self.name();
+                    (Expr::Literal(Literal::String(key.clone())), Some(key))
+                }
+                _ => return Err(self.error("Expected an object key")),
+            };
+
+            let value = if self.eat_symbol(":") {
+                self.object_value()?
+            } else if let Some(key) = shorthand {
+                Expr::Field(Box::new(Expr::Identity), key)
+            } else {
+                return Err(self.error("Expected ':'"));
+            };
+
+            entries.push((key, value));
+
+            if !self.eat_symbol(",") {
+                self.expect('}')?;
+                return Ok(Expr::Object(entries));
+            }
+        }
+    }
+
+    /// Object values may use pipes but not commas, which separate entries.
+    fn object_value(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.alternative()?;
+
+        while self.eat_symbol("|") {
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.alternative()?));
+        }
+
+        Ok(expr)
+    }
+
+    /// A literal keyword or builtin call. `map(f)` becomes `[.[] | f]`.
+    fn call(&mut self) -> Result<Expr, FilterError> {
+        let start = self.position;
+        let name = self.name();
+
+        let (builtin, arity) = match name.as_str() {
+            "true" => return Ok(Expr::Literal(Literal::Bool(true))),
+            "false" => return Ok(Expr::Literal(Literal::Bool(false))),
+            "null" => return Ok(Expr::Literal(Literal::Null)),
+            "map" => {
+                let mut arguments = self.arguments(&name, 1, start)?;
+                let iterate = Expr::Iterate(Box::new(Expr::Identity));
+
+                return Ok(Expr::Array(Some(Box::new(Expr::Pipe(
+                    Box::new(iterate),
+                    Box::new(arguments.remove(0)),
+                )))));
+            }
+            "select" => (Builtin::Select, 1),
+            "has" => (Builtin::Has, 1),
+            "keys" => (Builtin::Keys, 0),
+            "length" => (Builtin::Length, 0),
+            "not" => (Builtin::Not, 0),
+            "empty" => (Builtin::Empty, 0),
+            "add" => (Builtin::Add, 0),
+            "type" => (Builtin::Type, 0),
+            _ => {
+                self.position = start;
+                return Err(self.error(&format!("Unknown function '{}'", name)));
+            }
+        };
+
+        Ok(Expr::Call(builtin, self.arguments(&name, arity, start)?))
+    }
+
+    /// Arguments in jq syntax, `name(a; b)`, checked against `arity`.
+    fn arguments(
+        &mut self,
+        name: &str,
+        arity: usize,
+        start: usize,
+    ) -> Result<Vec<Expr>, FilterError> {
+        let mut arguments = vec![];
+
+        if self.peek() == Some('(') {
+            self.position += 1;
+
+            loop {
+                arguments.push(self.pipe()?);
+
+                if !self.eat_symbol(";") {
+                    self.expect(')')?;
+                    break;
+                }
+            }
+        }
+
+        if arguments.len() != arity {
+            self.position = start;
+            return Err(self.error(&format!("Function '{}' takes {} argument(s)", name, arity)));
+        }
+
+        Ok(arguments)
+    }
+
+    fn field_name(&mut self) -> Result<String, FilterError> {
+        if self.peek() == Some('"') {
+            return self.string();
+        }
+
+        Ok(self.name())
+    }
+
+    fn name(&mut self) -> String {
+        let start = self.position;
+
+        while self
+            .peek()
+            .is_some_and(|c| is_name_start(c) || c.is_ascii_digit())
+        {
+            self.position += 1;
+        }
+
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn string(&mut self) -> Result<String, FilterError> {
+        self.expect('"')?;
+        let mut value = String::new();
+
+        loop {
+            let character = self
+                .next()
+                .ok_or_else(|| self.error("Unterminated string"))?;
+
+            match character {
+                '"' => return Ok(value),
+                '\\' => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        Some('(') => {
+                            self.position -= 2;
+                            return Err(self.error("String interpolation is not supported"));
+                        }
+                        _ => return Err(self.error("Invalid escape sequence")),
+                    };
+
+                    value.push(escaped);
+                }
+                _ => value.push(character),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, FilterError> {
+        let mut code = 0;
+
+        for _ in 0..4 {
+            let digit = self
+                .next()
+                .and_then(|digit| digit.to_digit(16))
+                .ok_or_else(|| self.error("Invalid unicode escape"))?;
+            code = code * 16 + digit;
+        }
+
+        char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape"))
+    }
+
+    fn number(&mut self) -> Result<f64, FilterError> {
+        let start = self.position;
+
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.position += 1;
+        }
+
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.position += 1;
+
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.position += 1;
+            }
+
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.position += 1;
+            }
+        }
+
+        let text = self.chars[start..self.position].iter().collect::<String>();
+
+        text.parse::<f64>().map_err(|_| {
+            self.position = start;
+            self.error("Invalid number")
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Skips whitespace and consumes `symbol` if it comes next.
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        self.skip_whitespace();
+
+        let len = symbol.chars().count();
+        let matches = self.chars.len() >= self.position + len
+            && self.chars[self.position..self.position + len]
+                .iter()
+                .copied()
+                .eq(symbol.chars());
+
+        if matches {
+            self.position += len;
+        }
+
+        matches
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let start = self.position;
+        self.skip_whitespace();
+
+        if self.peek().is_some_and(is_name_start) && self.name() == keyword {
+            return true;
+        }
+
+        self.position = start;
+        false
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), FilterError> {
+        if self.eat_symbol(&expected.to_string()) {
+            return Ok(());
+        }
+
+        Err(self.error(&format!("Expected '{}'", expected)))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.position += 1;
+        Some(character)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn error(&self, message: &str) -> FilterError {
+        FilterError::Syntax(message.to_string(), self.position)
+    }
+}
+
+fn is_name_start(character: char) -> bool {
+    character.is_ascii_alphabetic() || character == '_'
+}
+
+#[cfg(test)]
+mod jq_tests {
+    use super::*;
+    use crate::parser_update::nodes::parse_str;
+
+    const ORDER: &str = r#"{
+        "user": {"name": "Ada", "email": null},
+        "items": [
+            {"name": "pen", "price": 1.5, "tags": ["office"]},
+            {"name": "book", "price": 12, "tags": []},
+            {"name": "lamp", "price": 30, "tags": ["home", "light"]}
+        ]
+    }"#;
+
+    fn outputs(program: &str) -> Vec<String> {
+        run(&parse_str(ORDER).unwrap(), program)
+            .unwrap()
+            .iter()
+            .map(|node| CompactPrint.print(node))
+            .collect()
+    }
+
+    #[test]
+    fn access() {
+        assert_eq!(outputs(".user.name"), vec![r#""Ada""#]);
+        assert_eq!(outputs(".items[-1].name"), vec![r#""lamp""#]);
+        assert_eq!(outputs(".items[1:][].price"), vec!["12", "30"]);
+        assert_eq!(outputs(".items[0].tags[0][1:]"), vec![r#""ffice""#]);
+        assert_eq!(outputs(".missing.deeper"), vec!["null"]);
+        assert_eq!(outputs(".user[]"), vec!["null", r#""Ada""#]);
+        assert_eq!(outputs(".user.name[0]?, 1"), vec!["1"]);
+        assert_eq!(
+            outputs("(.user.email, .user.name[0], .user.name)?"),
+            vec!["null"]
+        );
+        assert_eq!(outputs("[.items[].price | .x?]"), vec!["[]"]);
+        assert_eq!(outputs("[..] | length"), vec!["20"]);
+    }
+
+    #[test]
+    fn construction() {
+        assert_eq!(
+            outputs("[.items[] | select(.price > 10) | .name]"),
+            vec![r#"["book","lamp"]"#]
+        );
+        assert_eq!(
+            outputs("{name: .user.name, count: (.items | length), tags: [.items[].tags[]]}"),
+            vec![r#"{"count":3,"name":"Ada","tags":["office","home","light"]}"#]
+        );
+        assert_eq!(outputs(".items | map(.price * 2)"), vec!["[3,24,60]"]);
+        assert_eq!(
+            outputs(".user | {name, (\"x\", \"y\"): 1}"),
+            vec![r#"{"name":"Ada","x":1}"#, r#"{"name":"Ada","y":1}"#]
+        );
+        assert_eq!(
+            outputs(".user | keys, has(\"email\")"),
+            vec![r#"["email","name"]"#, "true"]
+        );
+        assert_eq!(
+            outputs("[.items[].tags | add]"),
+            vec![r#"["office",null,"homelight"]"#]
+        );
+    }
+
+    #[test]
+    fn operators() {
+        let node = Node::Primary(Literal::Null);
+        let outputs = |program: &str| {
+            run(&node, program)
+                .unwrap()
+                .iter()
+                .map(|node| CompactPrint.print(node))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(outputs("1 + 2 * 3 - 4 / 2"), vec!["5"]);
+        assert_eq!(outputs("(1, 2) + (10, 20)"), vec!["11", "12", "21", "22"]);
+        assert_eq!(outputs(r#""a,b" / "," | .[1] + "c""#), vec![r#""bc""#]);
+        assert_eq!(outputs("[1, 2, 3, 2] - [2]"), vec!["[1,3]"]);
+        assert_eq!(
+            outputs(r#"{"a": 1} + {"b": 2, "a": 3}"#),
+            vec![r#"{"a":3,"b":2}"#]
+        );
+        assert_eq!(outputs(".x // \"default\""), vec![r#""default""#]);
+        assert_eq!(
+            outputs("null < false, [1, 2] < [1, 3], {} < [], 7 % 4 == 3"),
+            vec!["true", "true", "false", "true"]
+        );
+        assert_eq!(
+            outputs("true and (null, 1), false or false, (1 | not), -(2 | -.)"),
+            vec!["false", "true", "false", "false", "2"]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let node = parse_str(ORDER).unwrap();
+        let error = |program: &str| run(&node, program).unwrap_err();
+
+        assert_eq!(
+            error(".user |"),
+            FilterError::Syntax("Expected an expression".to_string(), 7)
+        );
+        assert_eq!(
+            error("frobnicate"),
+            FilterError::Syntax("Unknown function 'frobnicate'".to_string(), 0)
+        );
+        assert_eq!(
+            error("select"),
+            FilterError::Syntax("Function 'select' takes 1 argument(s)".to_string(), 0)
+        );
+        assert_eq!(
+            error(".items[0].price.x"),
+            FilterError::Runtime("Cannot index number with \"x\"".to_string())
+        );
+        assert_eq!(
+            error(".user.name + 1").to_string(),
+            "string (\"Ada\") and number (1) cannot be added"
+        );
+        assert_eq!(
+            error("1 / 0").to_string(),
+            "number (1) and number (0) cannot be divided because the divisor is zero"
+        );
+        assert_eq!(
+            run(&parse_str("-1e19").unwrap(), ". % -1")
+                .unwrap_err()
+                .to_string(),
+            "number (-10000000000000000000) and number (-1) cannot be divided because the \
+             remainder overflows"
+        );
+
+        let partial = run(&parse_str(r#"{"a": 1, "b": 2}"#).unwrap(), "(.a, .b.c)?").unwrap();
+        assert_eq!(partial, vec![Node::number(1.0)]);
+    }
+}
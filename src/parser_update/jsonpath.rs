@@ -0,0 +1,1138 @@
+use std::fmt::Display;
+
+use super::{
+    nodes::{Literal, Node, Token, Visitor},
+    pointer::to_pointer,
+    regex::Regex,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    /// Character offset into the expression.
+    pub position: usize,
+}
+
+impl std::error::Error for QueryError {}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+/// A node selected by a query.
+#[derive(Debug)]
+pub struct Match<'a> {
+    /// Normalized path such as `$['items'][0]`.
+    pub path: String,
+    /// The same location as a JSON Pointer.
+    pub pointer: String,
+    pub node: &'a Node,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone)]
+enum Filter {
+    Or(Vec<Filter>),
+    And(Vec<Filter>),
+    Not(Box<Filter>),
+    Compare(Operand, Op, Operand),
+    /// Existence of a query result, or the result of a logical function.
+    Test(Operand),
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Literal(Literal),
+    Query(Query),
+    Function(Function, Vec<Operand>),
+    /// A literal pattern for `match` or `search`, compiled when the query
+    /// is parsed. `None` if the pattern is not a valid regular expression.
+    Pattern(Option<Regex>),
+}
+
+impl Operand {
+    /// Whether RFC 9535 allows the operand on either side of a comparison.
+    fn is_comparable(&self) -> bool {
+        match self {
+            Operand::Literal(_) => true,
+            Operand::Query(query) => query.is_singular(),
+            Operand::Function(function, _) => !function.is_logical(),
+            Operand::Pattern(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Query {
+    absolute: bool,
+    segments: Vec<Segment>,
+}
+
+impl Query {
+    /// A query that selects at most one node: child segments with a single
+    /// name or index each.
+    fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| {
+            matches!(segment, Segment::Child(selectors)
+                if matches!(selectors[..], [Selector::Name(_) | Selector::Index(_)]))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Length,
+    Count,
+    Match,
+    Search,
+    Value,
+}
+
+impl Function {
+    /// `match` and `search` return a logical value, the others a value.
+    fn is_logical(self) -> bool {
+        matches!(self, Function::Match | Function::Search)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A compiled JSONPath query following RFC 9535.
+///
+/// Supports name, wildcard, index, slice and filter selectors, child and
+/// descendant segments, and the `length`, `count`, `match`, `search` and
+/// `value` functions in filters.
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    pub fn parse(expression: &str) -> Result<JsonPath, QueryError> {
+        let mut parser = PathParser {
+            chars: expression.chars().collect(),
+            position: 0,
+        };
+
+        if !parser.eat('$') {
+            return Err(parser.error("Expected '$'"));
+        }
+
+        let segments = parser.segments()?;
+
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("Unexpected character"));
+        }
+
+        Ok(JsonPath { segments })
+    }
+
+    /// Returns every matching node in document order.
+    pub fn query<'a>(&self, root: &'a Node) -> Vec<Match<'a>> {
+        select(&self.segments, root, root)
+            .into_iter()
+            .map(|(steps, node)| Match {
+                path: normalized_path(&steps),
+                pointer: to_pointer(
+                    &steps
+                        .iter()
+                        .map(|step| match step {
+                            Step::Key(key) => key.clone(),
+                            Step::Index(index) => index.to_string(),
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+                node,
+            })
+            .collect()
+    }
+}
+
+/// Parses `expression` and runs it against `root`.
+pub fn query<'a>(root: &'a Node, expression: &str) -> Result<Vec<Match<'a>>, QueryError> {
+    Ok(JsonPath::parse(expression)?.query(root))
+}
+
+type Located<'a> = (Vec<Step>, &'a Node);
+
+fn select<'a>(segments: &[Segment], start: &'a Node, root: &'a Node) -> Vec<Located<'a>> {
+    let mut nodes = vec![(vec![], start)];
+
+    for segment in segments {
+        let mut selected = vec![];
+
+        for (path, node) in nodes {
+            match segment {
+                Segment::Child(selectors) => apply(selectors, &path, node, root, &mut selected),
+                Segment::Descendant(selectors) => {
+                    let mut visited = vec![];
+                    descendants(path, node, &mut visited);
+
+                    for (path, node) in visited {
+                        apply(selectors, &path, node, root, &mut selected);
+                    }
+                }
+            }
+        }
+
+        nodes = selected;
+    }
+
+    nodes
+}
+
+fn apply<'a>(
+    selectors: &[Selector],
+    path: &[Step],
+    node: &'a Node,
+    root: &'a Node,
+    selected: &mut Vec<Located<'a>>,
+) {
+    for selector in selectors {
+        for (step, position) in node.accept(&Select { selector, root }) {
+            let mut path = path.to_vec();
+            path.push(step);
+            selected.push((path, child(node, position)));
+        }
+    }
+}
+
+/// The node itself followed by all of its descendants, depth first.
+fn descendants<'a>(path: Vec<Step>, node: &'a Node, visited: &mut Vec<Located<'a>>) {
+    visited.push((path.clone(), node));
+
+    for (step, position) in node.accept(&Select {
+        selector: &Selector::Wildcard,
+        root: node,
+    }) {
+        let mut path = path.clone();
+        path.push(step);
+        descendants(path, child(node, position), visited);
+    }
+}
+
+/// The child at `position` as returned by [`Select`]: the value of the
+/// property at that position in an object, or the element of a list.
+fn child(node: &Node, position: usize) -> &Node {
+    match node {
+        Node::Object(_, properties, _) => match &properties[position] {
+            Node::Property(_, _, value) => value,
+            property => property,
+        },
+        Node::List(_, nodes, _) => &nodes[position],
+        _ => node,
+    }
+}
+
+/// Applies one selector to a node, returning the step to each selected
+/// child along with its position among the node's children.
+struct Select<'s, 'r> {
+    selector: &'s Selector,
+    root: &'r Node,
+}
+
+impl Visitor<Vec<(Step, usize)>> for Select<'_, '_> {
+    fn visit_primary(&self, _value: &Literal) -> Vec<(Step, usize)> {
+        vec![]
+    }
+
+    fn visit_object(
+        &self,
+        _left: &Token,
+        properties: &[Node],
+        _right: &Token,
+    ) -> Vec<(Step, usize)> {
+        let members =
+            properties
+                .iter()
+                .enumerate()
+                .filter_map(|(position, property)| match property {
+                    Node::Property(key, _, value) => {
+                        Some((key.literal.to_string(), &**value, position))
+                    }
+                    _ => None,
+                });
+
+        match self.selector {
+            Selector::Name(name) => members
+                .filter(|(key, _, _)| key == name)
+                .map(|(key, _, position)| (Step::Key(key), position))
+                .collect(),
+            Selector::Wildcard => members
+                .map(|(key, _, position)| (Step::Key(key), position))
+                .collect(),
+            Selector::Filter(filter) => members
+                .filter(|(_, value, _)| filter.test(value, self.root))
+                .map(|(key, _, position)| (Step::Key(key), position))
+                .collect(),
+            Selector::Index(_) | Selector::Slice(..) => vec![],
+        }
+    }
+
+    fn visit_property(&self, _key: &Token, _colon: &Token, _value: &Node) -> Vec<(Step, usize)> {
+        vec![]
+    }
+
+    fn visit_list(&self, _left: &Token, nodes: &[Node], _right: &Token) -> Vec<(Step, usize)> {
+        let len = nodes.len() as i64;
+
+        let indices = match self.selector {
+            Selector::Name(_) => vec![],
+            Selector::Wildcard => (0..len).collect(),
+            Selector::Index(index) => {
+                let index = if *index < 0 { len + index } else { *index };
+
+                if (0..len).contains(&index) {
+                    vec![index]
+                } else {
+                    vec![]
+                }
+            }
+            Selector::Slice(start, end, step) => slice(len, *start, *end, step.unwrap_or(1)),
+            Selector::Filter(filter) => (0..len)
+                .filter(|index| filter.test(&nodes[*index as usize], self.root))
+                .collect(),
+        };
+
+        indices
+            .into_iter()
+            .map(|index| (Step::Index(index as usize), index as usize))
+            .collect()
+    }
+
+    fn visit_error(&self, _token: &Token) -> Vec<(Step, usize)> {
+        vec![]
+    }
+}
+
+/// Indices selected by `[start:end:step]` on an array of length `len`.
+fn slice(len: i64, start: Option<i64>, end: Option<i64>, step: i64) -> Vec<i64> {
+    let normalize = |index: i64| if index < 0 { len + index } else { index };
+
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+
+        (lower..upper).step_by(step as usize).collect()
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = end.map_or(-1, |end| normalize(end).clamp(-1, len - 1));
+
+        let mut indices = vec![];
+        let mut index = upper;
+
+        while index > lower {
+            indices.push(index);
+            index += step;
+        }
+
+        indices
+    } else {
+        vec![]
+    }
+}
+
+/// Result of a filter operand.
+enum Value<'a> {
+    Nothing,
+    Node(&'a Node),
+    Owned(Node),
+    Nodes(Vec<&'a Node>),
+    Logical(bool),
+}
+
+impl Value<'_> {
+    /// The single value a comparison sees. Node lists of any other length
+    /// and logical results compare as nothing.
+    fn as_node(&self) -> Option<&Node> {
+        match self {
+            Value::Node(node) => Some(node),
+            Value::Owned(node) => Some(node),
+            Value::Nodes(nodes) if nodes.len() == 1 => Some(nodes[0]),
+            _ => None,
+        }
+    }
+}
+
+impl Filter {
+    fn test(&self, current: &Node, root: &Node) -> bool {
+        match self {
+            Filter::Or(filters) => filters.iter().any(|filter| filter.test(current, root)),
+            Filter::And(filters) => filters.iter().all(|filter| filter.test(current, root)),
+            Filter::Not(filter) => !filter.test(current, root),
+            Filter::Compare(left, op, right) => {
+                let left = left.evaluate(current, root);
+                let right = right.evaluate(current, root);
+
+                compare(left.as_node(), *op, right.as_node())
+            }
+            Filter::Test(operand) => match operand.evaluate(current, root) {
+                Value::Nothing => false,
+                Value::Nodes(nodes) => !nodes.is_empty(),
+                Value::Logical(value) => value,
+                Value::Node(_) | Value::Owned(_) => true,
+            },
+        }
+    }
+}
+
+impl Operand {
+    fn evaluate<'a>(&self, current: &'a Node, root: &'a Node) -> Value<'a> {
+        match self {
+            Operand::Literal(literal) => Value::Owned(Node::Primary(literal.clone())),
+            Operand::Query(query) => {
+                let start = if query.absolute { root } else { current };
+
+                Value::Nodes(
+                    select(&query.segments, start, root)
+                        .into_iter()
+                        .map(|(_, node)| node)
+                        .collect(),
+                )
+            }
+            Operand::Function(function @ (Function::Match | Function::Search), arguments) => {
+                let text = arguments[0].evaluate(current, root);
+                let Some(Node::Primary(Literal::String(text))) = text.as_node() else {
+                    return Value::Logical(false);
+                };
+
+                let matched = match &arguments[1] {
+                    Operand::Pattern(regex) => regex
+                        .as_ref()
+                        .is_some_and(|regex| matches(*function, regex, text)),
+                    pattern => match pattern.evaluate(current, root).as_node() {
+                        Some(Node::Primary(Literal::String(pattern))) => {
+                            Regex::new(pattern).is_ok_and(|regex| matches(*function, &regex, text))
+                        }
+                        _ => false,
+                    },
+                };
+
+                Value::Logical(matched)
+            }
+            Operand::Function(function, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.evaluate(current, root))
+                    .collect::<Vec<_>>();
+
+                call(*function, &arguments)
+            }
+            Operand::Pattern(_) => Value::Nothing,
+        }
+    }
+}
+
+/// Runs `match` or `search`. Functions cannot fail, so a match that runs
+/// out of steps counts as no match.
+fn matches(function: Function, regex: &Regex, text: &str) -> bool {
+    if function == Function::Match {
+        regex.is_full_match(text).unwrap_or(false)
+    } else {
+        regex.is_match(text).unwrap_or(false)
+    }
+}
+
+fn call<'a>(function: Function, arguments: &[Value<'a>]) -> Value<'a> {
+    let number = |value: usize| Value::Owned(Node::Primary(Literal::Number(value as f64)));
+
+    match function {
+        Function::Length => match arguments[0].as_node() {
+            Some(Node::Primary(Literal::String(value))) => number(value.chars().count()),
+            Some(Node::List(_, nodes, _)) => number(nodes.len()),
+            Some(Node::Object(_, properties, _)) => number(properties.len()),
+            _ => Value::Nothing,
+        },
+        Function::Count => match &arguments[0] {
+            Value::Nodes(nodes) => number(nodes.len()),
+            _ => Value::Nothing,
+        },
+        // Evaluated by `Operand::evaluate`, which reuses a literal pattern
+        // compiled with the query.
+        Function::Match | Function::Search => Value::Logical(false),
+        Function::Value => match &arguments[0] {
+            Value::Nodes(nodes) if nodes.len() == 1 => Value::Node(nodes[0]),
+            _ => Value::Nothing,
+        },
+    }
+}
+
+fn compare(left: Option<&Node>, op: Op, right: Option<&Node>) -> bool {
+    match op {
+        Op::Eq => left == right,
+        Op::Ne => left != right,
+        Op::Lt => less(left, right),
+        Op::Gt => less(right, left),
+        Op::Le => less(left, right) || left == right,
+        Op::Ge => less(right, left) || left == right,
+    }
+}
+
+fn less(left: Option<&Node>, right: Option<&Node>) -> bool {
+    match (left, right) {
+        (
+            Some(Node::Primary(Literal::Number(left))),
+            Some(Node::Primary(Literal::Number(right))),
+        ) => left < right,
+        (
+            Some(Node::Primary(Literal::String(left))),
+            Some(Node::Primary(Literal::String(right))),
+        ) => left < right,
+        _ => false,
+    }
+}
+
+fn normalized_path(steps: &[Step]) -> String {
+    let mut path = "$".to_string();
+
+    for step in steps {
+        match step {
+            Step::Index(index) => path += &format!("[{}]", index),
+            Step::Key(key) => {
+                path += "['";
+
+                for character in key.chars() {
+                    match character {
+                        '\'' => path += "\\'",
+                        '\\' => path += "\\\\",
+                        '\u{8}' => path += "\\b",
+                        '\u{c}' => path += "\\f",
+                        '\n' => path += "\\n",
+                        '\r' => path += "\\r",
+                        '\t' => path += "\\t",
+                        '\u{0}'..='\u{1f}' => path += &format!("\\u{:04x}", character as u32),
+                        _ => path.push(character),
+                    }
+                }
+
+                path += "']";
+            }
+        }
+    }
+
+    path
+}
+
+struct PathParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl PathParser {
+    fn segments(&mut self) -> Result<Vec<Segment>, QueryError> {
+        let mut segments = vec![];
+
+        loop {
+            let start = self.position;
+            self.skip_whitespace();
+
+            match (self.peek(), self.chars.get(self.position + 1)) {
+                (Some('.'), Some('.')) => {
+                    self.position += 2;
+
+                    let selectors = match self.peek() {
+                        Some('[') => self.bracketed()?,
+                        Some('*') => {
+                            self.position += 1;
+                            vec![Selector::Wildcard]
+                        }
+                        _ => vec![Selector::Name(self.member_name()?)],
+                    };
+
+                    segments.push(Segment::Descendant(selectors));
+                }
+                (Some('.'), _) => {
+                    self.position += 1;
+
+                    let selector = if self.eat('*') {
+                        Selector::Wildcard
+                    } else {
+                        Selector::Name(self.member_name()?)
+                    };
+
+                    segments.push(Segment::Child(vec![selector]));
+                }
+                (Some('['), _) => segments.push(Segment::Child(self.bracketed()?)),
+                _ => {
+                    self.position = start;
+                    return Ok(segments);
+                }
+            }
+        }
+    }
+
+    fn member_name(&mut self) -> Result<String, QueryError> {
+        let start = self.position;
+
+        while let Some(character) = self.peek() {
+            let allowed = character.is_ascii_alphabetic()
+                || character == '_'
+                || !character.is_ascii()
+                || (self.position > start && character.is_ascii_digit());
+
+            if !allowed {
+                break;
+            }
+
+            self.position += 1;
+        }
+
+        if self.position == start {
+            return Err(self.error("Expected a member name"));
+        }
+
+        Ok(self.chars[start..self.position].iter().collect())
+    }
+
+    fn bracketed(&mut self) -> Result<Vec<Selector>, QueryError> {
+        self.expect('[')?;
+        let mut selectors = vec![];
+
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+
+            if !self.eat(',') {
+                self.expect(']')?;
+                return Ok(selectors);
+            }
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, QueryError> {
+        match self.peek() {
+            Some(quote @ ('\'' | '"')) => Ok(Selector::Name(self.string(quote)?)),
+            Some('*') => {
+                self.position += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.position += 1;
+                Ok(Selector::Filter(self.or()?))
+            }
+            _ => {
+                let start = self.optional_integer()?;
+                self.skip_whitespace();
+
+                if !self.eat(':') {
+                    return start
+                        .map(Selector::Index)
+                        .ok_or_else(|| self.error("Expected a selector"));
+                }
+
+                self.skip_whitespace();
+                let end = self.optional_integer()?;
+                self.skip_whitespace();
+
+                let step = if self.eat(':') {
+                    self.skip_whitespace();
+                    self.optional_integer()?
+                } else {
+                    None
+                };
+
+                Ok(Selector::Slice(start, end, step))
+            }
+        }
+    }
+
+    fn or(&mut self) -> Result<Filter, QueryError> {
+        let mut filters = vec![self.and()?];
+
+        while self.eat_operator("||") {
+            filters.push(self.and()?);
+        }
+
+        Ok(match filters.len() {
+            1 => filters.remove(0),
+            _ => Filter::Or(filters),
+        })
+    }
+
+    fn and(&mut self) -> Result<Filter, QueryError> {
+        let mut filters = vec![self.basic()?];
+
+        while self.eat_operator("&&") {
+            filters.push(self.basic()?);
+        }
+
+        Ok(match filters.len() {
+            1 => filters.remove(0),
+            _ => Filter::And(filters),
+        })
+    }
+
+    fn basic(&mut self) -> Result<Filter, QueryError> {
+        self.skip_whitespace();
+
+        if self.eat('!') {
+            self.skip_whitespace();
+
+            let negated = if self.peek() == Some('(') {
+                self.parenthesized()?
+            } else {
+                self.test()?
+            };
+
+            return Ok(Filter::Not(Box::new(negated)));
+        }
+
+        if self.peek() == Some('(') {
+            return self.parenthesized();
+        }
+
+        let position = self.position;
+        let left = self.operand()?;
+        self.skip_whitespace();
+
+        let Some(op) = self.operator() else {
+            self.position = position;
+            return self.test();
+        };
+
+        self.skip_whitespace();
+        let right_position = self.position;
+        let right = self.operand()?;
+
+        for (operand, start) in [(&left, position), (&right, right_position)] {
+            if !operand.is_comparable() {
+                self.position = start;
+                return Err(self
+                    .error("Only literals, singular queries and value functions can be compared"));
+            }
+        }
+
+        Ok(Filter::Compare(left, op, right))
+    }
+
+    fn parenthesized(&mut self) -> Result<Filter, QueryError> {
+        self.expect('(')?;
+        let filter = self.or()?;
+        self.skip_whitespace();
+        self.expect(')')?;
+
+        Ok(filter)
+    }
+
+    fn test(&mut self) -> Result<Filter, QueryError> {
+        match self.operand()? {
+            Operand::Query(query) => Ok(Filter::Test(Operand::Query(query))),
+            Operand::Function(function, arguments) if function.is_logical() => {
+                Ok(Filter::Test(Operand::Function(function, arguments)))
+            }
+            _ => Err(self.error("Expected a comparison")),
+        }
+    }
+
+    fn operator(&mut self) -> Option<Op> {
+        [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ]
+        .into_iter()
+        .find(|(text, _)| self.eat_str(text))
+        .map(|(_, op)| op)
+    }
+
+    fn operand(&mut self) -> Result<Operand, QueryError> {
+        match self.peek() {
+            Some(root @ ('@' | '$')) => {
+                self.position += 1;
+
+                Ok(Operand::Query(Query {
+                    absolute: root == '$',
+                    segments: self.segments()?,
+                }))
+            }
+            Some(quote @ ('\'' | '"')) => {
+                Ok(Operand::Literal(Literal::String(self.string(quote)?)))
+            }
+            Some('-' | '0'..='9') => Ok(Operand::Literal(Literal::Number(self.number()?))),
+            Some('a'..='z') => self.word(),
+            _ => Err(self.error("Expected a value")),
+        }
+    }
+
+    /// A literal `true`, `false` or `null`, or a function call.
+    fn word(&mut self) -> Result<Operand, QueryError> {
+        let start = self.position;
+
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            self.position += 1;
+        }
+
+        let word = self.chars[start..self.position].iter().collect::<String>();
+
+        let (function, arity) = match word.as_str() {
+            "true" => return Ok(Operand::Literal(Literal::Bool(true))),
+            "false" => return Ok(Operand::Literal(Literal::Bool(false))),
+            "null" => return Ok(Operand::Literal(Literal::Null)),
+            "length" => (Function::Length, 1),
+            "count" => (Function::Count, 1),
+            "match" => (Function::Match, 2),
+            "search" => (Function::Search, 2),
+            "value" => (Function::Value, 1),
+            _ => {
+                self.position = start;
+                return Err(self.error(&format!("Unknown function '{}'", word)));
+            }
+        };
+
+        self.expect('(')?;
+        let mut arguments = vec![];
+
+        loop {
+            self.skip_whitespace();
+
+            if arguments.is_empty() && self.peek() == Some(')') {
+                break;
+            }
+
+            arguments.push(self.operand()?);
+            self.skip_whitespace();
+
+            if !self.eat(',') {
+                break;
+            }
+        }
+
+        self.expect(')')?;
+
+        if arguments.len() != arity {
+            self.position = start;
+            return Err(self.error(&format!("Function '{}' takes {} argument(s)", word, arity)));
+        }
+
+        if let [_, Operand::Literal(Literal::String(pattern))] = &arguments[..] {
+            if matches!(function, Function::Match | Function::Search) {
+                arguments[1] = Operand::Pattern(Regex::new(pattern).ok());
+            }
+        }
+
+        Ok(Operand::Function(function, arguments))
+    }
+
+    fn string(&mut self, quote: char) -> Result<String, QueryError> {
+        self.expect(quote)?;
+        let mut value = String::new();
+
+        loop {
+            let character = self
+                .next()
+                .ok_or_else(|| self.error("Unterminated string"))?;
+
+            match character {
+                _ if character == quote => return Ok(value),
+                '\\' => {
+                    let escaped = match self.next() {
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('/') => '/',
+                        Some('\\') => '\\',
+                        Some(escaped) if escaped == quote => quote,
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error("Invalid escape sequence")),
+                    };
+
+                    value.push(escaped);
+                }
+                '\u{0}'..='\u{1f}' => return Err(self.error("Unescaped control character")),
+                _ => value.push(character),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, QueryError> {
+        let high = self.hex_quad()?;
+
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("Invalid unicode escape"));
+        }
+
+        if !self.eat_str("\\u") {
+            return Err(self.error("Unpaired surrogate in unicode escape"));
+        }
+
+        let low = self.hex_quad()?;
+
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("Unpaired surrogate in unicode escape"));
+        }
+
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+            .ok_or_else(|| self.error("Invalid unicode escape"))
+    }
+
+    fn hex_quad(&mut self) -> Result<u32, QueryError> {
+        (0..4).try_fold(0, |value, _| {
+            self.next()
+                .and_then(|digit| digit.to_digit(16))
+                .map(|digit| value * 16 + digit)
+                .ok_or_else(|| self.error("Invalid unicode escape"))
+        })
+    }
+
+    /// An index or slice bound: no leading zeros, no `-0`, within the
+    /// interoperable range of ±(2^53 - 1).
+    fn optional_integer(&mut self) -> Result<Option<i64>, QueryError> {
+        if !matches!(self.peek(), Some('-' | '0'..='9')) {
+            return Ok(None);
+        }
+
+        let start = self.position;
+        self.eat('-');
+        let digits = self.position;
+
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+
+        let text = self.chars[start..self.position].iter().collect::<String>();
+        let leading_zero = self.chars.get(digits) == Some(&'0') && self.position - digits > 1;
+
+        match text.parse::<i64>() {
+            Ok(value) if !leading_zero && text != "-0" && value.abs() < (1 << 53) => {
+                Ok(Some(value))
+            }
+            _ => {
+                self.position = start;
+                Err(self.error("Invalid integer"))
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, QueryError> {
+        let start = self.position;
+
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            self.position += 1;
+        }
+
+        let text = self.chars[start..self.position].iter().collect::<String>();
+
+        text.parse::<f64>().map_err(|_| {
+            self.position = start;
+            self.error("Invalid number")
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn eat_operator(&mut self, operator: &str) -> bool {
+        let start = self.position;
+        self.skip_whitespace();
+
+        if self.eat_str(operator) {
+            return true;
+        }
+
+        self.position = start;
+        false
+    }
+
+    fn eat_str(&mut self, text: &str) -> bool {
+        let len = text.chars().count();
+        let matches = self.chars.len() >= self.position + len
+            && self.chars[self.position..self.position + len]
+                .iter()
+                .copied()
+                .eq(text.chars());
+
+        if matches {
+            self.position += len;
+        }
+
+        matches
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), QueryError> {
+        if self.eat(expected) {
+            return Ok(());
+        }
+
+        Err(self.error(&format!("Expected '{}'", expected)))
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.position += 1;
+        Some(character)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn error(&self, message: &str) -> QueryError {
+        QueryError {
+            message: message.to_string(),
+            position: self.position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod jsonpath_tests {
+    use super::*;
+    use crate::parser_update::{nodes::parse_str, printer::CompactPrint};
+
+    const STORE: &str = r#"{
+        "items": [
+            {"id": 1, "name": "pen", "price": 1.5, "tags": ["office"]},
+            {"id": 2, "name": "book", "price": 12, "tags": []},
+            {"id": 3, "name": "lamp", "price": 30}
+        ],
+        "users": [
+            {"id": "u1", "name": "Ada", "age": 36},
+            {"id": "u2", "name": "Bob", "age": 25}
+        ],
+        "o'k": {"a\\b": true}
+    }"#;
+
+    fn values(expression: &str) -> Vec<String> {
+        let root = parse_str(STORE).unwrap();
+
+        query(&root, expression)
+            .unwrap()
+            .iter()
+            .map(|found| CompactPrint.print(found.node))
+            .collect()
+    }
+
+    #[test]
+    fn selectors() {
+        assert_eq!(values("$.items[*].price"), vec!["1.5", "12", "30"]);
+        assert_eq!(values("$.items[-1].name"), vec![r#""lamp""#]);
+        assert_eq!(values("$.items[0:2].id"), vec!["1", "2"]);
+        assert_eq!(values("$.items[::-1].id"), vec!["3", "2", "1"]);
+        assert_eq!(
+            values("$.items[0, 2]['name', 'id']"),
+            vec![r#""pen""#, "1", r#""lamp""#, "3"]
+        );
+        assert_eq!(values("$..id"), vec!["1", "2", "3", r#""u1""#, r#""u2""#]);
+        assert_eq!(values("$.items[5]"), Vec::<String>::new());
+        assert_eq!(values("$.users.name"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(values("$.users[?(@.age > 30)].name"), vec![r#""Ada""#]);
+        assert_eq!(
+            values("$.users[?@.age <= 25 || @.name == 'Ada'].id"),
+            vec![r#""u1""#, r#""u2""#]
+        );
+        assert_eq!(values("$.items[?@.tags].id"), vec!["1", "2"]);
+        assert_eq!(values("$.items[?!@.tags].id"), vec!["3"]);
+        assert_eq!(values("$.items[?length(@.tags) == 0].id"), vec!["2"]);
+        assert_eq!(values("$.items[?count(@.*) == 4].id"), vec!["1", "2"]);
+        assert_eq!(values("$.items[?match(@.name, 'p.*')].id"), vec!["1"]);
+        assert_eq!(values("$.items[?search(@.name, 'o')].id"), vec!["2"]);
+        assert_eq!(
+            values("$.items[?match(@.name, '(')].id"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            values("$.items[?search(@.name, $.items[0].name)].id"),
+            vec!["1"]
+        );
+        assert_eq!(values("$.items[?@.price > $.items[1].price].id"), vec!["3"]);
+        assert_eq!(values("$.items[?@.tags == $.items[0].tags].id"), vec!["1"]);
+    }
+
+    #[test]
+    fn paths() {
+        let root = parse_str(STORE).unwrap();
+        let found = query(&root, "$..[?@ == true]").unwrap();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, r#"$['o\'k']['a\\b']"#);
+        assert_eq!(found[0].pointer, "/o'k/a\\b");
+
+        let found = query(&root, "$.users[1]").unwrap();
+        assert_eq!(found[0].path, "$['users'][1]");
+    }
+
+    #[test]
+    fn errors() {
+        let error = |expression: &str| JsonPath::parse(expression).unwrap_err();
+
+        assert_eq!(error("items").message, "Expected '$'");
+        assert_eq!(error("$.items[01]").position, 8);
+        assert_eq!(error("$.items[?@.a ==]").message, "Expected a value");
+        assert_eq!(error("$[?foo(@)]").message, "Unknown function 'foo'");
+        assert_eq!(
+            error("$[?length(@, 1)]").message,
+            "Function 'length' takes 1 argument(s)"
+        );
+        assert_eq!(error("$.a b").message, "Unexpected character");
+        assert_eq!(error("$[?length(@)]").message, "Expected a comparison");
+        assert_eq!(error("$[?1]").message, "Expected a comparison");
+
+        let comparison = error("$[?@.* == 1]");
+        assert_eq!(
+            comparison.message,
+            "Only literals, singular queries and value functions can be compared"
+        );
+        assert_eq!(comparison.position, 3);
+        assert_eq!(error("$[?1 == @..a]").position, 8);
+        assert_eq!(error("$[?1 == @['a', 'b']]").position, 8);
+        assert_eq!(error("$[?match(@, 'a') == true]").position, 3);
+    }
+}
@@ -0,0 +1,898 @@
+use std::io::Read;
+
+use super::{
+    error::{ErrorCode, ParseError, Span},
+    limits::Limits,
+    nodes::{Literal, Token, TokenType},
+};
+
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Which flavour of JSON the lexer and parser accept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /// Strict RFC 8259 JSON.
+    #[default]
+    Json,
+    /// JSON with `//` and `/* */` comments and trailing commas.
+    Jsonc,
+    /// JSON5: everything in JSONC plus identifier keys, single quoted and
+    /// multi-line strings, hex numbers, `Infinity` and `NaN`.
+    Json5,
+}
+
+impl Dialect {
+    pub fn allows_comments(&self) -> bool {
+        *self != Dialect::Json
+    }
+
+    pub fn allows_trailing_commas(&self) -> bool {
+        *self != Dialect::Json
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    Line,
+    Block,
+}
+
+/// A comment skipped by the lexer, `text` excludes the delimiters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub kind: CommentKind,
+    pub text: String,
+    pub span: Span,
+}
+
+/// Turns JSON text into [`Token`]s.
+///
+/// The lexer pulls bytes from any [`Read`] through a fixed size buffer, so
+/// it can be driven one token at a time over input of any length.
+pub struct Lexer<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    position: usize,
+    filled: usize,
+    offset: usize,
+    line: usize,
+    column: usize,
+    dialect: Dialect,
+    limits: Limits,
+    comments: Vec<Comment>,
+}
+
+impl<R: Read> Lexer<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_dialect(reader, Dialect::Json)
+    }
+
+    pub fn with_dialect(reader: R, dialect: Dialect) -> Self {
+        Self {
+            reader,
+            buffer: vec![0; BUFFER_SIZE],
+            position: 0,
+            filled: 0,
+            offset: 0,
+            line: 1,
+            column: 1,
+            dialect,
+            limits: Limits::default(),
+            comments: vec![],
+        }
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Hands over the comments skipped so far.
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        std::mem::take(&mut self.comments)
+    }
+
+    /// Reads the whole input, the returned tokens always end with `Eof`.
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, ParseError> {
+        let mut tokens = vec![];
+
+        loop {
+            let token = self.next_token()?;
+            let is_eof = token.token_type == TokenType::Eof;
+            tokens.push(token);
+
+            if is_eof {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    /// Reads the whole input without stopping at invalid tokens. Each run
+    /// of unreadable input is reported once and skipped.
+    pub fn tokenize_recovering(&mut self) -> (Vec<Token>, Vec<ParseError>) {
+        let mut tokens = vec![];
+        let mut errors = vec![];
+        let mut in_error = false;
+
+        loop {
+            let offset = self.offset;
+
+            match self.next_token() {
+                Ok(token) => {
+                    let is_eof = token.token_type == TokenType::Eof;
+                    tokens.push(token);
+                    in_error = false;
+
+                    if is_eof {
+                        return (tokens, errors);
+                    }
+                }
+                Err(error) => {
+                    if !in_error {
+                        tokens.push(Token::with_span(
+                            TokenType::Invalid,
+                            Literal::String(String::new()),
+                            error.span,
+                        ));
+                        errors.push(error);
+                    }
+
+                    in_error = true;
+
+                    if self.offset == offset && self.skip_char().is_err() {
+                        tokens.push(Token::with_span(
+                            TokenType::Eof,
+                            Literal::String(String::new()),
+                            Span::new(self.offset, self.offset, self.line, self.column),
+                        ));
+                        return (tokens, errors);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn next_token(&mut self) -> Result<Token, ParseError> {
+        self.skip_whitespace()?;
+
+        let (start, line, column) = (self.offset, self.line, self.column);
+
+        let Some(byte) = self.peek()? else {
+            return Ok(Token::with_span(
+                TokenType::Eof,
+                Literal::String(String::new()),
+                Span::new(start, start, line, column),
+            ));
+        };
+
+        let (token_type, literal) = match byte {
+            b'{' => self.punctuation(TokenType::LeftBrace, "{")?,
+            b'}' => self.punctuation(TokenType::RightBrace, "}")?,
+            b'[' => self.punctuation(TokenType::LeftBracket, "[")?,
+            b']' => self.punctuation(TokenType::RightBracket, "]")?,
+            b':' => self.punctuation(TokenType::Colon, ":")?,
+            b',' => self.punctuation(TokenType::Comma, ",")?,
+            b'"' => (TokenType::String, self.string()?),
+            b'\'' if self.is_json5() => (TokenType::String, self.string()?),
+            b'-' | b'0'..=b'9' => (TokenType::Number, self.number()?),
+            b'+' | b'.' if self.is_json5() => (TokenType::Number, self.number()?),
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' | b'$' => self.word()?,
+            0x80.. if self.is_json5() && self.peek_utf8()?.is_some_and(is_identifier_char) => {
+                self.word()?
+            }
+            _ => {
+                let character = self.peek_char()?;
+                let error = self.error_here(
+                    ErrorCode::UnexpectedCharacter,
+                    &format!("Unexpected character '{}'", character),
+                );
+
+                return Err(match character {
+                    '\'' => error.with_hint("JSON strings use double quotes, not single quotes"),
+                    _ => error,
+                });
+            }
+        };
+
+        Ok(Token::with_span(
+            token_type,
+            literal,
+            Span::new(start, self.offset, line, column),
+        ))
+    }
+
+    fn punctuation(
+        &mut self,
+        token_type: TokenType,
+        lexeme: &str,
+    ) -> Result<(TokenType, Literal), ParseError> {
+        self.advance()?;
+        Ok((token_type, Literal::String(lexeme.to_string())))
+    }
+
+    fn string(&mut self) -> Result<Literal, ParseError> {
+        let quote = self.advance()?;
+
+        let mut bytes = vec![];
+
+        loop {
+            let Some(byte) = self.advance()? else {
+                return Err(self.error_here(ErrorCode::UnterminatedString, "Unterminated string"));
+            };
+
+            match byte {
+                _ if Some(byte) == quote => break,
+                b'\\' => self.escape(&mut bytes)?,
+                b'\n' | b'\r' if self.is_json5() => {
+                    return Err(self.error_here(
+                        ErrorCode::ControlCharacter,
+                        "Unescaped line break in string",
+                    ))
+                }
+                0x00..=0x1f if !self.is_json5() => {
+                    return Err(self.error_here(
+                        ErrorCode::ControlCharacter,
+                        "Unescaped control character in string",
+                    ))
+                }
+                _ => bytes.push(byte),
+            }
+
+            if bytes.len() > self.limits.max_string_length {
+                return Err(self.error_here(
+                    ErrorCode::LimitExceeded,
+                    &format!("String longer than {} bytes", self.limits.max_string_length),
+                ));
+            }
+        }
+
+        String::from_utf8(bytes)
+            .map(Literal::String)
+            .map_err(|_| self.error_here(ErrorCode::InvalidUtf8, "Invalid UTF-8 in string"))
+    }
+
+    fn escape(&mut self, bytes: &mut Vec<u8>) -> Result<(), ParseError> {
+        let Some(character) = self.peek_utf8()? else {
+            return Err(self.error_here(ErrorCode::InvalidEscape, "Invalid escape sequence"));
+        };
+
+        for _ in 0..character.len_utf8() {
+            self.advance()?;
+        }
+
+        let escaped = match character {
+            '"' => '"',
+            '\\' => '\\',
+            '/' => '/',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => self.unicode_escape()?,
+            _ if !self.is_json5() => {
+                return Err(self.error_here(ErrorCode::InvalidEscape, "Invalid escape sequence"))
+            }
+            'v' => '\u{b}',
+            '0' if !matches!(self.peek()?, Some(b'0'..=b'9')) => '\0',
+            'x' => {
+                let value = self.hex_digits(2)?;
+                char::from_u32(value).ok_or_else(|| {
+                    self.error_here(ErrorCode::InvalidEscape, "Invalid escape sequence")
+                })?
+            }
+            '\r' => {
+                if self.peek()? == Some(b'\n') {
+                    self.advance()?;
+                }
+
+                return Ok(());
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => return Ok(()),
+            '0'..='9' => {
+                return Err(self.error_here(ErrorCode::InvalidEscape, "Invalid escape sequence"))
+            }
+            _ => character,
+        };
+
+        let mut encoded = [0; 4];
+        bytes.extend_from_slice(escaped.encode_utf8(&mut encoded).as_bytes());
+
+        Ok(())
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        let high = self.hex_quad()?;
+
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| {
+                self.error_here(ErrorCode::InvalidUnicodeEscape, "Invalid unicode escape")
+            });
+        }
+
+        if self.advance()? != Some(b'\\') || self.advance()? != Some(b'u') {
+            return Err(self.error_here(
+                ErrorCode::InvalidUnicodeEscape,
+                "Unpaired surrogate in unicode escape",
+            ));
+        }
+
+        let low = self.hex_quad()?;
+
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error_here(
+                ErrorCode::InvalidUnicodeEscape,
+                "Unpaired surrogate in unicode escape",
+            ));
+        }
+
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)).ok_or_else(|| {
+            self.error_here(ErrorCode::InvalidUnicodeEscape, "Invalid unicode escape")
+        })
+    }
+
+    fn hex_quad(&mut self) -> Result<u32, ParseError> {
+        self.hex_digits(4)
+            .map_err(|_| self.error_here(ErrorCode::InvalidUnicodeEscape, "Invalid unicode escape"))
+    }
+
+    fn hex_digits(&mut self, count: usize) -> Result<u32, ParseError> {
+        let mut value = 0;
+
+        for _ in 0..count {
+            let digit = self
+                .advance()?
+                .and_then(|byte| (byte as char).to_digit(16))
+                .ok_or_else(|| {
+                    self.error_here(ErrorCode::InvalidEscape, "Invalid escape sequence")
+                })?;
+
+            value = value * 16 + digit;
+        }
+
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Literal, ParseError> {
+        let mut lexeme = String::new();
+
+        match self.peek()? {
+            Some(b'-') => self.push_advance(&mut lexeme)?,
+            Some(b'+') if self.is_json5() => self.push_advance(&mut lexeme)?,
+            _ => {}
+        }
+
+        let integer = match self.peek()? {
+            Some(b'0') => {
+                self.push_advance(&mut lexeme)?;
+
+                if self.is_json5() && matches!(self.peek()?, Some(b'x' | b'X')) {
+                    return self.hex_number(lexeme.starts_with('-'));
+                }
+
+                true
+            }
+            Some(b'1'..=b'9') => {
+                self.digits(&mut lexeme)?;
+                true
+            }
+            Some(b'.') if self.is_json5() => false,
+            Some(b'I' | b'N') if self.is_json5() => return self.named_number(&lexeme),
+            _ => return Err(self.error_here(ErrorCode::InvalidNumber, "Invalid number")),
+        };
+
+        if self.peek()? == Some(b'.') {
+            self.push_advance(&mut lexeme)?;
+
+            if self.is_json5() && integer {
+                self.digits(&mut lexeme)?;
+            } else {
+                self.required_digits(&mut lexeme)?;
+            }
+        }
+
+        if matches!(self.peek()?, Some(b'e' | b'E')) {
+            self.push_advance(&mut lexeme)?;
+
+            if matches!(self.peek()?, Some(b'+' | b'-')) {
+                self.push_advance(&mut lexeme)?;
+            }
+
+            self.required_digits(&mut lexeme)?;
+        }
+
+        if matches!(self.peek()?, Some(b'0'..=b'9')) {
+            return Err(self.error_here(ErrorCode::InvalidNumber, "Invalid number"));
+        }
+
+        lexeme
+            .parse()
+            .map(Literal::Number)
+            .map_err(|_| self.error_here(ErrorCode::InvalidNumber, "Invalid number"))
+    }
+
+    fn hex_number(&mut self, negative: bool) -> Result<Literal, ParseError> {
+        self.advance()?;
+
+        let mut lexeme = String::new();
+
+        while self.peek()?.is_some_and(|byte| byte.is_ascii_hexdigit()) {
+            self.push_advance(&mut lexeme)?;
+        }
+
+        let value = u64::from_str_radix(&lexeme, 16)
+            .map_err(|_| self.error_here(ErrorCode::InvalidNumber, "Invalid hexadecimal number"))?
+            as f64;
+
+        Ok(Literal::Number(if negative { -value } else { value }))
+    }
+
+    fn named_number(&mut self, sign: &str) -> Result<Literal, ParseError> {
+        let (start, line, column) = (self.offset, self.line, self.column);
+        let (token_type, literal) = self.word()?;
+
+        match Token::new(token_type, literal).named_number() {
+            Some(Literal::Number(value)) if sign == "-" => Ok(Literal::Number(-value)),
+            Some(literal) => Ok(literal),
+            None => Err(ParseError::new(
+                ErrorCode::InvalidNumber,
+                "Invalid number",
+                Span::new(start, self.offset, line, column),
+            )),
+        }
+    }
+
+    fn required_digits(&mut self, lexeme: &mut String) -> Result<(), ParseError> {
+        if !matches!(self.peek()?, Some(b'0'..=b'9')) {
+            return Err(self.error_here(ErrorCode::InvalidNumber, "Invalid number"));
+        }
+
+        self.digits(lexeme)
+    }
+
+    fn digits(&mut self, lexeme: &mut String) -> Result<(), ParseError> {
+        while matches!(self.peek()?, Some(b'0'..=b'9')) {
+            self.push_advance(lexeme)?;
+        }
+
+        Ok(())
+    }
+
+    fn word(&mut self) -> Result<(TokenType, Literal), ParseError> {
+        let (start, line, column) = (self.offset, self.line, self.column);
+        let mut lexeme = String::new();
+
+        while let Some(character) = self.peek_utf8()?.filter(|c| is_identifier_char(*c)) {
+            for _ in 0..character.len_utf8() {
+                self.advance()?;
+            }
+
+            lexeme.push(character);
+        }
+
+        match lexeme.as_str() {
+            "true" => Ok((TokenType::True, Literal::Bool(true))),
+            "false" => Ok((TokenType::False, Literal::Bool(false))),
+            "null" => Ok((TokenType::Null, Literal::Null)),
+            _ if self.is_json5() && !lexeme.starts_with(|c: char| c.is_ascii_digit()) => {
+                Ok((TokenType::Identifier, Literal::String(lexeme)))
+            }
+            _ => {
+                let error = ParseError::new(
+                    ErrorCode::UnexpectedIdentifier,
+                    &format!("Unexpected identifier '{}'", lexeme),
+                    Span::new(start, self.offset, line, column),
+                );
+
+                Err(match self.identifier_hint(&lexeme)? {
+                    Some(hint) => error.with_hint(&hint),
+                    None => error,
+                })
+            }
+        }
+    }
+
+    /// Suggests a fix for a bare word in plain JSON: a literal spelled the
+    /// Python or JavaScript way, or a property key missing its quotes.
+    fn identifier_hint(&mut self, lexeme: &str) -> Result<Option<String>, ParseError> {
+        let literal = match lexeme.to_ascii_lowercase().as_str() {
+            "true" => Some("true"),
+            "false" => Some("false"),
+            "none" | "nil" | "null" | "undefined" => Some("null"),
+            _ => None,
+        };
+
+        if let Some(literal) = literal {
+            return Ok(Some(format!("write `{}` instead of `{}`", literal, lexeme)));
+        }
+
+        // Only look a short way ahead, a key is followed by its colon.
+        for n in 0..64 {
+            match self.peek_nth(n)? {
+                Some(b' ' | b'\t' | b'\n' | b'\r') => continue,
+                Some(b':') => {
+                    return Ok(Some(format!(
+                        "property keys must be quoted: `\"{}\"`",
+                        lexeme
+                    )))
+                }
+                _ => break,
+            }
+        }
+
+        Ok(Some(format!(
+            "if this is a string, put it in double quotes: `\"{}\"`",
+            lexeme
+        )))
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek()? {
+                Some(b' ' | b'\t' | b'\n' | b'\r') => {
+                    self.advance()?;
+                }
+                Some(b'/') if self.dialect.allows_comments() => self.comment()?,
+                Some(_) if self.is_json5() => match self.peek_utf8()? {
+                    Some(character) if is_json5_whitespace(character) => {
+                        for _ in 0..character.len_utf8() {
+                            self.advance()?;
+                        }
+                    }
+                    _ => return Ok(()),
+                },
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn comment(&mut self) -> Result<(), ParseError> {
+        let (start, line, column) = (self.offset, self.line, self.column);
+        self.advance()?;
+
+        let kind = match self.advance()? {
+            Some(b'/') => CommentKind::Line,
+            Some(b'*') => CommentKind::Block,
+            _ => {
+                return Err(ParseError::new(
+                    ErrorCode::UnexpectedCharacter,
+                    "Unexpected character '/'",
+                    Span::new(start, self.offset, line, column),
+                ))
+            }
+        };
+
+        let mut bytes = vec![];
+
+        loop {
+            match (kind, self.peek()?) {
+                (CommentKind::Line, None | Some(b'\n')) => break,
+                (CommentKind::Block, None) => {
+                    return Err(ParseError::new(
+                        ErrorCode::UnterminatedComment,
+                        "Unterminated comment",
+                        Span::new(start, self.offset, line, column),
+                    ))
+                }
+                (CommentKind::Block, Some(b'*')) => {
+                    self.advance()?;
+
+                    if self.peek()? == Some(b'/') {
+                        self.advance()?;
+                        break;
+                    }
+
+                    bytes.push(b'*');
+                }
+                (_, Some(byte)) => {
+                    self.advance()?;
+                    bytes.push(byte);
+                }
+            }
+        }
+
+        self.comments.push(Comment {
+            kind,
+            text: String::from_utf8_lossy(&bytes).into_owned(),
+            span: Span::new(start, self.offset, line, column),
+        });
+
+        Ok(())
+    }
+
+    fn push_advance(&mut self, lexeme: &mut String) -> Result<(), ParseError> {
+        if let Some(byte) = self.advance()? {
+            lexeme.push(byte as char);
+        }
+
+        if lexeme.len() > self.limits.max_number_length {
+            return Err(self.error_here(
+                ErrorCode::LimitExceeded,
+                &format!(
+                    "Number longer than {} characters",
+                    self.limits.max_number_length
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn skip_char(&mut self) -> Result<(), ParseError> {
+        let width = self.peek_utf8()?.map_or(1, char::len_utf8);
+
+        for _ in 0..width {
+            self.advance()?;
+        }
+
+        Ok(())
+    }
+
+    fn is_json5(&self) -> bool {
+        self.dialect == Dialect::Json5
+    }
+
+    fn peek_char(&mut self) -> Result<char, ParseError> {
+        Ok(self.peek_utf8()?.unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
+    /// Decodes the character at the current position without consuming it.
+    fn peek_utf8(&mut self) -> Result<Option<char>, ParseError> {
+        let width = match self.peek()? {
+            None => return Ok(None),
+            Some(0x00..=0x7f) => 1,
+            Some(0xc0..=0xdf) => 2,
+            Some(0xe0..=0xef) => 3,
+            Some(0xf0..=0xf7) => 4,
+            Some(_) => return Ok(None),
+        };
+
+        let mut bytes = [0; 4];
+
+        for (n, byte) in bytes.iter_mut().enumerate().take(width) {
+            match self.peek_nth(n)? {
+                Some(value) => *byte = value,
+                None => return Ok(None),
+            }
+        }
+
+        Ok(std::str::from_utf8(&bytes[..width])
+            .ok()
+            .and_then(|character| character.chars().next()))
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, ParseError> {
+        self.peek_nth(0)
+    }
+
+    /// Looks `n` bytes ahead, shifting the unread bytes to the front of the
+    /// buffer when more input is needed.
+    fn peek_nth(&mut self, n: usize) -> Result<Option<u8>, ParseError> {
+        while self.position + n >= self.filled {
+            self.buffer.copy_within(self.position..self.filled, 0);
+            self.filled -= self.position;
+            self.position = 0;
+
+            let read = loop {
+                match self.reader.read(&mut self.buffer[self.filled..]) {
+                    Ok(read) => break read,
+                    Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(error) => {
+                        return Err(self.error_here(ErrorCode::Io, &format!("I/O error: {}", error)))
+                    }
+                }
+            };
+
+            if read == 0 {
+                return Ok(None);
+            }
+
+            self.filled += read;
+        }
+
+        Ok(Some(self.buffer[self.position + n]))
+    }
+
+    fn advance(&mut self) -> Result<Option<u8>, ParseError> {
+        let byte = self.peek()?;
+
+        if let Some(byte) = byte {
+            if self.offset >= self.limits.max_input_size {
+                return Err(self.error_here(
+                    ErrorCode::LimitExceeded,
+                    &format!("Input larger than {} bytes", self.limits.max_input_size),
+                ));
+            }
+
+            self.position += 1;
+            self.offset += 1;
+
+            if byte == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if byte & 0xc0 != 0x80 {
+                self.column += 1;
+            }
+        }
+
+        Ok(byte)
+    }
+
+    fn error_here(&self, code: ErrorCode, message: &str) -> ParseError {
+        ParseError::new(
+            code,
+            message,
+            Span::new(self.offset, self.offset, self.line, self.column),
+        )
+    }
+}
+
+fn is_identifier_char(character: char) -> bool {
+    character.is_alphanumeric() || character == '_' || character == '$'
+}
+
+fn is_json5_whitespace(character: char) -> bool {
+    (character.is_whitespace() && character != '\u{85}') || character == '\u{feff}'
+}
+
+#[cfg(test)]
+mod lexer_tests {
+    use super::*;
+
+    fn token_types(input: &str) -> Vec<TokenType> {
+        Lexer::new(input.as_bytes())
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|token| token.token_type)
+            .collect()
+    }
+
+    #[test]
+    fn tokenize() {
+        assert_eq!(
+            token_types(r#"{"list": [1, -2.5e3, true, false, null]}"#),
+            vec![
+                TokenType::LeftBrace,
+                TokenType::String,
+                TokenType::Colon,
+                TokenType::LeftBracket,
+                TokenType::Number,
+                TokenType::Comma,
+                TokenType::Number,
+                TokenType::Comma,
+                TokenType::True,
+                TokenType::Comma,
+                TokenType::False,
+                TokenType::Comma,
+                TokenType::Null,
+                TokenType::RightBracket,
+                TokenType::RightBrace,
+                TokenType::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn string_escapes() {
+        let tokens = Lexer::new(r#""a\"b\né😀""#.as_bytes()).tokenize().unwrap();
+
+        assert_eq!(tokens[0].literal.to_string(), "a\"b\né😀");
+    }
+
+    #[test]
+    fn spans() {
+        let tokens = Lexer::new("{\n  \"a\": 1\n}".as_bytes())
+            .tokenize()
+            .unwrap();
+
+        assert_eq!(tokens[1].span, Span::new(4, 7, 2, 3));
+        assert_eq!(tokens[4].span, Span::new(11, 12, 3, 1));
+    }
+
+    #[test]
+    fn comments() {
+        let mut lexer = Lexer::with_dialect(
+            "// settings\n{ /* inline */ \"a\": 1 } // end".as_bytes(),
+            Dialect::Jsonc,
+        );
+        let tokens = lexer.tokenize().unwrap();
+        let comments = lexer.take_comments();
+
+        assert_eq!(tokens.len(), 6);
+        assert_eq!(
+            comments
+                .iter()
+                .map(|comment| (comment.kind, comment.text.as_str(), comment.span.line))
+                .collect::<Vec<_>>(),
+            vec![
+                (CommentKind::Line, " settings", 1),
+                (CommentKind::Block, " inline ", 2),
+                (CommentKind::Line, " end", 2),
+            ]
+        );
+        assert_eq!(comments[1].span, Span::new(14, 26, 2, 3));
+
+        let error = Lexer::new("// no".as_bytes()).tokenize().unwrap_err();
+        assert_eq!(error.message, "Unexpected character '/'");
+
+        let error = Lexer::with_dialect("[] /* open".as_bytes(), Dialect::Jsonc)
+            .tokenize()
+            .unwrap_err();
+        assert_eq!(error.message, "Unterminated comment");
+    }
+
+    #[test]
+    fn json5() {
+        let input = "{unquoted: 'it\\'s', hex: 0xFF, half: .5, whole: 2., pos: +1,\n\
+                     inf: -Infinity, nan: NaN, multi: 'a\\\nb\\x41',\u{a0}ünï: 1}";
+        let tokens = Lexer::with_dialect(input.as_bytes(), Dialect::Json5)
+            .tokenize()
+            .unwrap();
+
+        let literals = tokens
+            .iter()
+            .filter(|token| {
+                matches!(
+                    token.token_type,
+                    TokenType::Identifier | TokenType::String | TokenType::Number
+                )
+            })
+            .map(|token| token.literal.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            literals,
+            vec![
+                "unquoted", "it's", "hex", "255", "half", "0.5", "whole", "2", "pos", "1", "inf",
+                "-inf", "nan", "NaN", "multi", "abA", "ünï", "1"
+            ]
+        );
+        assert_eq!(tokens[1].token_type, TokenType::Identifier);
+
+        let error = Lexer::new("'single'".as_bytes()).tokenize().unwrap_err();
+        assert_eq!(error.message, "Unexpected character '''");
+
+        let error = Lexer::new("0x10".as_bytes()).tokenize().unwrap_err();
+        assert_eq!(error.message, "Unexpected identifier 'x10'");
+    }
+
+    #[test]
+    fn errors() {
+        let error = Lexer::new("[01]".as_bytes()).tokenize().unwrap_err();
+        assert_eq!(error.message, "Invalid number");
+
+        let error = Lexer::new("\n  nope".as_bytes()).tokenize().unwrap_err();
+        assert_eq!(error.message, "Unexpected identifier 'nope'");
+        assert_eq!((error.span.line, error.span.column), (2, 3));
+    }
+
+    #[test]
+    fn limits() {
+        let limits = Limits {
+            max_string_length: 4,
+            max_number_length: 5,
+            max_input_size: 32,
+            ..Limits::default()
+        };
+        let error = |input: &str| {
+            Lexer::new(input.as_bytes())
+                .limits(limits)
+                .tokenize()
+                .unwrap_err()
+                .message
+        };
+
+        assert!(Lexer::new("[\"abcd\", -1.25]".as_bytes())
+            .limits(limits)
+            .tokenize()
+            .is_ok());
+        assert_eq!(error("\"abcde\""), "String longer than 4 bytes");
+        assert_eq!(
+            error("\"\\u00e9\\u00e9\\u00e9\""),
+            "String longer than 4 bytes"
+        );
+        assert_eq!(error("123456"), "Number longer than 5 characters");
+        assert_eq!(error(&" ".repeat(40)), "Input larger than 32 bytes");
+    }
+}
@@ -0,0 +1,61 @@
+/// Caps on the size and shape of a document, enforced while lexing and
+/// parsing so hostile input fails early instead of exhausting memory or
+/// the stack.
+///
+/// The [`Default`] limits are on for every [`Lexer`](super::lexer::Lexer)
+/// and [`Parser`](super::nodes::Parser) and are meant to be safe for
+/// untrusted input while accepting any reasonable document. The
+/// [`EventReader`](super::events::EventReader) uses
+/// [`Limits::streaming`] instead, since it holds nothing in memory and is
+/// meant for inputs of any size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Deepest allowed nesting of objects and arrays.
+    pub max_depth: usize,
+    /// Longest allowed string in bytes, after unescaping.
+    pub max_string_length: usize,
+    /// Longest allowed number literal in characters.
+    pub max_number_length: usize,
+    /// Most members allowed in one object.
+    pub max_object_keys: usize,
+    /// Most elements allowed in one array.
+    pub max_array_length: usize,
+    /// Largest allowed input in bytes. Not capped when streaming.
+    pub max_input_size: usize,
+}
+
+impl Limits {
+    /// No limits at all, for trusted input.
+    pub fn unlimited() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            max_string_length: usize::MAX,
+            max_number_length: usize::MAX,
+            max_object_keys: usize::MAX,
+            max_array_length: usize::MAX,
+            max_input_size: usize::MAX,
+        }
+    }
+
+    /// The defaults without a cap on the total input size. Each value is
+    /// still bounded by the other limits.
+    pub fn streaming() -> Self {
+        Self {
+            max_input_size: usize::MAX,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            max_string_length: 8 * 1024 * 1024,
+            max_number_length: 256,
+            max_object_keys: 100_000,
+            max_array_length: 1_000_000,
+            max_input_size: 256 * 1024 * 1024,
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use std::fmt::Display;
+
+use super::{
+    cst::{EditError, SyntaxTree},
+    error::ParseError,
+    lexer::Dialect,
+    nodes::{parse_str_with, Literal, Node},
+    pointer::to_pointer,
+};
+
+pub const OURS_MARKER: &str = "<<<<<<< ours";
+pub const BASE_MARKER: &str = "||||||| base";
+pub const THEIRS_MARKER: &str = ">>>>>>> theirs";
+
+/// Why [`merge_text`] failed: one of the inputs could not be parsed, or
+/// a value from `theirs` could not be written into `ours`, with its path.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeError {
+    Base(ParseError),
+    Ours(ParseError),
+    Theirs(ParseError),
+    Edit(String, EditError),
+}
+
+impl std::error::Error for MergeError {}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Base(error) => write!(f, "base: {}", error),
+            Self::Ours(error) => write!(f, "ours: {}", error),
+            Self::Theirs(error) => write!(f, "theirs: {}", error),
+            Self::Edit(path, error) => write!(f, "cannot update '{}': {}", path, error),
+        }
+    }
+}
+
+/// A path both sides changed in different ways. A missing side means the
+/// value was deleted there, or never existed for `base`.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    /// JSON Pointer to the conflicting value.
+    pub path: String,
+    pub base: Option<Node>,
+    pub ours: Option<Node>,
+    pub theirs: Option<Node>,
+}
+
+impl Conflict {
+    /// The object left in the merged document in place of the value, with
+    /// a member for each side that has one:
+    ///
+    /// ```text
+    /// {"<<<<<<< ours": 1, "||||||| base": 0, ">>>>>>> theirs": 2}
+    /// ```
+    pub fn marker(&self) -> Node {
+        let mut properties = vec![];
+
+        for (name, side) in [
+            (OURS_MARKER, &self.ours),
+            (BASE_MARKER, &self.base),
+            (THEIRS_MARKER, &self.theirs),
+        ] {
+            if let Some(value) = side {
+                properties.push(Node::property(name, value.clone()));
+            }
+        }
+
+        Node::object(properties)
+    }
+
+    /// `{"path", "base"?, "ours"?, "theirs"?}`
+    pub fn to_node(&self) -> Node {
+        let mut properties = vec![Node::property(
+            "path",
+            Node::Primary(Literal::String(self.path.clone())),
+        )];
+
+        for (name, side) in [
+            ("base", &self.base),
+            ("ours", &self.ours),
+            ("theirs", &self.theirs),
+        ] {
+            if let Some(value) = side {
+                properties.push(Node::property(name, value.clone()));
+            }
+        }
+
+        properties.sort_by_key(|property| match property {
+            Node::Property(key, _, _) => key.literal.to_string(),
+            _ => String::new(),
+        });
+
+        Node::object(properties)
+    }
+}
+
+/// The outcome of a three-way merge. When there are conflicts `merged`
+/// holds a [`Conflict::marker`] at each conflicting path.
+#[derive(Debug, Clone)]
+pub struct Merge {
+    pub merged: Node,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merges the changes `ours` and `theirs` each made to `base`.
+///
+/// Objects merge member by member, so edits to different keys never
+/// conflict. Any other value, arrays included, is taken from whichever
+/// side changed it, and is a conflict if both changed it differently.
+pub fn merge(base: &Node, ours: &Node, theirs: &Node) -> Merge {
+    let mut merger = Merger::default();
+    let merged = merger
+        .merge(&mut vec![], Some(base), Some(ours), Some(theirs))
+        .unwrap_or(Node::Primary(Literal::Null));
+
+    Merge {
+        merged,
+        conflicts: merger.conflicts,
+    }
+}
+
+/// Merges three versions of a file, keeping the formatting and comments of
+/// `ours`: only values taken from `theirs` and conflict markers are
+/// rewritten. Returns the merged text and the conflicts.
+pub fn merge_text(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    dialect: Dialect,
+) -> Result<(String, Vec<Conflict>), MergeError> {
+    let (base, _) = parse_str_with(base, dialect).map_err(MergeError::Base)?;
+    let (theirs, _) = parse_str_with(theirs, dialect).map_err(MergeError::Theirs)?;
+    let mut tree = SyntaxTree::parse(ours, dialect).map_err(MergeError::Ours)?;
+    let ours = tree.to_node().map_err(MergeError::Ours)?;
+
+    let mut merger = Merger::default();
+    merger.merge(&mut vec![], Some(&base), Some(&ours), Some(&theirs));
+
+    for (path, value) in &merger.edits {
+        let segments = path.iter().map(String::as_str).collect::<Vec<_>>();
+
+        match value {
+            Some(value) => tree.set(&segments, value),
+            None => tree.remove(&segments),
+        }
+        .map_err(|error| MergeError::Edit(to_pointer(path), error))?;
+    }
+
+    Ok((tree.to_string(), merger.conflicts))
+}
+
+/// Tracks conflicts and the edits that turn `ours` into the merged result,
+/// as paths of object keys with the new value or `None` to remove it.
+#[derive(Default)]
+struct Merger {
+    conflicts: Vec<Conflict>,
+    edits: Vec<(Vec<String>, Option<Node>)>,
+}
+
+impl Merger {
+    fn merge(
+        &mut self,
+        path: &mut Vec<String>,
+        base: Option<&Node>,
+        ours: Option<&Node>,
+        theirs: Option<&Node>,
+    ) -> Option<Node> {
+        if ours == theirs || base == theirs {
+            return ours.cloned();
+        }
+
+        if base == ours {
+            self.edits.push((path.clone(), theirs.cloned()));
+            return theirs.cloned();
+        }
+
+        if let (Some(Node::Object(_, our_members, _)), Some(Node::Object(_, their_members, _))) =
+            (ours, theirs)
+        {
+            let base_members = match base {
+                Some(Node::Object(_, base_members, _)) => base_members.as_slice(),
+                _ => &[],
+            };
+
+            let mut keys = [base_members, our_members, their_members]
+                .into_iter()
+                .flatten()
+                .filter_map(|property| match property {
+                    Node::Property(key, _, _) => Some(key.literal.to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+
+            let mut properties = vec![];
+
+            for key in keys {
+                path.push(key.clone());
+
+                let merged = self.merge(
+                    path,
+                    base.and_then(|base| base.get_member(&key)),
+                    ours.and_then(|ours| ours.get_member(&key)),
+                    theirs.and_then(|theirs| theirs.get_member(&key)),
+                );
+
+                if let Some(value) = merged {
+                    properties.push(Node::property(&key, value));
+                }
+
+                path.pop();
+            }
+
+            return Some(Node::object(properties));
+        }
+
+        let conflict = Conflict {
+            path: to_pointer(path),
+            base: base.cloned(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+        };
+        let marker = conflict.marker();
+
+        self.edits.push((path.clone(), Some(marker.clone())));
+        self.conflicts.push(conflict);
+
+        Some(marker)
+    }
+}
+
+#[cfg(test)]
+mod merge_tests {
+    use super::*;
+    use crate::parser_update::{nodes::parse_str, printer::CompactPrint};
+
+    const BASE: &str =
+        r#"{"name": "app", "port": 80, "tags": ["a"], "db": {"host": "x", "pool": 5}, "old": 1}"#;
+
+    fn merged(ours: &str, theirs: &str) -> Merge {
+        merge(
+            &parse_str(BASE).unwrap(),
+            &parse_str(ours).unwrap(),
+            &parse_str(theirs).unwrap(),
+        )
+    }
+
+    #[test]
+    fn clean() {
+        let merge = merged(
+            r#"{"name": "app", "port": 8080, "tags": ["a"], "db": {"host": "x", "pool": 10}, "old": 1}"#,
+            r#"{"name": "app2", "port": 80, "tags": ["a", "b"], "db": {"host": "y", "pool": 5}, "new": true}"#,
+        );
+
+        assert!(merge.is_clean());
+        assert_eq!(
+            CompactPrint.print(&merge.merged),
+            r#"{"db":{"host":"y","pool":10},"name":"app2","new":true,"port":8080,"tags":["a","b"]}"#
+        );
+    }
+
+    #[test]
+    fn conflicts() {
+        let merge = merged(
+            r#"{"name": "app", "port": 81, "tags": ["a", "c"], "db": {"host": "x", "pool": 5}}"#,
+            r#"{"name": "app", "port": 82, "tags": ["a", "c"], "db": {"host": "x", "pool": 5}, "old": 2}"#,
+        );
+
+        assert_eq!(
+            merge
+                .conflicts
+                .iter()
+                .map(|conflict| CompactPrint.print(&conflict.to_node()))
+                .collect::<Vec<_>>(),
+            vec![
+                r#"{"base":1,"path":"/old","theirs":2}"#,
+                r#"{"base":80,"ours":81,"path":"/port","theirs":82}"#,
+            ]
+        );
+        assert_eq!(
+            CompactPrint.print(merge.merged.get_pointer("/port").unwrap()),
+            r#"{"<<<<<<< ours":81,"||||||| base":80,">>>>>>> theirs":82}"#
+        );
+    }
+
+    #[test]
+    fn text() {
+        let ours =
+            "{\n  // service name\n  \"name\": \"app\",\n  \"port\": 8080,\n  \"old\": 1\n}\n";
+        let theirs = r#"{"name": "app", "port": 80, "extra": [1]}"#;
+
+        let (text, conflicts) = merge_text(
+            r#"{"name": "app", "port": 80, "old": 1}"#,
+            ours,
+            theirs,
+            Dialect::Jsonc,
+        )
+        .unwrap();
+
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            text,
+            "{\n  // service name\n  \"name\": \"app\",\n  \"port\": 8080,\n  \"extra\": [1]\n}\n"
+        );
+
+        let (text, conflicts) = merge_text(
+            r#"{"a": 1}"#,
+            r#"{"a": 1, "c": 2}"#,
+            r#"{"a": 1e300}"#,
+            Dialect::Json,
+        )
+        .unwrap();
+
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            parse_str(&text).unwrap(),
+            parse_str(r#"{"a": 1e300, "c": 2}"#).unwrap()
+        );
+
+        let error = merge_text("{}", "{}", "{\n  \"a\": }", Dialect::Json).unwrap_err();
+        assert!(matches!(error, MergeError::Theirs(_)));
+        assert_eq!(
+            error.to_string(),
+            "theirs: Unknown literal at line 2, column 8"
+        );
+    }
+}
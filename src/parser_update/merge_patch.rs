@@ -0,0 +1,128 @@
+use super::{
+    nodes::{Literal, Node},
+    pointer::escape_token,
+};
+
+/// Applies a JSON Merge Patch (RFC 7396) to `target`.
+///
+/// An object patch merges member by member: `null` removes the member and
+/// any other value is merged into it recursively. A patch that is not an
+/// object replaces the target.
+pub fn apply_merge_patch(target: &mut Node, patch: &Node) {
+    let Node::Object(_, members, _) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !matches!(target, Node::Object(..)) {
+        *target = Node::object(vec![]);
+    }
+
+    for member in members {
+        let Node::Property(key, _, value) = member else {
+            continue;
+        };
+
+        let pointer = format!("/{}", escape_token(&key.literal.to_string()));
+
+        if let Node::Primary(Literal::Null) = **value {
+            let _ = target.remove_pointer(&pointer);
+            continue;
+        }
+
+        match target.get_pointer_mut(&pointer) {
+            Ok(current) => apply_merge_patch(current, value),
+            Err(_) => {
+                let mut current = Node::Primary(Literal::Null);
+                apply_merge_patch(&mut current, value);
+                let _ = target.set_pointer(&pointer, current);
+            }
+        }
+    }
+}
+
+/// Computes the merge patch that turns `from` into `to`.
+///
+/// Merge patches cannot set a member to `null` or change part of an array,
+/// so a `null` in `to` comes out as a removal and a changed array is sent
+/// whole.
+pub fn create_merge_patch(from: &Node, to: &Node) -> Node {
+    let (Node::Object(..), Node::Object(..)) = (from, to) else {
+        return to.clone();
+    };
+
+    let old = from.member_map();
+    let new = to.member_map();
+    let mut changes = vec![];
+
+    for key in old.keys().filter(|key| !new.contains_key(*key)) {
+        changes.push(Node::property(key, Node::Primary(Literal::Null)));
+    }
+
+    for (key, value) in &new {
+        match old.get(key) {
+            Some(current) if current == value => {}
+            Some(current) => changes.push(Node::property(key, create_merge_patch(current, value))),
+            None => changes.push(Node::property(key, (*value).clone())),
+        }
+    }
+
+    changes.sort_by_key(|property| match property {
+        Node::Property(key, _, _) => key.literal.to_string(),
+        _ => String::new(),
+    });
+
+    Node::object(changes)
+}
+
+#[cfg(test)]
+mod merge_patch_tests {
+    use super::*;
+    use crate::parser_update::{nodes::parse_str, printer::CompactPrint};
+
+    fn merged(target: &str, patch: &str) -> String {
+        let mut target = parse_str(target).unwrap();
+        apply_merge_patch(&mut target, &parse_str(patch).unwrap());
+
+        CompactPrint.print(&target)
+    }
+
+    #[test]
+    fn apply() {
+        assert_eq!(
+            merged(
+                r#"{"title": "Goodbye!", "author": {"givenName": "John", "familyName": "Doe"}, "tags": ["example", "sample"], "content": "This will be unchanged"}"#,
+                r#"{"title": "Hello!", "phoneNumber": "+01-123-456-7890", "author": {"familyName": null}, "tags": ["example"]}"#
+            ),
+            r#"{"author":{"givenName":"John"},"content":"This will be unchanged","phoneNumber":"+01-123-456-7890","tags":["example"],"title":"Hello!"}"#
+        );
+        assert_eq!(merged(r#"{"a": "b"}"#, r#"{"a": null}"#), "{}");
+        assert_eq!(merged(r#"["a", "b"]"#, r#"{"a": "c"}"#), r#"{"a":"c"}"#);
+        assert_eq!(merged(r#"{"a": "foo"}"#, "null"), "null");
+        assert_eq!(
+            merged("{}", r#"{"a": {"bb": {"ccc": null}}}"#),
+            r#"{"a":{"bb":{}}}"#
+        );
+    }
+
+    #[test]
+    fn create() {
+        let from = parse_str(r#"{"a": 1, "b": {"c": [1], "d": true}, "e": "x"}"#).unwrap();
+        let to = parse_str(r#"{"a": 1, "b": {"c": [1, 2], "d": true}, "f": null}"#).unwrap();
+
+        let patch = create_merge_patch(&from, &to);
+        assert_eq!(
+            CompactPrint.print(&patch),
+            r#"{"b":{"c":[1,2]},"e":null,"f":null}"#
+        );
+
+        let mut document = from.clone();
+        apply_merge_patch(&mut document, &patch);
+        assert_eq!(
+            CompactPrint.print(&document),
+            r#"{"a":1,"b":{"c":[1,2],"d":true}}"#
+        );
+
+        assert_eq!(CompactPrint.print(&create_merge_patch(&to, &to)), "{}");
+    }
+}
@@ -0,0 +1,212 @@
+use std::{collections::BTreeMap, fmt};
+
+use super::{
+    canonical::{CanonicalError, CanonicalPrint},
+    nodes::{Literal, Node, Token, Visitor},
+    pointer::escape_token,
+    printer::escape_string,
+    sha256::Sha256,
+};
+
+/// A SHA-256 content hash, shown as lowercase hex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Digest(pub [u8; 32]);
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The digest of a subtree and of every object and array inside it, keyed
+/// by JSON Pointer relative to the subtree.
+pub struct Subtree {
+    pub digest: Digest,
+    pub hashes: Vec<(String, Digest)>,
+}
+
+/// Hashes every object and array bottom-up, Merkle style: a scalar hashes
+/// its RFC 8785 canonical text, an array hashes its elements' digests in
+/// order and an object its keys and member digests sorted like canonical
+/// JSON. Semantically equal subtrees always get the same digest, and a
+/// change deep in a document only changes the digests on the way up.
+pub struct MerkleHasher;
+
+impl MerkleHasher {
+    /// Maps the pointer of each object and array in `root` to its digest.
+    pub fn hash(&self, root: &Node) -> Result<BTreeMap<String, Digest>, CanonicalError> {
+        Ok(root.accept(self)?.hashes.into_iter().collect())
+    }
+}
+
+impl Visitor<Result<Subtree, CanonicalError>> for MerkleHasher {
+    fn visit_primary(&self, value: &Literal) -> Result<Subtree, CanonicalError> {
+        let mut hasher = Sha256::new();
+        hasher.update(CanonicalPrint.visit_primary(value)?.as_bytes());
+
+        Ok(Subtree {
+            digest: Digest(hasher.finish()),
+            hashes: vec![],
+        })
+    }
+
+    fn visit_object(
+        &self,
+        _left: &Token,
+        properties: &[Node],
+        _right: &Token,
+    ) -> Result<Subtree, CanonicalError> {
+        let mut members = vec![];
+
+        for property in properties {
+            if let Node::Property(key, _, value) = property {
+                members.push((key.literal.to_string(), value.accept(self)?));
+            }
+        }
+
+        members.sort_by_cached_key(|(key, _)| key.encode_utf16().collect::<Vec<u16>>());
+
+        let mut hasher = Sha256::new();
+        let mut hashes = vec![];
+
+        hasher.update(b"{");
+        for (key, member) in members {
+            hasher.update(escape_string(&key).as_bytes());
+            hasher.update(b":");
+            hasher.update(&member.digest.0);
+
+            let prefix = format!("/{}", escape_token(&key));
+            hashes.extend(prefixed(&prefix, member.hashes));
+        }
+        hasher.update(b"}");
+
+        Ok(container(hasher, hashes))
+    }
+
+    fn visit_property(
+        &self,
+        _key: &Token,
+        _colon: &Token,
+        value: &Node,
+    ) -> Result<Subtree, CanonicalError> {
+        value.accept(self)
+    }
+
+    fn visit_list(
+        &self,
+        _left: &Token,
+        nodes: &[Node],
+        _right: &Token,
+    ) -> Result<Subtree, CanonicalError> {
+        let mut hasher = Sha256::new();
+        let mut hashes = vec![];
+
+        hasher.update(b"[");
+        for (index, node) in nodes.iter().enumerate() {
+            let element = node.accept(self)?;
+            hasher.update(&element.digest.0);
+
+            hashes.extend(prefixed(&format!("/{}", index), element.hashes));
+        }
+        hasher.update(b"]");
+
+        Ok(container(hasher, hashes))
+    }
+
+    fn visit_error(&self, _token: &Token) -> Result<Subtree, CanonicalError> {
+        Err(CanonicalError::ErrorNode)
+    }
+}
+
+fn container(hasher: Sha256, mut hashes: Vec<(String, Digest)>) -> Subtree {
+    let digest = Digest(hasher.finish());
+    hashes.push((String::new(), digest));
+
+    Subtree { digest, hashes }
+}
+
+fn prefixed(
+    prefix: &str,
+    hashes: Vec<(String, Digest)>,
+) -> impl Iterator<Item = (String, Digest)> + '_ {
+    hashes
+        .into_iter()
+        .map(move |(path, digest)| (format!("{}{}", prefix, path), digest))
+}
+
+/// The pointers of the objects and arrays whose content differs between
+/// two hash maps from [`MerkleHasher::hash`], including ones only present
+/// on one side. Ancestors of a change are listed too, since their digests
+/// change with it.
+///
+/// Taking maps rather than documents lets callers keep the hashes of the
+/// old version around instead of the document itself.
+pub fn changed_subtrees(
+    old: &BTreeMap<String, Digest>,
+    new: &BTreeMap<String, Digest>,
+) -> Vec<String> {
+    let mut changed = old
+        .iter()
+        .filter(|(path, digest)| new.get(*path) != Some(digest))
+        .map(|(path, _)| path.clone())
+        .chain(new.keys().filter(|path| !old.contains_key(*path)).cloned())
+        .collect::<Vec<_>>();
+
+    changed.sort();
+    changed
+}
+
+#[cfg(test)]
+mod merkle_tests {
+    use super::*;
+    use crate::parser_update::nodes::parse_str;
+
+    fn hashes(input: &str) -> BTreeMap<String, Digest> {
+        MerkleHasher.hash(&parse_str(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn every_container() {
+        let hashes = hashes(r#"{"a": [1, {"b": null}], "c/d": {}, "e": "x"}"#);
+
+        assert_eq!(
+            hashes.keys().collect::<Vec<_>>(),
+            vec!["", "/a", "/a/1", "/c~1d"]
+        );
+        assert_eq!(
+            hashes["/c~1d"].to_string(),
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+    }
+
+    #[test]
+    fn semantic() {
+        let built = Node::object(vec![
+            Node::property("b", Node::Primary(Literal::Number(1.0))),
+            Node::property("a", Node::list(vec![])),
+        ]);
+
+        assert_eq!(
+            MerkleHasher.hash(&built).unwrap(),
+            hashes(r#"{"a": [], "b": 1.0}"#)
+        );
+        assert_ne!(hashes("[1, 2]")[""], hashes("[2, 1]")[""]);
+        assert_ne!(hashes(r#"{"a": "1"}"#)[""], hashes(r#"{"a": 1}"#)[""]);
+    }
+
+    #[test]
+    fn changes() {
+        let old = hashes(r#"{"a": {"x": [1, 2]}, "b": {"y": true}, "c": [[0]]}"#);
+        let new = hashes(r#"{"a": {"x": [1, 3]}, "b": {"y": true}, "d": {}}"#);
+
+        assert_eq!(
+            changed_subtrees(&old, &new),
+            vec!["", "/a", "/a/x", "/c", "/c/0", "/d"]
+        );
+        assert!(changed_subtrees(&old, &old).is_empty());
+    }
+}
@@ -1 +1,28 @@
+pub mod canonical;
+pub mod cst;
+pub mod diagnostic;
+pub mod diff;
+pub mod equality;
+pub mod error;
+pub mod events;
+pub mod explain;
+pub mod infer;
+pub mod jq;
+pub mod jsonpath;
+pub mod lexer;
+pub mod limits;
+pub mod merge;
+pub mod merge_patch;
+pub mod merkle;
+pub mod ndjson;
 pub mod nodes;
+pub mod patch;
+pub mod pointer;
+pub mod printer;
+pub mod regex;
+pub mod report;
+pub mod rustgen;
+pub mod schema;
+pub mod sha256;
+pub mod tsgen;
+pub mod typegen;
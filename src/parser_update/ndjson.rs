@@ -0,0 +1,302 @@
+use std::{
+    fmt::Display,
+    io::{BufRead, Write},
+};
+
+use super::{
+    error::{ErrorCode, ParseError, Span},
+    events::EventReader,
+    lexer::Lexer,
+    nodes::{Node, Parser},
+    printer::CompactPrint,
+};
+
+/// How documents are separated in a multi-document input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// One document per line (JSON Lines / NDJSON), blank lines are ignored.
+    Lines,
+    /// Documents follow each other with optional whitespace in between.
+    Concatenated,
+}
+
+/// A record that failed to parse, with the line it started on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordError {
+    pub line: usize,
+    pub error: ParseError,
+}
+
+impl std::error::Error for RecordError {}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "record at line {}: {} at column {}",
+            self.line, self.error.message, self.error.span.column
+        )
+    }
+}
+
+enum Source<R: BufRead> {
+    Lines(R),
+    Concatenated(Box<EventReader<R>>),
+}
+
+/// Reads a stream of JSON documents, yielding one [`Node`] per record.
+///
+/// Every record is parsed on its own, so with [`Framing::Lines`] a bad line
+/// does not stop the reader. Spans in errors are relative to the whole input.
+pub struct DocumentReader<R: BufRead> {
+    source: Source<R>,
+    line: usize,
+    offset: usize,
+    skip_invalid: bool,
+    skipped: Vec<RecordError>,
+    finished: bool,
+}
+
+impl<R: BufRead> DocumentReader<R> {
+    pub fn new(reader: R, framing: Framing) -> Self {
+        let source = match framing {
+            Framing::Lines => Source::Lines(reader),
+            Framing::Concatenated => {
+                Source::Concatenated(Box::new(EventReader::new(reader).multiple_values(true)))
+            }
+        };
+
+        Self {
+            source,
+            line: 0,
+            offset: 0,
+            skip_invalid: false,
+            skipped: vec![],
+            finished: false,
+        }
+    }
+
+    /// Collects bad records in [`DocumentReader::skipped`] instead of
+    /// yielding them. Concatenated input cannot be resynchronised after an
+    /// error, so there the first error is still returned and ends the stream.
+    pub fn skip_invalid(mut self, enabled: bool) -> Self {
+        self.skip_invalid = enabled;
+        self
+    }
+
+    pub fn skipped(&self) -> &[RecordError] {
+        &self.skipped
+    }
+
+    fn next_line(&mut self) -> Option<Result<Node, RecordError>> {
+        let Source::Lines(reader) = &mut self.source else {
+            return None;
+        };
+
+        loop {
+            let mut bytes = vec![];
+            let read = match reader.read_until(b'\n', &mut bytes) {
+                Ok(0) => return None,
+                Ok(read) => read,
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(RecordError {
+                        line: self.line + 1,
+                        error: ParseError::new(
+                            ErrorCode::Io,
+                            &format!("I/O error: {}", error),
+                            Span::new(self.offset, self.offset, self.line + 1, 1),
+                        ),
+                    }));
+                }
+            };
+
+            let start = self.offset;
+            self.line += 1;
+            self.offset += read;
+
+            if bytes.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            let line = self.line;
+
+            return Some(
+                Lexer::new(&bytes[..])
+                    .tokenize()
+                    .and_then(|tokens| Parser::new(tokens).parse_document())
+                    .map_err(|mut error| {
+                        error.span.start += start;
+                        error.span.end += start;
+                        error.span.line = line;
+
+                        RecordError { line, error }
+                    }),
+            );
+        }
+    }
+
+    fn next_value(&mut self) -> Option<Result<Node, RecordError>> {
+        let Source::Concatenated(reader) = &mut self.source else {
+            return None;
+        };
+
+        match reader.read_node() {
+            Ok(node) => node.map(Ok),
+            Err(error) => {
+                self.finished = true;
+                Some(Err(RecordError {
+                    line: error.span.line,
+                    error,
+                }))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for DocumentReader<R> {
+    type Item = Result<Node, RecordError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.finished {
+                return None;
+            }
+
+            let record = match self.source {
+                Source::Lines(_) => self.next_line(),
+                Source::Concatenated(_) => self.next_value(),
+            };
+
+            match record {
+                Some(Err(error)) if self.skip_invalid && !self.finished => self.skipped.push(error),
+                record => return record,
+            }
+        }
+    }
+}
+
+/// Writes one compact document per line.
+pub struct DocumentWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> DocumentWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write(&mut self, node: &Node) -> std::io::Result<()> {
+        writeln!(self.writer, "{}", CompactPrint.print(node))
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod ndjson_tests {
+    use super::*;
+    use crate::parser_update::nodes::parse_str;
+
+    const LOG: &str =
+        "{\"level\": \"info\"}\n\n{\"level\": }\r\n[1, 2]\n{\"level\" \"warn\"}\n\"done\"";
+
+    #[test]
+    fn read_lines() {
+        let records = DocumentReader::new(LOG.as_bytes(), Framing::Lines).collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 5);
+        assert!(records[0].is_ok());
+
+        let error = records[1].as_ref().unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.error.message, "Unknown literal");
+        assert_eq!(&LOG[error.error.span.start..error.error.span.end], "}");
+
+        assert_eq!(records[3].as_ref().unwrap_err().line, 5);
+    }
+
+    #[test]
+    fn skip_invalid() {
+        let mut reader = DocumentReader::new(LOG.as_bytes(), Framing::Lines).skip_invalid(true);
+
+        let nodes = reader
+            .by_ref()
+            .map(|node| CompactPrint.print(&node.unwrap()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(nodes, vec![r#"{"level":"info"}"#, "[1,2]", r#""done""#]);
+        assert_eq!(
+            reader
+                .skipped()
+                .iter()
+                .map(|error| error.line)
+                .collect::<Vec<_>>(),
+            vec![3, 5]
+        );
+    }
+
+    #[test]
+    fn read_concatenated() {
+        let input = "{\"a\": 1}{\"a\": 2}\n[3] 4\n{";
+        let records =
+            DocumentReader::new(input.as_bytes(), Framing::Concatenated).collect::<Vec<_>>();
+
+        assert_eq!(records.len(), 5);
+        assert_eq!(
+            CompactPrint.print(records[1].as_ref().unwrap()),
+            r#"{"a":2}"#
+        );
+        assert_eq!(records[4].as_ref().unwrap_err().line, 3);
+    }
+
+    #[test]
+    fn read_empty() {
+        for input in ["", "  \n", "\n\n"] {
+            for framing in [Framing::Lines, Framing::Concatenated] {
+                assert!(DocumentReader::new(input.as_bytes(), framing)
+                    .next()
+                    .is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn write_lines() {
+        let mut writer = DocumentWriter::new(vec![]);
+
+        for node in DocumentReader::new("{ \"b\": [ true ] }\n  null \n".as_bytes(), Framing::Lines)
+        {
+            writer.write(&node.unwrap()).unwrap();
+        }
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "{\"b\":[true]}\nnull\n"
+        );
+    }
+
+    #[test]
+    fn write_read_round_trip() {
+        let records =
+            [r#"{"big": 1e300}"#, "[-1.5e-300, 0.1]"].map(|record| parse_str(record).unwrap());
+        let mut writer = DocumentWriter::new(vec![]);
+
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+
+        let written = writer.into_inner();
+        let read = DocumentReader::new(written.as_slice(), Framing::Lines)
+            .map(|record| record.unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(read, records);
+    }
+}
@@ -1,4 +1,13 @@
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    fmt::Display,
+};
+
+use super::{
+    error::{ErrorCode, ParseError, Span},
+    lexer::{Comment, Dialect, Lexer},
+    limits::Limits,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TokenType {
@@ -16,6 +25,8 @@ pub enum TokenType {
     True,
     False,
 
+    /// Unreadable input, only emitted by `Lexer::tokenize_recovering`.
+    Invalid,
     Eof,
 }
 
@@ -23,6 +34,7 @@ pub enum TokenType {
 pub struct Token {
     pub token_type: TokenType,
     pub literal: Literal,
+    pub span: Span,
 }
 
 impl Token {
@@ -30,42 +42,86 @@ impl Token {
         Self {
             token_type,
             literal,
+            span: Span::default(),
+        }
+    }
+
+    pub fn with_span(token_type: TokenType, literal: Literal, span: Span) -> Self {
+        Self {
+            token_type,
+            literal,
+            span,
+        }
+    }
+
+    /// This token as an object key, or `None` if it cannot be one. JSON5
+    /// keys are identifier names, which include `true`, `false` and
+    /// `null`; those come back as identifiers holding their spelling.
+    pub fn key(&self, dialect: Dialect) -> Option<Token> {
+        match self.token_type {
+            TokenType::String | TokenType::Identifier => Some(self.clone()),
+            TokenType::True | TokenType::False | TokenType::Null if dialect == Dialect::Json5 => {
+                Some(Token::with_span(
+                    TokenType::Identifier,
+                    Literal::String(self.literal.to_string()),
+                    self.span,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// The number a JSON5 `Infinity` or `NaN` stands for. The lexer reads
+    /// them as identifiers, since either can also be a key.
+    pub fn named_number(&self) -> Option<Literal> {
+        match (&self.token_type, &self.literal) {
+            (TokenType::Identifier, Literal::String(name)) if name == "Infinity" => {
+                Some(Literal::Number(f64::INFINITY))
+            }
+            (TokenType::Identifier, Literal::String(name)) if name == "NaN" => {
+                Some(Literal::Number(f64::NAN))
+            }
+            _ => None,
         }
     }
 }
 
 pub trait Visitor<T> {
     fn visit_primary(&self, value: &Literal) -> T;
-    fn visit_object(&self, left: &Token, properties: &Vec<Node>, right: &Token) -> T;
+    fn visit_object(&self, left: &Token, properties: &[Node], right: &Token) -> T;
     fn visit_property(&self, key: &Token, colon: &Token, value: &Node) -> T;
-    fn visit_list(&self, left: &Token, nodes: &Vec<Node>, right: &Token) -> T;
+    fn visit_list(&self, left: &Token, nodes: &[Node], right: &Token) -> T;
+    fn visit_error(&self, token: &Token) -> T;
 }
 
 #[derive(Debug, Clone)]
 pub enum Literal {
     String(String),
-    Number(f32),
+    Number(f64),
     Bool(bool),
     Null,
 }
 
-impl ToString for Literal {
-    fn to_string(&self) -> String {
+impl Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Literal::String(value) => value.to_string(),
-            Literal::Number(value) => value.to_string(),
-            Literal::Bool(value) => value.to_string(),
-            Literal::Null => "null".to_string(),
+            Literal::String(value) => write!(f, "{}", value),
+            Literal::Number(value) => write!(f, "{}", value),
+            Literal::Bool(value) => write!(f, "{}", value),
+            Literal::Null => write!(f, "null"),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Node {
     Primary(Literal),
     Object(Token, Vec<Node>, Token),
     Property(Token, Token, Box<Node>),
     List(Token, Vec<Node>, Token),
+    /// Stands in for a value that could not be parsed, holding the token
+    /// where parsing failed. Only produced by recovering parses.
+    Error(Token),
 }
 
 impl Node {
@@ -75,111 +131,254 @@ impl Node {
             Self::Object(left, properties, right) => visitor.visit_object(left, properties, right),
             Self::Property(key, colon, value) => visitor.visit_property(key, colon, value),
             Self::List(left, nodes, right) => visitor.visit_list(left, nodes, right),
+            Self::Error(token) => visitor.visit_error(token),
         }
     }
+
+    /// Builds an object from `Node::Property` nodes.
+    pub fn object(properties: Vec<Node>) -> Self {
+        Self::Object(
+            Token::new(TokenType::LeftBrace, Literal::String("{".to_string())),
+            properties,
+            Token::new(TokenType::RightBrace, Literal::String("}".to_string())),
+        )
+    }
+
+    pub fn property(key: &str, value: Node) -> Self {
+        Self::Property(
+            Token::new(TokenType::String, Literal::String(key.to_string())),
+            Token::new(TokenType::Colon, Literal::String(":".to_string())),
+            Box::new(value),
+        )
+    }
+
+    pub fn string(value: &str) -> Self {
+        Self::Primary(Literal::String(value.to_string()))
+    }
+
+    pub fn number(value: f64) -> Self {
+        Self::Primary(Literal::Number(value))
+    }
+
+    pub fn list(nodes: Vec<Node>) -> Self {
+        Self::List(
+            Token::new(TokenType::LeftBracket, Literal::String("[".to_string())),
+            nodes,
+            Token::new(TokenType::RightBracket, Literal::String("]".to_string())),
+        )
+    }
 }
 
 pub struct Parser {
     current: usize,
     tokens: Vec<Token>,
+    dialect: Dialect,
+    limits: Limits,
+    depth: usize,
+    recovering: bool,
+    diagnostics: Vec<ParseError>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { current: 0, tokens }
+        Self::with_dialect(tokens, Dialect::Json)
+    }
+
+    pub fn with_dialect(tokens: Vec<Token>, dialect: Dialect) -> Self {
+        Self {
+            current: 0,
+            tokens,
+            dialect,
+            limits: Limits::default(),
+            depth: 0,
+            recovering: false,
+            diagnostics: vec![],
+        }
+    }
+
+    /// Only the depth, key count and array length limits apply here, the
+    /// rest are checked by the [`Lexer`].
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
     }
 
-    pub fn parse(&mut self) -> Result<Node, String> {
+    pub fn parse(&mut self) -> Result<Node, ParseError> {
         self.parse_literal()
     }
 
-    fn parse_object(&mut self) -> Result<Node, String> {
+    /// Parses a single root value and requires nothing but `Eof` after it.
+    pub fn parse_document(&mut self) -> Result<Node, ParseError> {
+        let root = self.parse()?;
+
+        if !self.is_eof() {
+            let error = self.error(ErrorCode::TrailingContent, "Expected end of input");
+            self.recover(error)?;
+        }
+
+        Ok(root)
+    }
+
+    /// Parses the whole document without stopping at the first error.
+    ///
+    /// After an error the parser skips to the next `,`, `}` or `]` and
+    /// carries on, putting a `Node::Error` where a value could not be
+    /// parsed. Returns the partial tree and every error found. Exceeding a
+    /// limit still stops the parse.
+    pub fn parse_recovering(&mut self) -> (Node, Vec<ParseError>) {
+        self.recovering = true;
+
+        let root = self.parse_document().unwrap_or_else(|error| {
+            let span = error.span;
+            self.diagnostics.push(error);
+
+            Node::Error(Token::with_span(TokenType::Eof, Literal::Null, span))
+        });
+
+        (root, std::mem::take(&mut self.diagnostics))
+    }
+
+    fn parse_object(&mut self) -> Result<Node, ParseError> {
         let left = self
-            .get_or_error(TokenType::LeftBrace, "Expected {")?
+            .get_or_error(TokenType::LeftBrace, ErrorCode::ExpectedValue, "Expected {")?
             .clone();
+        self.enter(left.span)?;
 
         let mut p: BTreeMap<String, Node> = BTreeMap::new();
 
         if self.get_current_token().token_type != TokenType::RightBrace {
-            match self.parse_property()? {
-                Node::Property(key, colon, value) => {
-                    match p.entry(key.literal.to_string()) {
-                        std::collections::btree_map::Entry::Occupied(_) => Err(format!(
-                            "Duplicate property key {} found",
-                            key.literal.to_string()
-                        ))?,
-                        std::collections::btree_map::Entry::Vacant(entry) => {
-                            entry.insert(Node::Property(key, colon, value))
-                        }
-                    };
+            loop {
+                if let Err(error) = self
+                    .parse_property()
+                    .and_then(|property| Self::insert_property(&mut p, property))
+                {
+                    self.recover(error)?;
                 }
-                _ => {}
-            }
 
-            while self.match_token(TokenType::Comma) {
-                self.get_token_advance();
-
-                match self.parse_property()? {
-                    Node::Property(key, colon, value) => {
-                        match p.entry(key.literal.to_string()) {
-                            std::collections::btree_map::Entry::Occupied(_) => Err(format!(
-                                "Duplicate property key {} found",
-                                key.literal.to_string()
-                            ))?,
-                            std::collections::btree_map::Entry::Vacant(entry) => {
-                                entry.insert(Node::Property(key, colon, value))
-                            }
-                        };
+                if p.len() > self.limits.max_object_keys {
+                    return Err(self.error(
+                        ErrorCode::LimitExceeded,
+                        &format!("Object has more than {} keys", self.limits.max_object_keys),
+                    ));
+                }
+
+                if self.match_token(TokenType::Comma) {
+                    self.get_token_advance();
+
+                    if self.match_trailing(TokenType::RightBrace) {
+                        break;
                     }
-                    _ => {}
+
+                    continue;
                 }
+
+                if self.match_token(TokenType::String) || self.match_token(TokenType::Identifier) {
+                    self.report(self.error(ErrorCode::UnclosedObject, "Expected }"))?;
+                    continue;
+                }
+
+                break;
             }
         }
 
-        let right = self
-            .get_or_error(TokenType::RightBrace, "Expected }")?
-            .clone();
+        let right = self.get_closing(
+            TokenType::RightBrace,
+            ErrorCode::UnclosedObject,
+            "Expected }",
+        )?;
+
+        self.depth -= 1;
 
         let r: Vec<Node> = p.into_values().collect();
         Ok(Node::Object(left, r, right))
     }
 
-    fn parse_property(&mut self) -> Result<Node, String> {
-        let key = self
-            .get_or_error(TokenType::Identifier, "Expected identifier")?
-            .clone();
+    fn insert_property(p: &mut BTreeMap<String, Node>, property: Node) -> Result<(), ParseError> {
+        if let Node::Property(key, colon, value) = property {
+            match p.entry(key.literal.to_string()) {
+                Entry::Occupied(_) => Err(ParseError::new(
+                    ErrorCode::DuplicateKey,
+                    &format!("Duplicate property key {} found", key.literal),
+                    key.span,
+                ))?,
+                Entry::Vacant(entry) => entry.insert(Node::Property(key, colon, value)),
+            };
+        }
+
+        Ok(())
+    }
+
+    fn parse_property(&mut self) -> Result<Node, ParseError> {
+        let Some(key) = self.get_current_token().key(self.dialect) else {
+            return Err(self.error(ErrorCode::ExpectedKey, "Expected identifier"));
+        };
+        self.get_token_advance();
+
         let colon = self
-            .get_or_error(TokenType::Colon, "Expected colon")?
+            .get_or_error(TokenType::Colon, ErrorCode::ExpectedColon, "Expected colon")?
             .clone();
         let value = self.parse_literal()?;
 
         Ok(Node::Property(key, colon, Box::new(value)))
     }
 
-    fn parse_list(&mut self) -> Result<Node, String> {
+    fn parse_list(&mut self) -> Result<Node, ParseError> {
         let left = self
-            .get_or_error(TokenType::LeftBracket, "Expected [")?
+            .get_or_error(
+                TokenType::LeftBracket,
+                ErrorCode::ExpectedValue,
+                "Expected [",
+            )?
             .clone();
+        self.enter(left.span)?;
 
         let mut properties = vec![];
 
         if self.get_current_token().token_type != TokenType::RightBracket {
-            properties.push(self.parse_literal()?);
-
-            while self.match_token(TokenType::Comma) {
-                self.get_token_advance();
+            loop {
                 properties.push(self.parse_literal()?);
+
+                if properties.len() > self.limits.max_array_length {
+                    return Err(self.error(
+                        ErrorCode::LimitExceeded,
+                        &format!(
+                            "Array has more than {} elements",
+                            self.limits.max_array_length
+                        ),
+                    ));
+                }
+
+                if self.match_token(TokenType::Comma) {
+                    self.get_token_advance();
+
+                    if self.match_trailing(TokenType::RightBracket) {
+                        break;
+                    }
+
+                    continue;
+                }
+
+                if self.starts_value() {
+                    self.report(self.error(ErrorCode::UnclosedArray, "Expected ]"))?;
+                    continue;
+                }
+
+                break;
             }
         }
 
-        let right = self
-            .get_or_error(TokenType::RightBracket, "Expected ]")?
-            .clone();
+        let right = self.get_closing(
+            TokenType::RightBracket,
+            ErrorCode::UnclosedArray,
+            "Expected ]",
+        )?;
+
+        self.depth -= 1;
 
         Ok(Node::List(left, properties, right))
     }
 
-    fn parse_literal(&mut self) -> Result<Node, String> {
+    fn parse_literal(&mut self) -> Result<Node, ParseError> {
         if self.match_token(TokenType::String) {
             return Ok(Node::Primary(self.get_token_advance().clone().literal));
         }
@@ -200,6 +399,11 @@ impl Parser {
             return Ok(Node::Primary(self.get_token_advance().clone().literal));
         }
 
+        if let Some(literal) = self.get_current_token().named_number() {
+            self.get_token_advance();
+            return Ok(Node::Primary(literal));
+        }
+
         if self.match_token(TokenType::LeftBracket) {
             return self.parse_list();
         }
@@ -208,15 +412,173 @@ impl Parser {
             return self.parse_object();
         }
 
-        Err("Unknown literal".to_string())
+        if self.match_token(TokenType::Invalid) {
+            return Ok(Node::Error(self.get_token_advance().clone()));
+        }
+
+        let token = self.get_current_token().clone();
+        self.recover(self.error(ErrorCode::ExpectedValue, "Unknown literal"))?;
+
+        Ok(Node::Error(token))
     }
 
-    fn get_or_error(&mut self, token_type: TokenType, error: &str) -> Result<&Token, String> {
+    fn enter(&mut self, span: Span) -> Result<(), ParseError> {
+        self.depth += 1;
+
+        if self.depth > self.limits.max_depth {
+            return Err(ParseError::new(
+                ErrorCode::LimitExceeded,
+                &format!("Nesting deeper than {} levels", self.limits.max_depth),
+                span,
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn get_or_error(
+        &mut self,
+        token_type: TokenType,
+        code: ErrorCode,
+        error: &str,
+    ) -> Result<&Token, ParseError> {
         if self.match_token(token_type) {
             return Ok(self.get_token_advance());
         }
 
-        Err(error.to_string())
+        Err(self.error(code, error))
+    }
+
+    /// Like `get_or_error` for `}` and `]`, but when recovering a missing
+    /// closer is reported and stood in for, so the enclosing value can
+    /// still be completed.
+    fn get_closing(
+        &mut self,
+        token_type: TokenType,
+        code: ErrorCode,
+        error: &str,
+    ) -> Result<Token, ParseError> {
+        if self.match_token(token_type.clone()) {
+            return Ok(self.get_token_advance().clone());
+        }
+
+        self.recover(self.error(code, error))?;
+
+        if self.match_token(token_type.clone()) {
+            return Ok(self.get_token_advance().clone());
+        }
+
+        Ok(Token::with_span(
+            token_type,
+            Literal::Null,
+            self.get_current_token().span,
+        ))
+    }
+
+    /// Records `error` when recovering, otherwise returns it.
+    fn report(&mut self, error: ParseError) -> Result<(), ParseError> {
+        if !self.recovering {
+            return Err(error);
+        }
+
+        let reported = self.diagnostics.last().map(|last| last.span) == Some(error.span)
+            || self.match_token(TokenType::Invalid);
+
+        if !reported {
+            self.diagnostics.push(error);
+        }
+
+        Ok(())
+    }
+
+    /// Reports `error` and skips to the next `,`, `}` or `]` at the current
+    /// nesting level.
+    fn recover(&mut self, error: ParseError) -> Result<(), ParseError> {
+        self.report(error)?;
+
+        let mut depth = 0;
+
+        while !self.is_eof() {
+            match self.get_current_token().token_type {
+                TokenType::Comma | TokenType::RightBrace | TokenType::RightBracket
+                    if depth == 0 =>
+                {
+                    break
+                }
+                TokenType::LeftBrace | TokenType::LeftBracket => depth += 1,
+                TokenType::RightBrace | TokenType::RightBracket => depth -= 1,
+                _ => {}
+            }
+
+            self.get_token_advance();
+        }
+
+        Ok(())
+    }
+
+    fn error(&self, code: ErrorCode, message: &str) -> ParseError {
+        let error = ParseError::new(code, message, self.get_current_token().span);
+
+        match self.hint(code) {
+            Some(hint) => error.with_hint(hint),
+            None => error,
+        }
+    }
+
+    /// Recognises common mistakes from the tokens around the error.
+    fn hint(&self, code: ErrorCode) -> Option<&'static str> {
+        let previous = self
+            .current
+            .checked_sub(1)
+            .and_then(|index| self.tokens.get(index))
+            .map(|token| &token.token_type);
+
+        match (code, previous, &self.get_current_token().token_type) {
+            (
+                ErrorCode::ExpectedKey | ErrorCode::ExpectedValue,
+                Some(TokenType::Comma),
+                TokenType::RightBrace | TokenType::RightBracket,
+            ) => Some("trailing commas are not allowed in JSON, remove the last `,`"),
+            (ErrorCode::ExpectedColon, Some(TokenType::String | TokenType::Identifier), _) => {
+                Some("add `:` after the key")
+            }
+            (ErrorCode::UnclosedObject, _, TokenType::RightBracket) => {
+                Some("this `]` would close an object, use `}`")
+            }
+            (ErrorCode::UnclosedArray, _, TokenType::RightBrace) => {
+                Some("this `}` would close an array, use `]`")
+            }
+            (ErrorCode::UnclosedObject, _, TokenType::String | TokenType::Identifier) => {
+                Some("add a `,` between properties")
+            }
+            (
+                ErrorCode::UnclosedArray,
+                _,
+                TokenType::String
+                | TokenType::Number
+                | TokenType::True
+                | TokenType::False
+                | TokenType::Null
+                | TokenType::LeftBrace
+                | TokenType::LeftBracket,
+            ) => Some("add a `,` between elements"),
+            _ => None,
+        }
+    }
+
+    fn starts_value(&mut self) -> bool {
+        [
+            TokenType::String,
+            TokenType::Number,
+            TokenType::True,
+            TokenType::False,
+            TokenType::Null,
+            TokenType::LeftBrace,
+            TokenType::LeftBracket,
+        ]
+        .into_iter()
+        .any(|token_type| self.match_token(token_type))
+            || self.get_current_token().named_number().is_some()
     }
 
     fn match_token(&mut self, token_type: TokenType) -> bool {
@@ -227,6 +589,10 @@ impl Parser {
         false
     }
 
+    fn match_trailing(&mut self, closing: TokenType) -> bool {
+        self.dialect.allows_trailing_commas() && self.match_token(closing)
+    }
+
     fn get_token_advance(&mut self) -> &Token {
         let token = self.tokens.get(self.current).unwrap();
         self.current += 1;
@@ -238,11 +604,40 @@ impl Parser {
     }
 
     fn is_eof(&self) -> bool {
-        self.current >= self.tokens.len()
+        self.current >= self.tokens.len() || self.get_current_token().token_type == TokenType::Eof
     }
 }
 
-struct PrettyPrint;
+/// Lexes and parses a complete JSON document.
+pub fn parse_str(input: &str) -> Result<Node, ParseError> {
+    let tokens = Lexer::new(input.as_bytes()).tokenize()?;
+
+    Parser::new(tokens).parse_document()
+}
+
+/// Parses a complete document in the given dialect, also returning the
+/// comments found so they can be put back by a formatter.
+pub fn parse_str_with(input: &str, dialect: Dialect) -> Result<(Node, Vec<Comment>), ParseError> {
+    let mut lexer = Lexer::with_dialect(input.as_bytes(), dialect);
+    let tokens = lexer.tokenize()?;
+    let root = Parser::with_dialect(tokens, dialect).parse_document()?;
+
+    Ok((root, lexer.take_comments()))
+}
+
+/// Parses a document reporting every lexical and syntax error in one pass,
+/// see [`Parser::parse_recovering`]. Errors are ordered by position.
+pub fn parse_str_recovering(input: &str, dialect: Dialect) -> (Node, Vec<ParseError>) {
+    let (tokens, mut errors) = Lexer::with_dialect(input.as_bytes(), dialect).tokenize_recovering();
+    let (root, diagnostics) = Parser::with_dialect(tokens, dialect).parse_recovering();
+
+    errors.extend(diagnostics);
+    errors.sort_by_key(|error| error.span.start);
+
+    (root, errors)
+}
+
+pub struct PrettyPrint;
 
 impl PrettyPrint {
     pub fn print(&self, root: &Node) -> String {
@@ -250,20 +645,19 @@ impl PrettyPrint {
     }
 
     pub fn dfs(&self, root: &Node) -> String {
-       self.trav(root, 0)
+        self.trav(root, 0)
     }
 
     fn trav(&self, node: &Node, mut depth: u32) -> String {
         match node {
-            Node::Primary(literal) => {
-                return literal.to_string()
-            },
+            Node::Primary(literal) => literal.to_string(),
+            Node::Error(_) => "null".to_string(),
             Node::Object(left_brace, vec, right_brace) => {
-                let brace_space = " ".repeat((depth * 4)as usize);
+                let brace_space = " ".repeat((depth * 4) as usize);
 
                 depth += 1;
 
-                let property_space = " ".repeat((depth * 4)as usize);
+                let property_space = " ".repeat((depth * 4) as usize);
 
                 let mut buf = String::new();
 
@@ -277,7 +671,7 @@ impl PrettyPrint {
                     buf += "\n";
                 }
 
-                for (i, node) in vec.into_iter().enumerate() {
+                for (i, node) in vec.iter().enumerate() {
                     buf += &property_space;
                     buf += self.trav(node, depth).as_str();
 
@@ -293,23 +687,21 @@ impl PrettyPrint {
                 }
                 buf += right_brace.literal.to_string().as_str();
 
-                depth -= 1;
-
-                return buf
-            },
+                buf
+            }
             Node::Property(key, colon, value) => {
                 let mut buf = String::new();
                 buf += format!("\"{}\"", &key.literal.to_string()).as_str();
                 buf += &colon.literal.to_string();
                 buf += " ";
-                buf += &self.trav(&value, depth);
+                buf += &self.trav(value, depth);
 
-                return buf
+                buf
             }
             Node::List(left_bracket, vec, right_bracket) => {
-                let bracket_space = " ".repeat((depth * 4)as usize);
+                let bracket_space = " ".repeat((depth * 4) as usize);
                 depth += 1;
-                let primary_space = " ".repeat((depth * 4)as usize);
+                let primary_space = " ".repeat((depth * 4) as usize);
 
                 let mut buf = String::new();
 
@@ -323,7 +715,7 @@ impl PrettyPrint {
                     buf += "\n";
                 }
 
-                for (i, node) in vec.into_iter().enumerate() {
+                for (i, node) in vec.iter().enumerate() {
                     buf += &primary_space;
                     buf += self.trav(node, depth).as_str();
 
@@ -339,10 +731,8 @@ impl PrettyPrint {
                 }
                 buf += right_bracket.literal.to_string().as_str();
 
-                depth -= 1;
-
-                return buf
-            },
+                buf
+            }
         }
     }
 }
@@ -356,47 +746,37 @@ impl Visitor<String> for PrettyPrint {
         value.to_string()
     }
 
-    fn visit_object(&self, left: &Token, properties: &Vec<Node>, right: &Token) -> String {
+    fn visit_object(&self, left: &Token, properties: &[Node], right: &Token) -> String {
         let properties = properties
-            .into_iter()
+            .iter()
             .map(|node| node.accept(self))
             .collect::<String>();
 
-        format!(
-            "{}{}{}",
-            left.literal.to_string(),
-            properties,
-            right.literal.to_string()
-        )
+        format!("{}{}{}", left.literal, properties, right.literal)
     }
 
     fn visit_property(&self, key: &Token, colon: &Token, value: &Node) -> String {
-        format!(
-            "{}{}{}",
-            key.literal.to_string(),
-            colon.literal.to_string(),
-            value.accept(self)
-        )
+        format!("{}{}{}", key.literal, colon.literal, value.accept(self))
     }
 
-    fn visit_list(&self, left: &Token, nodes: &Vec<Node>, right: &Token) -> String {
+    fn visit_list(&self, left: &Token, nodes: &[Node], right: &Token) -> String {
         let nodes = nodes
-            .into_iter()
+            .iter()
             .map(|node| node.accept(self))
             .collect::<String>();
 
-        format!(
-            "{}{}{}",
-            left.literal.to_string(),
-            nodes,
-            right.literal.to_string()
-        )
+        format!("{}{}{}", left.literal, nodes, right.literal)
+    }
+
+    fn visit_error(&self, _token: &Token) -> String {
+        "null".to_string()
     }
 }
 
 #[cfg(test)]
 mod node_tests {
     use super::*;
+    use crate::parser_update::printer::CompactPrint;
 
     #[test]
     fn creation() {
@@ -464,6 +844,7 @@ mod node_tests {
     }
 
     #[test]
+    #[allow(unused_variables, unused_mut)]
     fn dfs() {
         let mut parser = Parser::new(vec![
             Token::new(TokenType::LeftBracket, Literal::String("[".to_string())),
@@ -570,4 +951,202 @@ mod node_tests {
         let res = pretty.dfs(&root);
         println!("{res}");
     }
+
+    #[test]
+    fn parse_text() {
+        let root = parse_str(r#"{"name": "Alex", "tags": ["a", 1.5], "ok": true}"#).unwrap();
+
+        let pretty = PrettyPrint;
+        assert_eq!(
+            pretty.dfs(&root),
+            "{\n    \"name\": Alex,\n    \"ok\": true,\n    \"tags\": [\n        a,\n        1.5\n    ]\n}"
+        );
+
+        let error = parse_str("{\"a\": 1,\n \"a\": 2}").unwrap_err();
+        assert_eq!(error.message, "Duplicate property key a found");
+        assert_eq!((error.span.line, error.span.column), (2, 2));
+
+        assert_eq!(
+            parse_str("[1] 2").unwrap_err().message,
+            "Expected end of input"
+        );
+    }
+
+    #[test]
+    fn parse_jsonc() {
+        let input = "{\n  // compiler\n  \"strict\": true,\n  \"lib\": [\"es2020\", /* dom */],\n}";
+        let (root, comments) = parse_str_with(input, Dialect::Jsonc).unwrap();
+
+        assert_eq!(
+            PrettyPrint.dfs(&root),
+            "{\n    \"lib\": [\n        es2020\n    ],\n    \"strict\": true\n}"
+        );
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[1].span.line, 4);
+
+        assert_eq!(
+            parse_str_with("[1,]", Dialect::Json).unwrap_err().message,
+            "Unknown literal"
+        );
+        assert_eq!(
+            parse_str_with("[1,,]", Dialect::Jsonc).unwrap_err().message,
+            "Unknown literal"
+        );
+    }
+
+    #[test]
+    fn parse_json5() {
+        let input = "// config\n{ name: 'jtool', size: 0x10, ratio: .5, tags: ['a', 'b',], }";
+        let (root, comments) = parse_str_with(input, Dialect::Json5).unwrap();
+
+        assert_eq!(
+            PrettyPrint.dfs(&root),
+            "{\n    \"name\": jtool,\n    \"ratio\": 0.5,\n    \"size\": 16,\n    \"tags\": [\n        a,\n        b\n    ]\n}"
+        );
+        assert_eq!(comments[0].text, " config");
+
+        assert!(parse_str_with(input, Dialect::Json).is_err());
+        assert!(parse_str_with(input, Dialect::Jsonc).is_err());
+        assert_eq!(
+            parse_str_with("{a: b}", Dialect::Json5)
+                .unwrap_err()
+                .message,
+            "Unknown literal"
+        );
+    }
+
+    #[test]
+    fn parse_json5_keyword_keys() {
+        let input = "{null: 1, true: 2, false: 3, Infinity: NaN, NaN: -Infinity, if: 4}";
+        let (root, _) = parse_str_with(input, Dialect::Json5).unwrap();
+
+        for key in ["null", "true", "false", "Infinity", "NaN", "if"] {
+            assert!(root.get_member(key).is_some(), "missing {}", key);
+        }
+        assert!(matches!(
+            root.get_member("Infinity"),
+            Some(Node::Primary(Literal::Number(value))) if value.is_nan()
+        ));
+        assert!(matches!(
+            root.get_member("NaN"),
+            Some(Node::Primary(Literal::Number(value))) if *value == f64::NEG_INFINITY
+        ));
+
+        for input in ["{null: 1}", "{true: 1}"] {
+            assert_eq!(
+                parse_str_with(input, Dialect::Json).unwrap_err().code,
+                ErrorCode::ExpectedKey
+            );
+        }
+        for input in ["{1e999: 1}", "{-Infinity: 1}", "{[]: 1}"] {
+            assert_eq!(
+                parse_str_with(input, Dialect::Json5).unwrap_err().code,
+                ErrorCode::ExpectedKey
+            );
+        }
+    }
+
+    #[test]
+    fn parse_recovering() {
+        let input = "{\n  \"a\": ,\n  \"b\": [1 2],\n  \"c\" 3,\n  \"d\": tru,\n  \"e\": 5,\n}";
+        let (root, errors) = parse_str_recovering(input, Dialect::Json);
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| (error.message.as_str(), error.span.line))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Unknown literal", 2),
+                ("Expected ]", 3),
+                ("Expected colon", 4),
+                ("Unexpected identifier 'tru'", 5),
+                ("Expected identifier", 7),
+            ]
+        );
+        assert!(matches!(
+            &root,
+            Node::Object(_, properties, _) if matches!(&properties[0], Node::Property(_, _, value) if matches!(**value, Node::Error(_)))
+        ));
+        assert_eq!(
+            CompactPrint.print(&root),
+            r#"{"a":null,"b":[1,2],"d":null,"e":5}"#
+        );
+
+        let (_, errors) = parse_str_recovering("[1, {\"a\": [true}, 3", Dialect::Json);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Expected ]", "Expected ]"]
+        );
+
+        let (_, errors) = parse_str_recovering("{\"ok\": [1, 2]}", Dialect::Json);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn hints() {
+        let hint = |input: &str| parse_str(input).unwrap_err().hint.unwrap();
+
+        assert_eq!(hint("{\"a\": 1 \"b\": 2}"), "add a `,` between properties");
+        assert_eq!(
+            hint("[1, 2,]"),
+            "trailing commas are not allowed in JSON, remove the last `,`"
+        );
+        assert_eq!(
+            hint("{\"a\": 1,}"),
+            "trailing commas are not allowed in JSON, remove the last `,`"
+        );
+        assert_eq!(
+            hint("{'a': 1}"),
+            "JSON strings use double quotes, not single quotes"
+        );
+        assert_eq!(hint("{a: 1}"), "property keys must be quoted: `\"a\"`");
+        assert_eq!(hint("[True, None]"), "write `true` instead of `True`");
+        assert_eq!(hint("{\"a\": None}"), "write `null` instead of `None`");
+        assert_eq!(
+            hint("{\"a\": [1, 2}"),
+            "this `}` would close an array, use `]`"
+        );
+        assert_eq!(
+            hint("[{\"a\": 1]"),
+            "this `]` would close an object, use `}`"
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let parse = |input: &str, limits: Limits| {
+            let tokens = Lexer::new(input.as_bytes()).tokenize().unwrap();
+            Parser::new(tokens).limits(limits).parse_document()
+        };
+        let limits = Limits {
+            max_depth: 2,
+            max_object_keys: 2,
+            max_array_length: 3,
+            ..Limits::default()
+        };
+
+        assert!(parse("[[1, 2, 3], {\"a\": 1, \"b\": 2}]", limits).is_ok());
+
+        let error = parse("[[[]]]", limits).unwrap_err();
+        assert_eq!(error.code, ErrorCode::LimitExceeded);
+        assert_eq!(error.message, "Nesting deeper than 2 levels");
+        assert_eq!(error.span.start, 2);
+
+        let error = parse("{\"a\": 1, \"b\": 2, \"c\": 3}", limits).unwrap_err();
+        assert_eq!(error.message, "Object has more than 2 keys");
+
+        let error = parse("[1, 2, 3, 4]", limits).unwrap_err();
+        assert_eq!(error.message, "Array has more than 3 elements");
+
+        let deep = "[".repeat(100_000);
+        let error = parse_str(&deep).unwrap_err();
+        assert_eq!(error.message, "Nesting deeper than 128 levels");
+
+        let (_, errors) = parse_str_recovering(&deep, Dialect::Json);
+        assert_eq!(errors.last().unwrap().code, ErrorCode::LimitExceeded);
+    }
 }
@@ -0,0 +1,394 @@
+use std::{collections::BTreeSet, fmt::Display};
+
+use super::{
+    nodes::{Literal, Node},
+    pointer::{escape_token, PointerError},
+};
+
+/// One JSON Patch (RFC 6902) operation. Paths are JSON Pointers.
+#[derive(Debug, Clone)]
+pub enum Operation {
+    Add { path: String, value: Node },
+    Remove { path: String },
+    Replace { path: String, value: Node },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Node },
+}
+
+/// Why a patch could not be read or applied. Every variant holds the index
+/// of the operation at fault.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// The operation object is malformed, e.g. a missing `value`.
+    Invalid(usize, String),
+    Pointer(usize, PointerError),
+    /// A `test` operation found a different value at the path.
+    TestFailed(usize, String),
+    /// A `move` whose target is inside the value being moved.
+    MoveIntoChild(usize, String),
+}
+
+impl std::error::Error for PatchError {}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(index, message) => write!(f, "Operation {}: {}", index, message),
+            Self::Pointer(index, error) => write!(f, "Operation {}: {}", index, error),
+            Self::TestFailed(index, path) => {
+                write!(f, "Operation {}: test failed at '{}'", index, path)
+            }
+            Self::MoveIntoChild(index, path) => {
+                write!(
+                    f,
+                    "Operation {}: cannot move a value into its own child '{}'",
+                    index, path
+                )
+            }
+        }
+    }
+}
+
+/// A JSON Patch document: a list of operations applied in order.
+#[derive(Debug, Clone, Default)]
+pub struct Patch {
+    pub operations: Vec<Operation>,
+}
+
+impl Patch {
+    /// Reads a patch from its JSON form, an array of operation objects.
+    pub fn from_node(node: &Node) -> Result<Patch, PatchError> {
+        let Node::List(_, nodes, _) = node else {
+            return Err(PatchError::Invalid(0, "patch must be an array".to_string()));
+        };
+
+        let operations = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| read_operation(index, node))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Patch { operations })
+    }
+
+    pub fn to_node(&self) -> Node {
+        Node::list(
+            self.operations
+                .iter()
+                .map(|operation| {
+                    let (op, path, from, value) = match operation {
+                        Operation::Add { path, value } => ("add", path, None, Some(value)),
+                        Operation::Remove { path } => ("remove", path, None, None),
+                        Operation::Replace { path, value } => ("replace", path, None, Some(value)),
+                        Operation::Move { from, path } => ("move", path, Some(from), None),
+                        Operation::Copy { from, path } => ("copy", path, Some(from), None),
+                        Operation::Test { path, value } => ("test", path, None, Some(value)),
+                    };
+
+                    let mut properties = vec![];
+
+                    if let Some(from) = from {
+                        properties.push(Node::property("from", Node::string(from)));
+                    }
+
+                    properties.push(Node::property("op", Node::string(op)));
+                    properties.push(Node::property("path", Node::string(path)));
+
+                    if let Some(value) = value {
+                        properties.push(Node::property("value", value.clone()));
+                    }
+
+                    Node::object(properties)
+                })
+                .collect(),
+        )
+    }
+
+    /// Applies every operation to `target`. If any operation fails `target`
+    /// is left as it was.
+    pub fn apply(&self, target: &mut Node) -> Result<(), PatchError> {
+        let mut document = target.clone();
+
+        for (index, operation) in self.operations.iter().enumerate() {
+            apply_operation(&mut document, operation, index)?;
+        }
+
+        *target = document;
+        Ok(())
+    }
+
+    /// Builds a patch that turns `from` into `to`.
+    ///
+    /// Objects are compared key by key and arrays element by element after
+    /// trimming a common prefix and suffix, so a single insertion or removal
+    /// becomes one operation. Values of different types are replaced.
+    pub fn diff(from: &Node, to: &Node) -> Patch {
+        let mut patch = Patch::default();
+        diff_into(from, to, "", &mut patch.operations);
+        patch
+    }
+}
+
+fn read_operation(index: usize, node: &Node) -> Result<Operation, PatchError> {
+    let Node::Object(..) = node else {
+        return Err(PatchError::Invalid(
+            index,
+            "operation must be an object".to_string(),
+        ));
+    };
+
+    let string = |name: &str| match node.get_member(name) {
+        Some(Node::Primary(Literal::String(value))) => Ok(value.clone()),
+        Some(_) => Err(PatchError::Invalid(
+            index,
+            format!("'{}' must be a string", name),
+        )),
+        None => Err(PatchError::Invalid(index, format!("missing '{}'", name))),
+    };
+
+    let value = || {
+        node.get_member("value")
+            .cloned()
+            .ok_or_else(|| PatchError::Invalid(index, "missing 'value'".to_string()))
+    };
+
+    let path = string("path")?;
+
+    match string("op")?.as_str() {
+        "add" => Ok(Operation::Add {
+            path,
+            value: value()?,
+        }),
+        "remove" => Ok(Operation::Remove { path }),
+        "replace" => Ok(Operation::Replace {
+            path,
+            value: value()?,
+        }),
+        "move" => Ok(Operation::Move {
+            from: string("from")?,
+            path,
+        }),
+        "copy" => Ok(Operation::Copy {
+            from: string("from")?,
+            path,
+        }),
+        "test" => Ok(Operation::Test {
+            path,
+            value: value()?,
+        }),
+        op => Err(PatchError::Invalid(index, format!("unknown op '{}'", op))),
+    }
+}
+
+fn apply_operation(
+    document: &mut Node,
+    operation: &Operation,
+    index: usize,
+) -> Result<(), PatchError> {
+    let pointer = |error| PatchError::Pointer(index, error);
+
+    match operation {
+        Operation::Add { path, value } => document
+            .insert_pointer(path, value.clone())
+            .map_err(pointer)?,
+        Operation::Remove { path } => {
+            document.remove_pointer(path).map_err(pointer)?;
+        }
+        Operation::Replace { path, value } => {
+            *document.get_pointer_mut(path).map_err(pointer)? = value.clone()
+        }
+        Operation::Move { from, path } => {
+            if path == from {
+                document.get_pointer(from).map_err(pointer)?;
+                return Ok(());
+            }
+
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(PatchError::MoveIntoChild(index, path.clone()));
+            }
+
+            let value = document.remove_pointer(from).map_err(pointer)?;
+            document.insert_pointer(path, value).map_err(pointer)?;
+        }
+        Operation::Copy { from, path } => {
+            let value = document.get_pointer(from).map_err(pointer)?.clone();
+            document.insert_pointer(path, value).map_err(pointer)?;
+        }
+        Operation::Test { path, value } => {
+            if document.get_pointer(path).map_err(pointer)? != value {
+                return Err(PatchError::TestFailed(index, path.clone()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn diff_into(from: &Node, to: &Node, path: &str, operations: &mut Vec<Operation>) {
+    if from == to {
+        return;
+    }
+
+    match (from, to) {
+        (Node::Object(..), Node::Object(..)) => {
+            let old = from.member_map();
+            let new = to.member_map();
+            let keys = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+
+            for key in keys {
+                let path = format!("{}/{}", path, escape_token(key));
+
+                match (old.get(key).copied(), new.get(key).copied()) {
+                    (Some(old), Some(new)) => diff_into(old, new, &path, operations),
+                    (Some(_), None) => operations.push(Operation::Remove { path }),
+                    (None, Some(new)) => operations.push(Operation::Add {
+                        path,
+                        value: new.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Node::List(_, old, _), Node::List(_, new, _)) => {
+            let prefix = old
+                .iter()
+                .zip(new)
+                .take_while(|(old, new)| old == new)
+                .count();
+            let suffix = old[prefix..]
+                .iter()
+                .rev()
+                .zip(new[prefix..].iter().rev())
+                .take_while(|(old, new)| old == new)
+                .count();
+
+            let old = &old[prefix..old.len() - suffix];
+            let new = &new[prefix..new.len() - suffix];
+            let shared = old.len().min(new.len());
+
+            for index in 0..shared {
+                let path = format!("{}/{}", path, prefix + index);
+                diff_into(&old[index], &new[index], &path, operations);
+            }
+
+            for index in (shared..old.len()).rev() {
+                operations.push(Operation::Remove {
+                    path: format!("{}/{}", path, prefix + index),
+                });
+            }
+
+            for (index, value) in new.iter().enumerate().skip(shared) {
+                operations.push(Operation::Add {
+                    path: format!("{}/{}", path, prefix + index),
+                    value: value.clone(),
+                });
+            }
+        }
+        _ => operations.push(Operation::Replace {
+            path: path.to_string(),
+            value: to.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod patch_tests {
+    use super::*;
+    use crate::parser_update::{nodes::parse_str, printer::CompactPrint};
+
+    fn patched(document: &str, patch: &str) -> Result<String, PatchError> {
+        let mut document = parse_str(document).unwrap();
+        Patch::from_node(&parse_str(patch).unwrap())?.apply(&mut document)?;
+
+        Ok(CompactPrint.print(&document))
+    }
+
+    #[test]
+    fn apply() {
+        assert_eq!(
+            patched(
+                r#"{"foo": ["bar", "baz"], "q": {"x": 1}}"#,
+                r#"[
+                    {"op": "add", "path": "/foo/1", "value": "qux"},
+                    {"op": "remove", "path": "/foo/0"},
+                    {"op": "replace", "path": "/q/x", "value": [true]},
+                    {"op": "copy", "from": "/q", "path": "/r"},
+                    {"op": "move", "from": "/foo", "path": "/q/foo"},
+                    {"op": "add", "path": "/r/x/-", "value": null},
+                    {"op": "test", "path": "/q/foo/1", "value": "baz"}
+                ]"#
+            ),
+            Ok(r#"{"q":{"foo":["qux","baz"],"x":[true]},"r":{"x":[true,null]}}"#.to_string())
+        );
+        assert_eq!(
+            patched(
+                r#"{"a": 1}"#,
+                r#"[{"op": "replace", "path": "", "value": 2}]"#
+            ),
+            Ok("2".to_string())
+        );
+    }
+
+    #[test]
+    fn all_or_nothing() {
+        let mut document = parse_str(r#"{"a": [1, 2]}"#).unwrap();
+        let patch = parse_str(
+            r#"[
+                {"op": "add", "path": "/b", "value": 1},
+                {"op": "test", "path": "/a/0", "value": 2}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Patch::from_node(&patch).unwrap().apply(&mut document),
+            Err(PatchError::TestFailed(1, "/a/0".to_string()))
+        );
+        assert_eq!(CompactPrint.print(&document), r#"{"a":[1,2]}"#);
+
+        assert_eq!(
+            patched("{}", r#"[{"op": "remove", "path": "/x"}]"#),
+            Err(PatchError::Pointer(
+                0,
+                PointerError::NotFound("/x".to_string())
+            ))
+        );
+        assert_eq!(
+            patched(
+                r#"{"a": {}}"#,
+                r#"[{"op": "move", "from": "/a", "path": "/a/b"}]"#
+            ),
+            Err(PatchError::MoveIntoChild(0, "/a/b".to_string()))
+        );
+        assert_eq!(
+            patched("{}", r#"[{"op": "add", "path": "/x"}]"#),
+            Err(PatchError::Invalid(0, "missing 'value'".to_string()))
+        );
+    }
+
+    #[test]
+    fn diff() {
+        let from =
+            parse_str(r#"{"keep": 1, "drop": true, "list": [1, 2, 3, 4], "nested": {"x": "a"}}"#)
+                .unwrap();
+        let to =
+            parse_str(r#"{"keep": 1, "add": null, "list": [1, 3, 4, 5], "nested": {"x": "b"}}"#)
+                .unwrap();
+
+        let patch = Patch::diff(&from, &to);
+
+        assert_eq!(
+            CompactPrint.print(&patch.to_node()),
+            r#"[{"op":"add","path":"/add","value":null},{"op":"remove","path":"/drop"},{"op":"replace","path":"/list/1","value":3},{"op":"replace","path":"/list/2","value":4},{"op":"replace","path":"/list/3","value":5},{"op":"replace","path":"/nested/x","value":"b"}]"#
+        );
+
+        let mut document = from.clone();
+        Patch::from_node(&patch.to_node())
+            .unwrap()
+            .apply(&mut document)
+            .unwrap();
+        assert_eq!(CompactPrint.print(&document), CompactPrint.print(&to));
+        assert!(Patch::diff(&to, &to).operations.is_empty());
+    }
+}
@@ -0,0 +1,402 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use super::nodes::{Literal, Node};
+
+/// Why a JSON Pointer could not be resolved. Every variant holds the
+/// pointer up to and including the segment that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerError {
+    /// Not empty and not starting with `/`, or a `~` not followed by `0` or `1`.
+    Syntax(String),
+    NotFound(String),
+    NotAContainer(String),
+    InvalidIndex(String),
+    IndexOutOfBounds(String, usize),
+}
+
+impl std::error::Error for PointerError {}
+
+impl Display for PointerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Syntax(pointer) => write!(f, "Invalid JSON Pointer '{}'", pointer),
+            Self::NotFound(pointer) => write!(f, "No value found at '{}'", pointer),
+            Self::NotAContainer(pointer) => {
+                write!(
+                    f,
+                    "Cannot descend into '{}', not an object or array",
+                    pointer
+                )
+            }
+            Self::InvalidIndex(pointer) => write!(f, "Invalid array index at '{}'", pointer),
+            Self::IndexOutOfBounds(pointer, len) => {
+                write!(
+                    f,
+                    "Index at '{}' is out of bounds for length {}",
+                    pointer, len
+                )
+            }
+        }
+    }
+}
+
+/// Splits an RFC 6901 pointer into unescaped reference tokens. The empty
+/// pointer refers to the whole document and has no tokens.
+pub fn parse_pointer(pointer: &str) -> Result<Vec<String>, PointerError> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(PointerError::Syntax(pointer.to_string()));
+    };
+
+    rest.split('/')
+        .map(|token| {
+            let mut unescaped = String::with_capacity(token.len());
+            let mut characters = token.chars();
+
+            while let Some(character) = characters.next() {
+                match character {
+                    '~' => match characters.next() {
+                        Some('0') => unescaped.push('~'),
+                        Some('1') => unescaped.push('/'),
+                        _ => return Err(PointerError::Syntax(pointer.to_string())),
+                    },
+                    _ => unescaped.push(character),
+                }
+            }
+
+            Ok(unescaped)
+        })
+        .collect()
+}
+
+/// Escapes `~` and `/` so `token` can be used as one pointer segment.
+pub fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Builds a pointer from unescaped tokens.
+pub fn to_pointer<S: AsRef<str>>(tokens: &[S]) -> String {
+    tokens
+        .iter()
+        .map(|token| format!("/{}", escape_token(token.as_ref())))
+        .collect()
+}
+
+impl Node {
+    /// The value of the member named `key` if this is an object.
+    pub fn get_member(&self, key: &str) -> Option<&Node> {
+        match self {
+            Node::Object(_, properties, _) => properties
+                .iter()
+                .find_map(|property| member_value(property, key)),
+            _ => None,
+        }
+    }
+
+    /// The members of this object by key, empty for anything else. Build
+    /// it once when looking up many keys of the same object.
+    pub fn member_map(&self) -> BTreeMap<&str, &Node> {
+        let Node::Object(_, properties, _) = self else {
+            return BTreeMap::new();
+        };
+
+        properties
+            .iter()
+            .filter_map(|property| match property {
+                Node::Property(key, _, value) => match &key.literal {
+                    Literal::String(key) => Some((key.as_str(), &**value)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn get_pointer(&self, pointer: &str) -> Result<&Node, PointerError> {
+        let mut node = self;
+        let mut path = String::new();
+
+        for token in parse_pointer(pointer)? {
+            path = format!("{}/{}", path, escape_token(&token));
+
+            node = match node {
+                Node::Object(..) => node
+                    .get_member(&token)
+                    .ok_or_else(|| PointerError::NotFound(path.clone()))?,
+                Node::List(_, nodes, _) => &nodes[existing_index(&token, nodes.len(), &path)?],
+                _ => return Err(PointerError::NotAContainer(path)),
+            };
+        }
+
+        Ok(node)
+    }
+
+    pub fn get_pointer_mut(&mut self, pointer: &str) -> Result<&mut Node, PointerError> {
+        let mut node = self;
+        let mut path = String::new();
+
+        for token in parse_pointer(pointer)? {
+            path = format!("{}/{}", path, escape_token(&token));
+
+            node = match node {
+                Node::Object(_, properties, _) => properties
+                    .iter_mut()
+                    .find_map(|property| member_value_mut(property, &token))
+                    .ok_or_else(|| PointerError::NotFound(path.clone()))?,
+                Node::List(_, nodes, _) => {
+                    let index = existing_index(&token, nodes.len(), &path)?;
+                    &mut nodes[index]
+                }
+                _ => return Err(PointerError::NotAContainer(path)),
+            };
+        }
+
+        Ok(node)
+    }
+
+    /// Sets the value at `pointer`, returning the value it replaced.
+    ///
+    /// A missing object member is added in key order. In arrays an existing
+    /// index is replaced and `-` or the length appends. The parent must exist.
+    pub fn set_pointer(
+        &mut self,
+        pointer: &str,
+        value: Node,
+    ) -> Result<Option<Node>, PointerError> {
+        let Some((parent, token, path)) = split_last(pointer)? else {
+            return Ok(Some(std::mem::replace(self, value)));
+        };
+
+        match self.get_pointer_mut(&parent)? {
+            Node::Object(_, properties, _) => {
+                if let Some(current) = properties
+                    .iter_mut()
+                    .find_map(|property| member_value_mut(property, &token))
+                {
+                    return Ok(Some(std::mem::replace(current, value)));
+                }
+
+                let index = properties.partition_point(|property| {
+                    matches!(property, Node::Property(key, _, _) if key.literal.to_string() < token)
+                });
+                properties.insert(index, Node::property(&token, value));
+
+                Ok(None)
+            }
+            Node::List(_, nodes, _) => {
+                let index = if token == "-" {
+                    nodes.len()
+                } else {
+                    parse_index(&token, &path)?
+                };
+
+                match index.cmp(&nodes.len()) {
+                    std::cmp::Ordering::Less => {
+                        Ok(Some(std::mem::replace(&mut nodes[index], value)))
+                    }
+                    std::cmp::Ordering::Equal => {
+                        nodes.push(value);
+                        Ok(None)
+                    }
+                    std::cmp::Ordering::Greater => {
+                        Err(PointerError::IndexOutOfBounds(path, nodes.len()))
+                    }
+                }
+            }
+            _ => Err(PointerError::NotAContainer(path)),
+        }
+    }
+
+    /// Adds `value` the way a JSON Patch `add` does: like
+    /// [`Node::set_pointer`], except an array index inserts before the
+    /// element already there instead of replacing it.
+    pub fn insert_pointer(&mut self, pointer: &str, value: Node) -> Result<(), PointerError> {
+        if let Some((parent, token, path)) = split_last(pointer)? {
+            if let Node::List(_, nodes, _) = self.get_pointer_mut(&parent)? {
+                if token != "-" {
+                    let index = parse_index(&token, &path)?;
+
+                    if index > nodes.len() {
+                        return Err(PointerError::IndexOutOfBounds(path, nodes.len()));
+                    }
+
+                    nodes.insert(index, value);
+                    return Ok(());
+                }
+            }
+        }
+
+        self.set_pointer(pointer, value).map(|_| ())
+    }
+
+    /// Removes and returns the value at `pointer`. The whole document cannot
+    /// be removed.
+    pub fn remove_pointer(&mut self, pointer: &str) -> Result<Node, PointerError> {
+        let Some((parent, token, path)) = split_last(pointer)? else {
+            return Err(PointerError::NotFound(pointer.to_string()));
+        };
+
+        match self.get_pointer_mut(&parent)? {
+            Node::Object(_, properties, _) => {
+                let index = properties
+                    .iter()
+                    .position(|property| member_value(property, &token).is_some())
+                    .ok_or(PointerError::NotFound(path))?;
+
+                match properties.remove(index) {
+                    Node::Property(_, _, value) => Ok(*value),
+                    node => Ok(node),
+                }
+            }
+            Node::List(_, nodes, _) => {
+                let index = existing_index(&token, nodes.len(), &path)?;
+                Ok(nodes.remove(index))
+            }
+            _ => Err(PointerError::NotAContainer(path)),
+        }
+    }
+}
+
+fn member_value<'a>(property: &'a Node, token: &str) -> Option<&'a Node> {
+    match property {
+        Node::Property(key, _, value) if is_key(&key.literal, token) => Some(value),
+        _ => None,
+    }
+}
+
+fn member_value_mut<'a>(property: &'a mut Node, token: &str) -> Option<&'a mut Node> {
+    match property {
+        Node::Property(key, _, value) if is_key(&key.literal, token) => Some(value),
+        _ => None,
+    }
+}
+
+fn is_key(key: &Literal, token: &str) -> bool {
+    matches!(key, Literal::String(key) if key == token)
+}
+
+/// Splits off the last token, giving the parent pointer, the unescaped
+/// token and the full pointer for errors. `None` for the root.
+fn split_last(pointer: &str) -> Result<Option<(String, String, String)>, PointerError> {
+    let mut tokens = parse_pointer(pointer)?;
+
+    Ok(tokens
+        .pop()
+        .map(|token| (to_pointer(&tokens), token, pointer.to_string())))
+}
+
+/// Array indices are `0` or digits without a leading zero.
+fn parse_index(token: &str, path: &str) -> Result<usize, PointerError> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|byte| byte.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+
+    if !valid {
+        return Err(PointerError::InvalidIndex(path.to_string()));
+    }
+
+    token
+        .parse::<usize>()
+        .map_err(|_| PointerError::InvalidIndex(path.to_string()))
+}
+
+fn existing_index(token: &str, len: usize, path: &str) -> Result<usize, PointerError> {
+    if token == "-" {
+        return Err(PointerError::IndexOutOfBounds(path.to_string(), len));
+    }
+
+    let index = parse_index(token, path)?;
+
+    if index >= len {
+        return Err(PointerError::IndexOutOfBounds(path.to_string(), len));
+    }
+
+    Ok(index)
+}
+
+#[cfg(test)]
+mod pointer_tests {
+    use super::*;
+    use crate::parser_update::{nodes::parse_str, printer::CompactPrint};
+
+    const DOCUMENT: &str = r#"{"a": [{"b": 1}, 2], "m~n": {"c/d": true}, "": 0}"#;
+
+    #[test]
+    fn get() {
+        let root = parse_str(DOCUMENT).unwrap();
+        let get = |pointer: &str| CompactPrint.print(root.get_pointer(pointer).unwrap());
+
+        assert_eq!(get("/a/0/b"), "1");
+        assert_eq!(get("/m~0n/c~1d"), "true");
+        assert_eq!(get("/"), "0");
+        assert_eq!(get(""), CompactPrint.print(&root));
+
+        assert_eq!(
+            parse_pointer("/a~2"),
+            Err(PointerError::Syntax("/a~2".to_string()))
+        );
+        assert_eq!(
+            root.get_pointer("a").unwrap_err(),
+            PointerError::Syntax("a".to_string())
+        );
+        assert_eq!(
+            root.get_pointer("/x/y").unwrap_err(),
+            PointerError::NotFound("/x".to_string())
+        );
+        assert_eq!(
+            root.get_pointer("/a/1/b").unwrap_err(),
+            PointerError::NotAContainer("/a/1/b".to_string())
+        );
+        assert_eq!(
+            root.get_pointer("/a/01").unwrap_err(),
+            PointerError::InvalidIndex("/a/01".to_string())
+        );
+        assert_eq!(
+            root.get_pointer("/a/2").unwrap_err(),
+            PointerError::IndexOutOfBounds("/a/2".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn set_and_remove() {
+        let mut root = parse_str(DOCUMENT).unwrap();
+
+        if let Node::Primary(Literal::Number(value)) = root.get_pointer_mut("/a/1").unwrap() {
+            *value += 1.0;
+        }
+
+        root.set_pointer("/a/-", Node::Primary(Literal::Null))
+            .unwrap();
+        root.set_pointer("/b", Node::list(vec![])).unwrap();
+        let replaced = root.set_pointer("/a/0/b", Node::Primary(Literal::Bool(false)));
+        assert_eq!(CompactPrint.print(&replaced.unwrap().unwrap()), "1");
+
+        assert_eq!(
+            CompactPrint.print(&root.remove_pointer("/m~0n").unwrap()),
+            r#"{"c/d":true}"#
+        );
+        root.remove_pointer("/a/1").unwrap();
+
+        assert_eq!(
+            CompactPrint.print(&root),
+            r#"{"":0,"a":[{"b":false},null],"b":[]}"#
+        );
+
+        assert_eq!(
+            root.set_pointer("/a/5", Node::Primary(Literal::Null))
+                .unwrap_err(),
+            PointerError::IndexOutOfBounds("/a/5".to_string(), 2)
+        );
+        assert_eq!(
+            root.remove_pointer("/b/0").unwrap_err(),
+            PointerError::IndexOutOfBounds("/b/0".to_string(), 0)
+        );
+        assert_eq!(
+            root.remove_pointer("/x").unwrap_err(),
+            PointerError::NotFound("/x".to_string())
+        );
+    }
+}
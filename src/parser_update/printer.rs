@@ -0,0 +1,107 @@
+use super::{
+    canonical::format_number,
+    nodes::{Literal, Node, Token, Visitor},
+};
+
+/// Serializes a [`Node`] as JSON without any whitespace. Numbers take the
+/// shortest form that reads back as the same value, with an exponent for
+/// very large or small ones.
+pub struct CompactPrint;
+
+impl CompactPrint {
+    pub fn print(&self, root: &Node) -> String {
+        root.accept(self)
+    }
+}
+
+impl Visitor<String> for CompactPrint {
+    fn visit_primary(&self, value: &Literal) -> String {
+        match value {
+            Literal::String(value) => escape_string(value),
+            Literal::Number(value) => format_number(*value).unwrap_or_else(|_| "null".to_string()),
+            _ => value.to_string(),
+        }
+    }
+
+    fn visit_object(&self, _left: &Token, properties: &[Node], _right: &Token) -> String {
+        let properties = properties
+            .iter()
+            .map(|node| node.accept(self))
+            .collect::<Vec<String>>();
+
+        format!("{{{}}}", properties.join(","))
+    }
+
+    fn visit_property(&self, key: &Token, _colon: &Token, value: &Node) -> String {
+        format!(
+            "{}:{}",
+            escape_string(&key.literal.to_string()),
+            value.accept(self)
+        )
+    }
+
+    fn visit_list(&self, _left: &Token, nodes: &[Node], _right: &Token) -> String {
+        let nodes = nodes
+            .iter()
+            .map(|node| node.accept(self))
+            .collect::<Vec<String>>();
+
+        format!("[{}]", nodes.join(","))
+    }
+
+    fn visit_error(&self, _token: &Token) -> String {
+        "null".to_string()
+    }
+}
+
+/// Quotes `value` as a JSON string, escaping only what JSON requires.
+pub fn escape_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{0}'..='\u{1f}' => escaped.push_str(&format!("\\u{:04x}", character as u32)),
+            _ => escaped.push(character),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod printer_tests {
+    use super::*;
+    use crate::parser_update::nodes::parse_str;
+
+    #[test]
+    fn compact() {
+        let root =
+            parse_str("{ \"b\": [1, 2.5, null],\n \"a\": {\"c\": \"x\\ty\\u0001\"} }").unwrap();
+
+        assert_eq!(
+            CompactPrint.print(&root),
+            r#"{"a":{"c":"x\ty\u0001"},"b":[1,2.5,null]}"#
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        let root = parse_str("[1e300, -2.5e-300, 1e21, 123456789012345680000, 0.000001]").unwrap();
+        let printed = CompactPrint.print(&root);
+
+        assert_eq!(
+            printed,
+            "[1e+300,-2.5e-300,1e+21,123456789012345680000,0.000001]"
+        );
+        assert_eq!(parse_str(&printed).unwrap(), root);
+    }
+}
@@ -0,0 +1,677 @@
+use std::fmt::Display;
+
+/// Gives up on a match after this many steps, so a large pattern against a
+/// long text cannot hang a query or validation.
+const STEP_LIMIT: usize = 10_000_000;
+
+/// The most instructions a pattern may compile to, since counted
+/// repetitions such as `(a{1000}){1000}` multiply in size.
+const PROGRAM_LIMIT: usize = 100_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl std::error::Error for RegexError {}
+
+impl Display for RegexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ClassItem {
+    Range(char, char),
+    /// A shorthand such as `\d`.
+    In(&'static [(char, char)]),
+    /// A negated shorthand such as `\D`.
+    NotIn(&'static [(char, char)]),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    Concat(Vec<Expr>),
+    Alternate(Vec<Expr>),
+    Repeat(Box<Expr>, usize, Option<usize>),
+}
+
+/// A small regular expression engine.
+///
+/// Patterns compile to a program for a Pike VM, which runs every possible
+/// match in lockstep. Matching takes time proportional to the length of
+/// the text times the size of the program and never recurses, so untrusted
+/// text cannot exhaust the stack. Supports the I-Regexp subset used by JSONPath and the common parts of
+/// ECMA-262 used in JSON Schema patterns: literals, `.`, bracket classes,
+/// `\d \w \s` and their negations, groups, alternation, `^`, `$` and the
+/// `* + ? {n,m}` quantifiers. Lookaround and backreferences are not
+/// supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regex {
+    program: Vec<Inst>,
+    step_limit: usize,
+    steps_per_character: usize,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, RegexError> {
+        let mut parser = PatternParser {
+            chars: pattern.chars().collect(),
+            position: 0,
+        };
+        let expr = parser.alternation()?;
+
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("Unmatched ')'"));
+        }
+
+        let mut compiler = Compiler { program: vec![] };
+        compiler.emit(&expr).map_err(|message| RegexError {
+            message: message.to_string(),
+            position: parser.position,
+        })?;
+        compiler.program.push(Inst::Match);
+
+        Ok(Regex {
+            program: compiler.program,
+            step_limit: STEP_LIMIT,
+            steps_per_character: 0,
+        })
+    }
+
+    /// The most steps a match may take before failing with an error.
+    pub fn step_limit(mut self, limit: usize) -> Self {
+        self.step_limit = limit;
+        self
+    }
+
+    /// Raises the step limit for long texts to this many steps per
+    /// character, so the limit stops runaway patterns without rejecting
+    /// plain matches against long text.
+    pub fn steps_per_character(mut self, steps: usize) -> Self {
+        self.steps_per_character = steps;
+        self
+    }
+
+    /// Whether the pattern matches anywhere in `text`.
+    pub fn is_match(&self, text: &str) -> Result<bool, RegexError> {
+        self.run(text, false)
+    }
+
+    /// Whether the pattern matches the whole of `text`.
+    pub fn is_full_match(&self, text: &str) -> Result<bool, RegexError> {
+        self.run(text, true)
+    }
+
+    /// Runs the program over `text`, advancing every live thread one
+    /// character at a time. Unanchored searches start a new thread at
+    /// every position.
+    fn run(&self, text: &str, full: bool) -> Result<bool, RegexError> {
+        let text = text.chars().collect::<Vec<char>>();
+        let mut threads = Threads {
+            seen: vec![usize::MAX; self.program.len()],
+            stack: vec![],
+            steps: 0,
+            limit: self
+                .step_limit
+                .max(self.steps_per_character.saturating_mul(text.len())),
+        };
+
+        let mut current = vec![];
+        let mut next = vec![];
+
+        for position in 0..=text.len() {
+            if !full || position == 0 {
+                threads.add(&self.program, &mut current, 0, position, text.len())?;
+            }
+
+            if current.is_empty() && full {
+                return Ok(false);
+            }
+
+            let character = text.get(position).copied();
+
+            for &pc in &current {
+                threads.step(position)?;
+
+                let accepted = match &self.program[pc] {
+                    Inst::Match if !full || position == text.len() => return Ok(true),
+                    Inst::Char(expected) => character == Some(*expected),
+                    Inst::Any => {
+                        character.is_some_and(|character| character != '\n' && character != '\r')
+                    }
+                    Inst::Class(items, negated) => character.is_some_and(|character| {
+                        let found = items.iter().any(|item| match item {
+                            ClassItem::Range(low, high) => *low <= character && character <= *high,
+                            ClassItem::In(ranges) => in_ranges(ranges, character),
+                            ClassItem::NotIn(ranges) => !in_ranges(ranges, character),
+                        });
+
+                        found != *negated
+                    }),
+                    _ => false,
+                };
+
+                if accepted {
+                    threads.add(&self.program, &mut next, pc + 1, position + 1, text.len())?;
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+
+        Ok(false)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    /// Continues at both targets.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn emit(&mut self, expr: &Expr) -> Result<(), &'static str> {
+        match expr {
+            Expr::Char(character) => {
+                self.push(Inst::Char(*character))?;
+            }
+            Expr::Any => {
+                self.push(Inst::Any)?;
+            }
+            Expr::Class(items, negated) => {
+                self.push(Inst::Class(items.clone(), *negated))?;
+            }
+            Expr::Start => {
+                self.push(Inst::Start)?;
+            }
+            Expr::End => {
+                self.push(Inst::End)?;
+            }
+            Expr::Concat(items) => {
+                for item in items {
+                    self.emit(item)?;
+                }
+            }
+            Expr::Alternate(alternatives) => {
+                let mut jumps = vec![];
+
+                for (index, alternative) in alternatives.iter().enumerate() {
+                    if index + 1 == alternatives.len() {
+                        self.emit(alternative)?;
+                        break;
+                    }
+
+                    let split = self.push(Inst::Split(self.program.len() + 1, 0))?;
+                    self.emit(alternative)?;
+                    jumps.push(self.push(Inst::Jump(0))?);
+                    self.patch(split);
+                }
+
+                for jump in jumps {
+                    self.patch(jump);
+                }
+            }
+            Expr::Repeat(inner, min, max) => {
+                for _ in 0..*min {
+                    self.emit(inner)?;
+                }
+
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(self.program.len() + 1, 0))?;
+                        self.emit(inner)?;
+                        self.push(Inst::Jump(split))?;
+                        self.patch(split);
+                    }
+                    Some(max) => {
+                        let mut splits = vec![];
+
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(self.program.len() + 1, 0))?);
+                            self.emit(inner)?;
+                        }
+
+                        for split in splits {
+                            self.patch(split);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push(&mut self, inst: Inst) -> Result<usize, &'static str> {
+        if self.program.len() >= PROGRAM_LIMIT {
+            return Err("Pattern too large");
+        }
+
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    /// Points the open branch of the split or jump at `pc` to the next
+    /// instruction.
+    fn patch(&mut self, pc: usize) {
+        let target = self.program.len();
+
+        match &mut self.program[pc] {
+            Inst::Split(_, next) | Inst::Jump(next) => *next = target,
+            _ => unreachable!("only splits and jumps are patched"),
+        }
+    }
+}
+
+/// The bookkeeping shared by every step of a run.
+struct Threads {
+    /// The position each instruction was last added at, so no thread is
+    /// added twice for one position.
+    seen: Vec<usize>,
+    stack: Vec<usize>,
+    steps: usize,
+    limit: usize,
+}
+
+impl Threads {
+    fn step(&mut self, position: usize) -> Result<(), RegexError> {
+        self.steps += 1;
+
+        if self.steps > self.limit {
+            return Err(RegexError {
+                message: format!("Match exceeded {} steps", self.limit),
+                position,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Adds the thread at `pc` to `list`, following splits, jumps and
+    /// anchors until every thread waits on a character or a match.
+    fn add(
+        &mut self,
+        program: &[Inst],
+        list: &mut Vec<usize>,
+        pc: usize,
+        position: usize,
+        len: usize,
+    ) -> Result<(), RegexError> {
+        self.stack.push(pc);
+
+        while let Some(pc) = self.stack.pop() {
+            if self.seen[pc] == position {
+                continue;
+            }
+
+            self.seen[pc] = position;
+            self.step(position)?;
+
+            match program[pc] {
+                Inst::Split(first, second) => {
+                    self.stack.push(second);
+                    self.stack.push(first);
+                }
+                Inst::Jump(next) => self.stack.push(next),
+                Inst::Start if position == 0 => self.stack.push(pc + 1),
+                Inst::End if position == len => self.stack.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                _ => list.push(pc),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct PatternParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl PatternParser {
+    fn alternation(&mut self) -> Result<Expr, RegexError> {
+        let mut alternatives = vec![self.concat()?];
+
+        while self.eat('|') {
+            alternatives.push(self.concat()?);
+        }
+
+        Ok(match alternatives.len() {
+            1 => alternatives.remove(0),
+            _ => Expr::Alternate(alternatives),
+        })
+    }
+
+    fn concat(&mut self) -> Result<Expr, RegexError> {
+        let mut items = vec![];
+
+        while let Some(character) = self.peek() {
+            if character == '|' || character == ')' {
+                break;
+            }
+
+            let atom = self.atom()?;
+            items.push(self.quantifier(atom)?);
+        }
+
+        Ok(Expr::Concat(items))
+    }
+
+    fn atom(&mut self) -> Result<Expr, RegexError> {
+        let character = self.next().ok_or_else(|| self.error("Unexpected end"))?;
+
+        Ok(match character {
+            '(' => {
+                if self.peek() == Some('?') {
+                    self.next();
+
+                    if !self.eat(':') {
+                        return Err(self.error("Unsupported group"));
+                    }
+                }
+
+                let inner = self.alternation()?;
+
+                if !self.eat(')') {
+                    return Err(self.error("Expected ')'"));
+                }
+
+                inner
+            }
+            '[' => self.class()?,
+            '.' => Expr::Any,
+            '^' => Expr::Start,
+            '$' => Expr::End,
+            '\\' => match self.escape()? {
+                ClassItem::Range(start, end) if start == end => Expr::Char(start),
+                item => Expr::Class(vec![item], false),
+            },
+            '*' | '+' | '?' | '{' => return Err(self.error("Nothing to repeat")),
+            character => Expr::Char(character),
+        })
+    }
+
+    fn quantifier(&mut self, atom: Expr) -> Result<Expr, RegexError> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.next();
+                let min = self.integer()?;
+                let max = if self.eat(',') {
+                    match self.peek() {
+                        Some('}') => None,
+                        _ => Some(self.integer()?),
+                    }
+                } else {
+                    Some(min)
+                };
+
+                if self.peek() != Some('}') || max.is_some_and(|max| max < min) {
+                    return Err(self.error("Invalid repetition"));
+                }
+
+                (min, max)
+            }
+            _ => return Ok(atom),
+        };
+
+        self.next();
+        // Lazy quantifiers only change which match is found, not whether
+        // one exists, so they can be treated as greedy.
+        self.eat('?');
+
+        Ok(Expr::Repeat(Box::new(atom), min, max))
+    }
+
+    fn class(&mut self) -> Result<Expr, RegexError> {
+        let negated = self.eat('^');
+        let mut items = vec![];
+        let mut first = true;
+
+        loop {
+            // A `]` right after the opening bracket is a literal.
+            let start = match self.next() {
+                None => return Err(self.error("Unterminated character class")),
+                Some(']') if !first => break,
+                Some('\\') => self.escape()?,
+                Some(character) => ClassItem::Range(character, character),
+            };
+
+            first = false;
+
+            let ClassItem::Range(low, _) = start else {
+                items.push(start);
+                continue;
+            };
+
+            if self.peek() == Some('-') && self.chars.get(self.position + 1) != Some(&']') {
+                self.next();
+
+                let high = match self.next() {
+                    Some('\\') => match self.escape()? {
+                        ClassItem::Range(high, _) => high,
+                        _ => return Err(self.error("Invalid class range")),
+                    },
+                    Some(high) => high,
+                    None => return Err(self.error("Unterminated character class")),
+                };
+
+                if high < low {
+                    return Err(self.error("Invalid class range"));
+                }
+
+                items.push(ClassItem::Range(low, high));
+            } else {
+                items.push(start);
+            }
+        }
+
+        Ok(Expr::Class(items, negated))
+    }
+
+    /// Reads the escape after a `\`. Shorthand classes come back as `In` or
+    /// `NotIn`, single characters as a one character range.
+    fn escape(&mut self) -> Result<ClassItem, RegexError> {
+        let character = self.next().ok_or_else(|| self.error("Unexpected end"))?;
+
+        let single = |character| ClassItem::Range(character, character);
+
+        Ok(match character {
+            'd' => ClassItem::In(DIGIT),
+            'D' => ClassItem::NotIn(DIGIT),
+            'w' => ClassItem::In(WORD),
+            'W' => ClassItem::NotIn(WORD),
+            's' => ClassItem::In(SPACE),
+            'S' => ClassItem::NotIn(SPACE),
+            'n' => single('\n'),
+            'r' => single('\r'),
+            't' => single('\t'),
+            'f' => single('\u{c}'),
+            'v' => single('\u{b}'),
+            'u' => {
+                let digits = (0..4)
+                    .map(|_| self.next().and_then(|digit| digit.to_digit(16)))
+                    .collect::<Option<Vec<u32>>>()
+                    .ok_or_else(|| self.error("Invalid unicode escape"))?;
+                let value = digits
+                    .into_iter()
+                    .fold(0, |value, digit| value * 16 + digit);
+
+                single(char::from_u32(value).ok_or_else(|| self.error("Invalid unicode escape"))?)
+            }
+            'p' | 'P' => return Err(self.error("Unicode property escapes are not supported")),
+            character if character.is_ascii_alphanumeric() => {
+                return Err(self.error("Unknown escape"))
+            }
+            character => single(character),
+        })
+    }
+
+    fn integer(&mut self) -> Result<usize, RegexError> {
+        let start = self.position;
+
+        while self
+            .peek()
+            .is_some_and(|character| character.is_ascii_digit())
+        {
+            self.next();
+        }
+
+        self.chars[start..self.position]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .map_err(|_| self.error("Expected a number"))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.position += 1;
+        Some(character)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn error(&self, message: &str) -> RegexError {
+        RegexError {
+            message: message.to_string(),
+            position: self.position,
+        }
+    }
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE: &[(char, char)] = &[
+    ('\t', '\r'),
+    (' ', ' '),
+    ('\u{a0}', '\u{a0}'),
+    ('\u{2028}', '\u{2029}'),
+    ('\u{feff}', '\u{feff}'),
+];
+
+fn in_ranges(ranges: &[(char, char)], character: char) -> bool {
+    ranges
+        .iter()
+        .any(|&(low, high)| low <= character && character <= high)
+}
+
+#[cfg(test)]
+mod regex_tests {
+    use super::*;
+
+    #[test]
+    fn full_match() {
+        let regex = Regex::new("[a-z]+(-[a-z0-9]+)*").unwrap();
+
+        assert!(regex.is_full_match("jtool-v2").unwrap());
+        assert!(!regex.is_full_match("jtool-").unwrap());
+        assert!(!regex.is_full_match("Jtool").unwrap());
+
+        assert!(Regex::new("a{2,3}").unwrap().is_full_match("aaa").unwrap());
+        assert!(!Regex::new("a{2,3}").unwrap().is_full_match("aaaa").unwrap());
+        assert!(Regex::new("(ab|a)*c")
+            .unwrap()
+            .is_full_match("abaabc")
+            .unwrap());
+        assert!(Regex::new("[^\\d\\s]\\w*")
+            .unwrap()
+            .is_full_match("x_1")
+            .unwrap());
+        assert!(Regex::new("\\u00e9.").unwrap().is_full_match("éa").unwrap());
+    }
+
+    #[test]
+    fn search() {
+        assert!(Regex::new("\\d{3}")
+            .unwrap()
+            .is_match("call 555-1234")
+            .unwrap());
+        assert!(!Regex::new("^\\d{3}$")
+            .unwrap()
+            .is_match("call 555")
+            .unwrap());
+        assert!(Regex::new("^$").unwrap().is_match("").unwrap());
+        assert!(!Regex::new("(a*)*b")
+            .unwrap()
+            .is_match(&"a".repeat(40))
+            .unwrap());
+    }
+
+    #[test]
+    fn long_input() {
+        let text = "a".repeat(200_000);
+
+        assert!(Regex::new("^[a-z]*$").unwrap().is_match(&text).unwrap());
+        assert!(Regex::new("(a|b)*").unwrap().is_full_match(&text).unwrap());
+        assert!(!Regex::new("a*b").unwrap().is_match(&text).unwrap());
+    }
+
+    #[test]
+    fn step_limit() {
+        let regex = Regex::new("[a-z]*").unwrap().step_limit(1_000);
+
+        assert!(regex.is_full_match("abc").unwrap());
+        assert_eq!(
+            regex.is_full_match(&"a".repeat(1_000)).unwrap_err().message,
+            "Match exceeded 1000 steps"
+        );
+
+        let scaled = regex.steps_per_character(10);
+        assert!(scaled.is_full_match(&"a".repeat(1_000)).unwrap());
+        assert!(scaled.is_full_match(&"a".repeat(10_000)).unwrap());
+
+        assert_eq!(
+            Regex::new("(a{1000}){1000}").unwrap_err().message,
+            "Pattern too large"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Regex::new("a)").unwrap_err().message, "Unmatched ')'");
+        assert_eq!(
+            Regex::new("[a-").unwrap_err().message,
+            "Unterminated character class"
+        );
+        assert_eq!(Regex::new("*a").unwrap_err().message, "Nothing to repeat");
+        assert_eq!(
+            Regex::new("a{3,1}").unwrap_err().message,
+            "Invalid repetition"
+        );
+    }
+}
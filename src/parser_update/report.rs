@@ -0,0 +1,237 @@
+use super::{
+    diagnostic::{Diagnostic, Renderer},
+    explain::explain,
+    nodes::Node,
+    printer::CompactPrint,
+};
+
+/// Output formats for a batch of diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Source snippets with carets, see [`Renderer`].
+    Text,
+    /// A flat JSON array, one object per diagnostic.
+    Json,
+    /// SARIF 2.1.0, as collected by CI code-scanning services.
+    Sarif,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "sarif" => Some(Format::Sarif),
+            _ => None,
+        }
+    }
+}
+
+/// Diagnostics found in one input file.
+pub struct FileReport {
+    pub file: String,
+    pub source: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Serializes `reports` in `format`. `color` only applies to [`Format::Text`].
+pub fn emit(reports: &[FileReport], format: Format, color: bool) -> String {
+    match format {
+        Format::Text => to_text(reports, color),
+        Format::Json => CompactPrint.print(&to_json(reports)),
+        Format::Sarif => CompactPrint.print(&to_sarif(reports)),
+    }
+}
+
+pub fn to_text(reports: &[FileReport], color: bool) -> String {
+    reports
+        .iter()
+        .flat_map(|report| {
+            let renderer = Renderer::new(&report.file).color(color);
+
+            report
+                .diagnostics
+                .iter()
+                .map(move |diagnostic| renderer.render(&report.source, diagnostic))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// `[{"code", "severity", "message", "file", "line", "column", "start", "end", "hint"?}]`
+/// with byte offsets for `start` and `end`.
+pub fn to_json(reports: &[FileReport]) -> Node {
+    Node::list(
+        reports
+            .iter()
+            .flat_map(|report| {
+                report.diagnostics.iter().map(|diagnostic| {
+                    let mut properties = vec![
+                        Node::property("code", Node::string(diagnostic.code.as_str())),
+                        Node::property("severity", Node::string(&diagnostic.severity.to_string())),
+                        Node::property("message", Node::string(&diagnostic.message)),
+                        Node::property("file", Node::string(&report.file)),
+                        Node::property("line", Node::number(diagnostic.span.line as f64)),
+                        Node::property("column", Node::number(diagnostic.span.column as f64)),
+                        Node::property("start", Node::number(diagnostic.span.start as f64)),
+                        Node::property("end", Node::number(diagnostic.span.end as f64)),
+                    ];
+
+                    if let Some(hint) = &diagnostic.hint {
+                        properties.push(Node::property("hint", Node::string(hint)));
+                    }
+
+                    Node::object(properties)
+                })
+            })
+            .collect(),
+    )
+}
+
+/// A single SARIF run with one rule per error code that occurs.
+pub fn to_sarif(reports: &[FileReport]) -> Node {
+    let mut codes = reports
+        .iter()
+        .flat_map(|report| report.diagnostics.iter().map(|diagnostic| diagnostic.code))
+        .collect::<Vec<_>>();
+    codes.sort();
+    codes.dedup();
+
+    let rules = codes
+        .into_iter()
+        .map(|code| {
+            Node::object(vec![
+                Node::property("id", Node::string(code.as_str())),
+                Node::property("name", Node::string(&format!("{:?}", code))),
+                Node::property(
+                    "shortDescription",
+                    Node::object(vec![Node::property(
+                        "text",
+                        Node::string(explain(code).title),
+                    )]),
+                ),
+                Node::property(
+                    "fullDescription",
+                    Node::object(vec![Node::property(
+                        "text",
+                        Node::string(explain(code).description),
+                    )]),
+                ),
+                Node::property(
+                    "help",
+                    Node::object(vec![Node::property(
+                        "text",
+                        Node::string(explain(code).fix),
+                    )]),
+                ),
+            ])
+        })
+        .collect();
+
+    let results = reports
+        .iter()
+        .flat_map(|report| {
+            report.diagnostics.iter().map(|diagnostic| {
+                let span = diagnostic.span;
+                let region = Node::object(vec![
+                    Node::property("startLine", Node::number(span.line as f64)),
+                    Node::property("startColumn", Node::number(span.column as f64)),
+                    Node::property("byteOffset", Node::number(span.start as f64)),
+                    Node::property("byteLength", Node::number((span.end - span.start) as f64)),
+                ]);
+                let location = Node::object(vec![Node::property(
+                    "physicalLocation",
+                    Node::object(vec![
+                        Node::property(
+                            "artifactLocation",
+                            Node::object(vec![Node::property("uri", Node::string(&report.file))]),
+                        ),
+                        Node::property("region", region),
+                    ]),
+                )]);
+
+                let mut result = vec![
+                    Node::property("ruleId", Node::string(diagnostic.code.as_str())),
+                    Node::property("level", Node::string(&diagnostic.severity.to_string())),
+                    Node::property(
+                        "message",
+                        Node::object(vec![Node::property(
+                            "text",
+                            Node::string(&diagnostic.message),
+                        )]),
+                    ),
+                    Node::property("locations", Node::list(vec![location])),
+                ];
+
+                if let Some(hint) = &diagnostic.hint {
+                    result.push(Node::property(
+                        "properties",
+                        Node::object(vec![Node::property("hint", Node::string(hint))]),
+                    ));
+                }
+
+                Node::object(result)
+            })
+        })
+        .collect();
+
+    let driver = Node::object(vec![
+        Node::property("name", Node::string("jtool")),
+        Node::property("version", Node::string(env!("CARGO_PKG_VERSION"))),
+        Node::property("rules", Node::list(rules)),
+    ]);
+
+    Node::object(vec![
+        Node::property(
+            "$schema",
+            Node::string("https://json.schemastore.org/sarif-2.1.0.json"),
+        ),
+        Node::property("version", Node::string("2.1.0")),
+        Node::property(
+            "runs",
+            Node::list(vec![Node::object(vec![
+                Node::property("tool", Node::object(vec![Node::property("driver", driver)])),
+                Node::property("columnKind", Node::string("unicodeCodePoints")),
+                Node::property("results", Node::list(results)),
+            ])]),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod report_tests {
+    use super::*;
+    use crate::parser_update::{lexer::Dialect, nodes::parse_str_recovering};
+
+    fn report(source: &str) -> FileReport {
+        let (_, errors) = parse_str_recovering(source, Dialect::Json);
+
+        FileReport {
+            file: "a.json".to_string(),
+            source: source.to_string(),
+            diagnostics: errors.iter().map(Diagnostic::from).collect(),
+        }
+    }
+
+    #[test]
+    fn json() {
+        let output = emit(&[report("{\"a\" 1}")], Format::Json, false);
+
+        assert_eq!(
+            output,
+            r#"[{"code":"J0013","severity":"error","message":"Expected colon","file":"a.json","line":1,"column":6,"start":5,"end":6,"hint":"add `:` after the key"}]"#
+        );
+    }
+
+    #[test]
+    fn sarif() {
+        let output = emit(&[report("[1 2, }")], Format::Sarif, false);
+
+        assert!(output.starts_with(r#"{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","runs":[{"tool":{"driver":{"name":"jtool""#));
+        assert!(output.contains(r#""rules":[{"id":"J0011","name":"ExpectedValue","shortDescription":{"text":"Expected a value"},"#));
+        assert!(output.contains(
+            r#"{"id":"J0015","name":"UnclosedArray","shortDescription":{"text":"Expected `]`"},"#
+        ));
+        assert!(output.contains(r#"{"ruleId":"J0015","level":"error","message":{"text":"Expected ]"},"locations":[{"physicalLocation":{"artifactLocation":{"uri":"a.json"},"region":{"startLine":1,"startColumn":4,"byteOffset":3,"byteLength":1}}}],"properties":{"hint":"add a `,` between elements"}}"#));
+    }
+}
@@ -0,0 +1,324 @@
+use std::fmt::Write;
+
+use super::typegen::{pascal_case, words, Definition, Kind, Model, Type};
+
+/// Keywords that cannot be raw identifiers.
+const NOT_RAW: [&str; 5] = ["_", "crate", "self", "super", "Self"];
+
+const KEYWORDS: [&str; 50] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while",
+];
+
+/// Emits Rust declarations for a [`Model`], using serde for the mapping
+/// to JSON.
+///
+/// Structs get `Option` fields for optional or nullable members and a
+/// `rename` wherever the member name is not a snake_case identifier.
+/// String enums become fieldless enums, other unions `untagged` enums, and
+/// open places `serde_json::Value`. Definitions that contain themselves
+/// are boxed.
+pub struct RustGenerator;
+
+impl RustGenerator {
+    pub fn generate(&self, model: &Model) -> String {
+        let mut output = String::from("use serde::{Deserialize, Serialize};\n");
+
+        if model.root != Type::Named(model.name.clone()) {
+            let _ = write!(
+                output,
+                "\npub type {} = {};\n",
+                model.name,
+                self.type_of(model, &model.root, None)
+            );
+        }
+
+        for definition in &model.definitions {
+            output.push('\n');
+            self.definition(model, definition, &mut output);
+        }
+
+        output
+    }
+
+    fn definition(&self, model: &Model, definition: &Definition, output: &mut String) {
+        match &definition.kind {
+            Kind::Struct(fields) => {
+                output.push_str("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n");
+                let _ = writeln!(output, "pub struct {} {{", definition.name);
+
+                let mut taken = vec![];
+
+                for field in fields {
+                    let ident = unique(field_ident(&field.key), &mut taken, "_");
+
+                    let mut attributes = vec![];
+                    if ident.trim_start_matches("r#") != field.key {
+                        attributes.push(format!("rename = {:?}", field.key));
+                    }
+
+                    let ty = match (&field.ty, field.required) {
+                        (Type::Nullable(_), _) | (_, true) => {
+                            self.type_of(model, &field.ty, Some(&definition.name))
+                        }
+                        (ty, false) => {
+                            attributes
+                                .push("skip_serializing_if = \"Option::is_none\"".to_string());
+                            format!(
+                                "Option<{}>",
+                                self.type_of(model, ty, Some(&definition.name))
+                            )
+                        }
+                    };
+
+                    if !attributes.is_empty() {
+                        let _ = writeln!(output, "    #[serde({})]", attributes.join(", "));
+                    }
+                    let _ = writeln!(output, "    pub {}: {},", ident, ty);
+                }
+
+                output.push_str("}\n");
+            }
+            Kind::Enum(values) => {
+                output.push_str(
+                    "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]\n",
+                );
+                let _ = writeln!(output, "pub enum {} {{", definition.name);
+
+                let mut taken = vec![];
+
+                for value in values {
+                    let variant = unique(variant_ident(value), &mut taken, "");
+
+                    if variant != *value {
+                        let _ = writeln!(output, "    #[serde(rename = {:?})]", value);
+                    }
+                    let _ = writeln!(output, "    {},", variant);
+                }
+
+                output.push_str("}\n");
+            }
+            Kind::Union(types) => {
+                output.push_str("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n");
+                output.push_str("#[serde(untagged)]\n");
+                let _ = writeln!(output, "pub enum {} {{", definition.name);
+
+                let mut taken = vec![];
+
+                for ty in types {
+                    let variant = match ty {
+                        Type::Any => "Value".to_string(),
+                        Type::Bool => "Bool".to_string(),
+                        Type::Integer => "Integer".to_string(),
+                        Type::Number => "Number".to_string(),
+                        Type::String => "String".to_string(),
+                        Type::Array(_) => "Array".to_string(),
+                        Type::Map(_) => "Object".to_string(),
+                        Type::Named(name) => name.clone(),
+                        Type::Null | Type::Nullable(_) => "Null".to_string(),
+                    };
+                    let variant = unique(variant, &mut taken, "");
+
+                    let _ = writeln!(
+                        output,
+                        "    {}({}),",
+                        variant,
+                        self.type_of(model, ty, Some(&definition.name))
+                    );
+                }
+
+                output.push_str("}\n");
+            }
+        }
+    }
+
+    /// The Rust type for `ty` as part of the definition `within`, boxed if
+    /// it would otherwise contain that definition.
+    fn type_of(&self, model: &Model, ty: &Type, within: Option<&str>) -> String {
+        match ty {
+            Type::Any => "serde_json::Value".to_string(),
+            Type::Null => "()".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::Integer => "i64".to_string(),
+            Type::Number => "f64".to_string(),
+            Type::String => "String".to_string(),
+            Type::Array(ty) => format!("Vec<{}>", self.type_of(model, ty, None)),
+            Type::Map(ty) => format!(
+                "std::collections::BTreeMap<String, {}>",
+                self.type_of(model, ty, None)
+            ),
+            Type::Nullable(ty) => format!("Option<{}>", self.type_of(model, ty, within)),
+            Type::Named(name) if within.is_some_and(|within| model.contains(ty, within)) => {
+                format!("Box<{}>", name)
+            }
+            Type::Named(name) => name.clone(),
+        }
+    }
+}
+
+/// `key` as a snake_case field name.
+fn field_ident(key: &str) -> String {
+    let mut ident = words(key)
+        .iter()
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+
+    if ident.is_empty() {
+        ident = "field".to_string();
+    } else if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+
+    if NOT_RAW.contains(&ident.as_str()) {
+        ident.push('_');
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        ident.insert_str(0, "r#");
+    }
+
+    ident
+}
+
+/// `value` as a PascalCase variant name. `Self` is the only keyword that
+/// can come out of [`pascal_case`], and it cannot be a raw identifier.
+/// Appending `_` would trip the `non_camel_case_types` lint.
+fn variant_ident(value: &str) -> String {
+    let mut ident = pascal_case(value);
+
+    if NOT_RAW.contains(&ident.as_str()) {
+        ident.push_str("Value");
+    }
+
+    ident
+}
+
+/// `name`, or `name` with a number appended if it is already `taken`.
+/// Trailing underscores go first, so `self_` becomes `self_2` rather than
+/// the non-snake-case `self__2`.
+fn unique(name: String, taken: &mut Vec<String>, separator: &str) -> String {
+    let mut candidate = name.clone();
+    let stem = name.trim_end_matches('_');
+    let mut counter = 1;
+
+    while taken.contains(&candidate) {
+        counter += 1;
+        candidate = format!("{}{}{}", stem, separator, counter);
+    }
+
+    taken.push(candidate.clone());
+    candidate
+}
+
+#[cfg(test)]
+mod rustgen_tests {
+    use super::*;
+    use crate::parser_update::nodes::parse_str;
+
+    fn generate(schema: &str) -> String {
+        RustGenerator.generate(&Model::from_schema(&parse_str(schema).unwrap(), "Root").unwrap())
+    }
+
+    #[test]
+    fn structs() {
+        assert_eq!(
+            generate(
+                r##"{
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "integer"},
+                        "first-name": {"type": "string"},
+                        "type": {"enum": ["a-b", "c"]},
+                        "self": {"type": ["number", "null"]},
+                        "next": {"$ref": "#"},
+                        "tags": {"type": "array", "items": {"type": ["string", "boolean"]}},
+                        "extra": {}
+                    },
+                    "required": ["id", "self"]
+                }"##
+            ),
+            r#"use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Root {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra: Option<serde_json::Value>,
+    #[serde(rename = "first-name", skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+    pub id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<Box<Root>>,
+    #[serde(rename = "self")]
+    pub self_: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<Tag>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<Type>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Tag {
+    String(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Type {
+    #[serde(rename = "a-b")]
+    AB,
+    #[serde(rename = "c")]
+    C,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn keyword_variants() {
+        assert_eq!(
+            generate(r#"{"enum": ["self", "Self", "super"]}"#),
+            r#"use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Root {
+    #[serde(rename = "self")]
+    SelfValue,
+    #[serde(rename = "Self")]
+    SelfValue2,
+    #[serde(rename = "super")]
+    Super,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        assert_eq!(
+            generate(r#"{"type": "object", "additionalProperties": {"type": "integer"}}"#),
+            "use serde::{Deserialize, Serialize};\n\
+             \n\
+             pub type Root = std::collections::BTreeMap<String, i64>;\n"
+        );
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(field_ident("userId"), "user_id");
+        assert_eq!(field_ident("2fa"), "_2fa");
+        assert_eq!(field_ident("match"), "r#match");
+        assert_eq!(field_ident("crate"), "crate_");
+        assert_eq!(field_ident("$"), "field");
+        assert_eq!(variant_ident("self"), "SelfValue");
+        assert_eq!(variant_ident("in-active"), "InActive");
+
+        let mut taken = vec![];
+        assert_eq!(unique("a".to_string(), &mut taken, "_"), "a");
+        assert_eq!(unique("a".to_string(), &mut taken, "_"), "a_2");
+        assert_eq!(unique("self_".to_string(), &mut taken, "_"), "self_");
+        assert_eq!(unique("self_".to_string(), &mut taken, "_"), "self_2");
+    }
+}
@@ -0,0 +1,1030 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use super::{
+    diagnostic::{Diagnostic, Severity},
+    error::{ErrorCode, Span},
+    nodes::{Literal, Node},
+    pointer::escape_token,
+    printer::CompactPrint,
+    regex::{Regex, RegexError},
+};
+
+/// How many `$ref`s may be followed without descending into the instance,
+/// so a schema like `{"$ref": "#"}` fails instead of recursing forever.
+const REF_DEPTH_LIMIT: usize = 64;
+
+/// The most steps one `pattern` or `patternProperties` match may take, so
+/// a costly pattern cannot stall validation. Long instance strings get
+/// [`PATTERN_STEPS_PER_CHARACTER`] instead when that is more.
+const PATTERN_STEP_LIMIT: usize = 1_000_000;
+
+const PATTERN_STEPS_PER_CHARACTER: usize = 100;
+
+/// A schema that cannot be compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// JSON Pointer to the offending keyword in the schema.
+    pub path: String,
+    pub message: String,
+}
+
+impl std::error::Error for SchemaError {}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at #{}", self.message, self.path)
+    }
+}
+
+/// One way an instance fails a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// `SchemaViolation`, or `LimitExceeded` when the check could not be
+    /// completed.
+    pub code: ErrorCode,
+    pub severity: Severity,
+    /// JSON Pointer to the failing value in the instance.
+    pub instance_path: String,
+    /// JSON Pointer to the failing keyword in the schema. Keywords reached
+    /// through `$ref` are reported where they are defined.
+    pub schema_path: String,
+    /// The failing value in the source. Scalars carry no position, so they
+    /// get the span of their key, or of the enclosing array.
+    pub span: Span,
+    pub message: String,
+}
+
+impl From<&ValidationError> for Diagnostic {
+    fn from(error: &ValidationError) -> Self {
+        Self {
+            severity: error.severity,
+            ..Diagnostic::error(error.code, &error.to_string(), error.span)
+        }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = match self.instance_path.as_str() {
+            "" => "(root)",
+            path => path,
+        };
+
+        write!(f, "{}: {} (#{})", path, self.message, self.schema_path)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Subschema {
+    path: String,
+    body: Body,
+}
+
+#[derive(Debug, Clone)]
+enum Body {
+    /// `true` accepts everything and `false` nothing.
+    Bool(bool),
+    Keywords(Vec<Keyword>),
+}
+
+#[derive(Debug, Clone)]
+enum Keyword {
+    Type(Vec<String>),
+    Enum(Vec<Node>),
+    Const(Node),
+    Properties(Vec<(String, Subschema)>),
+    PatternProperties(Vec<(Regex, Subschema)>),
+    AdditionalProperties(Box<Subschema>),
+    Required(Vec<String>),
+    MinProperties(usize),
+    MaxProperties(usize),
+    PrefixItems(Vec<Subschema>),
+    Items(Box<Subschema>),
+    MinItems(usize),
+    MaxItems(usize),
+    UniqueItems(bool),
+    Minimum(f64),
+    Maximum(f64),
+    ExclusiveMinimum(f64),
+    ExclusiveMaximum(f64),
+    MinLength(usize),
+    MaxLength(usize),
+    Pattern(String, Regex),
+    AllOf(Vec<Subschema>),
+    AnyOf(Vec<Subschema>),
+    OneOf(Vec<Subschema>),
+    Not(Box<Subschema>),
+    /// Pointer to the target within the root schema.
+    Ref(String),
+}
+
+impl Keyword {
+    fn name(&self) -> &'static str {
+        match self {
+            Keyword::Type(_) => "type",
+            Keyword::Enum(_) => "enum",
+            Keyword::Const(_) => "const",
+            Keyword::Properties(_) => "properties",
+            Keyword::PatternProperties(_) => "patternProperties",
+            Keyword::AdditionalProperties(_) => "additionalProperties",
+            Keyword::Required(_) => "required",
+            Keyword::MinProperties(_) => "minProperties",
+            Keyword::MaxProperties(_) => "maxProperties",
+            Keyword::PrefixItems(_) => "prefixItems",
+            Keyword::Items(_) => "items",
+            Keyword::MinItems(_) => "minItems",
+            Keyword::MaxItems(_) => "maxItems",
+            Keyword::UniqueItems(_) => "uniqueItems",
+            Keyword::Minimum(_) => "minimum",
+            Keyword::Maximum(_) => "maximum",
+            Keyword::ExclusiveMinimum(_) => "exclusiveMinimum",
+            Keyword::ExclusiveMaximum(_) => "exclusiveMaximum",
+            Keyword::MinLength(_) => "minLength",
+            Keyword::MaxLength(_) => "maxLength",
+            Keyword::Pattern(_, _) => "pattern",
+            Keyword::AllOf(_) => "allOf",
+            Keyword::AnyOf(_) => "anyOf",
+            Keyword::OneOf(_) => "oneOf",
+            Keyword::Not(_) => "not",
+            Keyword::Ref(_) => "$ref",
+        }
+    }
+}
+
+/// A compiled JSON Schema (draft 2020-12).
+///
+/// Covers the core applicators and the validation vocabulary: `type`,
+/// `enum`, `const`, the object, array, number and string keywords, `allOf`,
+/// `anyOf`, `oneOf`, `not`, and `$ref` to anywhere in the same document,
+/// usually `$defs`. Other keywords, `format` included, are ignored as the
+/// specification allows. Patterns use [`Regex`], so lookaround and
+/// backreferences are rejected at compile time.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    root: Subschema,
+    /// Every `$ref` target, by pointer.
+    targets: BTreeMap<String, Subschema>,
+}
+
+impl Schema {
+    pub fn compile(schema: &Node) -> Result<Schema, SchemaError> {
+        let mut compiler = Compiler {
+            root: schema,
+            targets: BTreeMap::new(),
+            pending: vec![],
+        };
+        let root = compiler.compile(schema, String::new())?;
+
+        while let Some(pointer) = compiler.pending.pop() {
+            if compiler.targets.contains_key(&pointer) {
+                continue;
+            }
+
+            let target = schema.get_pointer(&pointer).map_err(|_| SchemaError {
+                path: pointer.clone(),
+                message: "Unresolved $ref".to_string(),
+            })?;
+            let target = compiler.compile(target, pointer.clone())?;
+            compiler.targets.insert(pointer, target);
+        }
+
+        Ok(Schema {
+            root,
+            targets: compiler.targets,
+        })
+    }
+
+    /// Every violation in `instance`, ordered by position in the source.
+    /// Empty if it is valid.
+    pub fn validate(&self, instance: &Node) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        let at = Location {
+            path: String::new(),
+            // A scalar document has no span, so point at its start.
+            span: span_of(instance, Span::new(0, 0, 1, 1)),
+        };
+
+        self.check(&self.root, instance, &at, 0, &mut errors);
+        errors.sort_by_key(|error| error.span.start);
+        errors
+    }
+
+    pub fn is_valid(&self, instance: &Node) -> bool {
+        self.validate(instance).is_empty()
+    }
+
+    fn check(
+        &self,
+        schema: &Subschema,
+        instance: &Node,
+        at: &Location,
+        depth: usize,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let keywords = match &schema.body {
+            Body::Bool(true) => return,
+            Body::Bool(false) => {
+                errors.push(at.error(&schema.path, "No value is allowed here".to_string()));
+                return;
+            }
+            Body::Keywords(keywords) => keywords,
+        };
+
+        for keyword in keywords {
+            let path = format!("{}/{}", schema.path, keyword.name());
+            let error = |message: String| at.error(&path, message);
+
+            match (keyword, instance) {
+                (Keyword::Type(types), _) if !types.iter().any(|name| has_type(instance, name)) => {
+                    errors.push(error(format!(
+                        "Expected {}, found {}",
+                        types.join(" or "),
+                        type_name(instance)
+                    )));
+                }
+                (Keyword::Enum(values), _) if !values.contains(instance) => {
+                    let values = values
+                        .iter()
+                        .map(|value| CompactPrint.print(value))
+                        .collect::<Vec<_>>();
+
+                    errors.push(error(format!(
+                        "Expected one of {}, found {}",
+                        values.join(", "),
+                        CompactPrint.print(instance)
+                    )));
+                }
+                (Keyword::Const(value), _) if value != instance => {
+                    errors.push(error(format!(
+                        "Expected {}, found {}",
+                        CompactPrint.print(value),
+                        CompactPrint.print(instance)
+                    )));
+                }
+                (Keyword::Properties(schemas), Node::Object(_, properties, _)) => {
+                    for (name, key, value) in members(properties) {
+                        if let Some((_, schema)) = schemas.iter().find(|(other, _)| *other == name)
+                        {
+                            let at = at.member(&name, key, value);
+                            self.check(schema, value, &at, 0, errors);
+                        }
+                    }
+                }
+                (Keyword::PatternProperties(schemas), Node::Object(_, properties, _)) => {
+                    for (name, key, value) in members(properties) {
+                        let at = at.member(&name, key, value);
+
+                        for (regex, schema) in schemas {
+                            match regex.is_match(&name) {
+                                Ok(true) => self.check(schema, value, &at, 0, errors),
+                                Ok(false) => {}
+                                Err(limit) => errors.push(at.limit_exceeded(&path, &limit)),
+                            }
+                        }
+                    }
+                }
+                (Keyword::AdditionalProperties(schema), Node::Object(_, properties, _)) => {
+                    for (name, key, value) in members(properties) {
+                        if !is_declared(keywords, &name) {
+                            let at = at.member(&name, key, value);
+                            self.check(schema, value, &at, 0, errors);
+                        }
+                    }
+                }
+                (Keyword::Required(names), Node::Object(_, properties, _)) => {
+                    for name in names {
+                        if !members(properties).any(|(other, _, _)| other == *name) {
+                            errors.push(error(format!("Missing required property '{}'", name)));
+                        }
+                    }
+                }
+                (Keyword::MinProperties(limit), Node::Object(_, properties, _))
+                    if properties.len() < *limit =>
+                {
+                    errors.push(error(format!(
+                        "Expected at least {} properties, found {}",
+                        limit,
+                        properties.len()
+                    )));
+                }
+                (Keyword::MaxProperties(limit), Node::Object(_, properties, _))
+                    if properties.len() > *limit =>
+                {
+                    errors.push(error(format!(
+                        "Expected at most {} properties, found {}",
+                        limit,
+                        properties.len()
+                    )));
+                }
+                (Keyword::PrefixItems(schemas), Node::List(_, nodes, _)) => {
+                    for (index, (schema, node)) in schemas.iter().zip(nodes).enumerate() {
+                        self.check(schema, node, &at.element(index, node), 0, errors);
+                    }
+                }
+                (Keyword::Items(schema), Node::List(_, nodes, _)) => {
+                    let prefix = keywords
+                        .iter()
+                        .find_map(|keyword| match keyword {
+                            Keyword::PrefixItems(schemas) => Some(schemas.len()),
+                            _ => None,
+                        })
+                        .unwrap_or(0);
+
+                    for (index, node) in nodes.iter().enumerate().skip(prefix) {
+                        self.check(schema, node, &at.element(index, node), 0, errors);
+                    }
+                }
+                (Keyword::MinItems(limit), Node::List(_, nodes, _)) if nodes.len() < *limit => {
+                    errors.push(error(format!(
+                        "Expected at least {} items, found {}",
+                        limit,
+                        nodes.len()
+                    )));
+                }
+                (Keyword::MaxItems(limit), Node::List(_, nodes, _)) if nodes.len() > *limit => {
+                    errors.push(error(format!(
+                        "Expected at most {} items, found {}",
+                        limit,
+                        nodes.len()
+                    )));
+                }
+                (Keyword::UniqueItems(true), Node::List(_, nodes, _)) => {
+                    let duplicate =
+                        (0..nodes.len()).find(|&index| nodes[..index].contains(&nodes[index]));
+
+                    if let Some(index) = duplicate {
+                        errors.push(error(format!(
+                            "Item {} is a duplicate of an earlier item",
+                            index
+                        )));
+                    }
+                }
+                (Keyword::Minimum(limit), Node::Primary(Literal::Number(value)))
+                    if value < limit =>
+                {
+                    errors.push(error(format!(
+                        "Expected at least {}, found {}",
+                        limit, value
+                    )));
+                }
+                (Keyword::Maximum(limit), Node::Primary(Literal::Number(value)))
+                    if value > limit =>
+                {
+                    errors.push(error(format!(
+                        "Expected at most {}, found {}",
+                        limit, value
+                    )));
+                }
+                (Keyword::ExclusiveMinimum(limit), Node::Primary(Literal::Number(value)))
+                    if value <= limit =>
+                {
+                    errors.push(error(format!(
+                        "Expected more than {}, found {}",
+                        limit, value
+                    )));
+                }
+                (Keyword::ExclusiveMaximum(limit), Node::Primary(Literal::Number(value)))
+                    if value >= limit =>
+                {
+                    errors.push(error(format!(
+                        "Expected less than {}, found {}",
+                        limit, value
+                    )));
+                }
+                (Keyword::MinLength(limit), Node::Primary(Literal::String(value))) => {
+                    let length = value.chars().count();
+
+                    if length < *limit {
+                        errors.push(error(format!(
+                            "Expected at least {} characters, found {}",
+                            limit, length
+                        )));
+                    }
+                }
+                (Keyword::MaxLength(limit), Node::Primary(Literal::String(value))) => {
+                    let length = value.chars().count();
+
+                    if length > *limit {
+                        errors.push(error(format!(
+                            "Expected at most {} characters, found {}",
+                            limit, length
+                        )));
+                    }
+                }
+                (Keyword::Pattern(pattern, regex), Node::Primary(Literal::String(value))) => {
+                    match regex.is_match(value) {
+                        Ok(true) => {}
+                        Ok(false) => errors.push(error(format!(
+                            "'{}' does not match the pattern '{}'",
+                            value, pattern
+                        ))),
+                        Err(limit) => errors.push(at.limit_exceeded(&path, &limit)),
+                    }
+                }
+                (Keyword::AllOf(schemas), _) => {
+                    for schema in schemas {
+                        self.check(schema, instance, at, depth, errors);
+                    }
+                }
+                (Keyword::AnyOf(schemas), _) => {
+                    let mut matched = false;
+                    let mut limits = vec![];
+
+                    for schema in schemas {
+                        match self.matches(schema, instance, at, depth) {
+                            Ok(true) => {
+                                matched = true;
+                                limits.clear();
+                                break;
+                            }
+                            Ok(false) => {}
+                            Err(exceeded) => limits.extend(exceeded),
+                        }
+                    }
+
+                    if !matched && limits.is_empty() {
+                        errors.push(error("Does not match any of the schemas".to_string()));
+                    }
+
+                    errors.extend(limits);
+                }
+                (Keyword::OneOf(schemas), _) => {
+                    let mut matched = 0;
+                    let mut limits = vec![];
+
+                    for schema in schemas {
+                        match self.matches(schema, instance, at, depth) {
+                            Ok(true) => matched += 1,
+                            Ok(false) => {}
+                            Err(exceeded) => limits.extend(exceeded),
+                        }
+                    }
+
+                    // More than one match fails whatever the others would
+                    // have done.
+                    if matched > 1 || limits.is_empty() && matched != 1 {
+                        errors.push(error(format!(
+                            "Expected to match exactly one of the schemas, matched {}",
+                            matched
+                        )));
+                    } else {
+                        errors.extend(limits);
+                    }
+                }
+                (Keyword::Not(schema), _) => match self.matches(schema, instance, at, depth) {
+                    Ok(true) => {
+                        errors.push(error("Matches a schema it must not match".to_string()))
+                    }
+                    Ok(false) => {}
+                    Err(exceeded) => errors.extend(exceeded),
+                },
+                (Keyword::Ref(pointer), _) => {
+                    if depth == REF_DEPTH_LIMIT {
+                        errors.push(ValidationError {
+                            code: ErrorCode::LimitExceeded,
+                            ..error("Too many nested $ref".to_string())
+                        });
+                    } else {
+                        self.check(&self.targets[pointer], instance, at, depth + 1, errors);
+                    }
+                }
+                // Keywords for other types do not apply.
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether `instance` matches `schema`, or the limit errors that kept
+    /// the check from finishing. A violation alongside them is still a
+    /// definite mismatch.
+    fn matches(
+        &self,
+        schema: &Subschema,
+        instance: &Node,
+        at: &Location,
+        depth: usize,
+    ) -> Result<bool, Vec<ValidationError>> {
+        let mut errors = vec![];
+        self.check(schema, instance, at, depth, &mut errors);
+
+        if !errors.is_empty()
+            && errors
+                .iter()
+                .all(|error| error.code == ErrorCode::LimitExceeded)
+        {
+            Err(errors)
+        } else {
+            Ok(errors.is_empty())
+        }
+    }
+}
+
+/// Where a value sits in the instance.
+struct Location {
+    path: String,
+    span: Span,
+}
+
+impl Location {
+    fn member(&self, name: &str, key: Span, value: &Node) -> Location {
+        Location {
+            path: format!("{}/{}", self.path, escape_token(name)),
+            span: span_of(value, key),
+        }
+    }
+
+    fn element(&self, index: usize, node: &Node) -> Location {
+        Location {
+            path: format!("{}/{}", self.path, index),
+            span: span_of(node, self.span),
+        }
+    }
+
+    fn error(&self, schema_path: &str, message: String) -> ValidationError {
+        ValidationError {
+            code: ErrorCode::SchemaViolation,
+            severity: Severity::Error,
+            instance_path: self.path.clone(),
+            schema_path: schema_path.to_string(),
+            span: self.span,
+            message,
+        }
+    }
+
+    /// A pattern that ran out of steps, which neither passes nor fails.
+    fn limit_exceeded(&self, schema_path: &str, error: &RegexError) -> ValidationError {
+        ValidationError {
+            code: ErrorCode::LimitExceeded,
+            ..self.error(
+                schema_path,
+                format!("Pattern could not be checked, {}", error.message),
+            )
+        }
+    }
+}
+
+fn span_of(node: &Node, fallback: Span) -> Span {
+    match node {
+        Node::Object(left, _, right) | Node::List(left, _, right) => Span {
+            end: right.span.end,
+            ..left.span
+        },
+        _ => fallback,
+    }
+}
+
+fn members(properties: &[Node]) -> impl Iterator<Item = (String, Span, &Node)> {
+    properties.iter().filter_map(|property| match property {
+        Node::Property(key, _, value) => Some((key.literal.to_string(), key.span, &**value)),
+        _ => None,
+    })
+}
+
+/// Whether `properties` or `patternProperties` covers `name`, which keeps
+/// it out of `additionalProperties`.
+fn is_declared(keywords: &[Keyword], name: &str) -> bool {
+    keywords.iter().any(|keyword| match keyword {
+        Keyword::Properties(schemas) => schemas.iter().any(|(other, _)| other == name),
+        // A pattern that ran out of steps is already reported by
+        // `patternProperties`, so the member is not reported again.
+        Keyword::PatternProperties(schemas) => schemas
+            .iter()
+            .any(|(regex, _)| regex.is_match(name).unwrap_or(true)),
+        _ => false,
+    })
+}
+
+fn type_name(node: &Node) -> &'static str {
+    match node {
+        Node::Primary(Literal::Null) => "null",
+        Node::Primary(Literal::Bool(_)) => "boolean",
+        Node::Primary(Literal::Number(_)) => "number",
+        Node::Primary(Literal::String(_)) => "string",
+        Node::List(..) => "array",
+        Node::Object(..) | Node::Property(..) | Node::Error(_) => "object",
+    }
+}
+
+fn has_type(node: &Node, name: &str) -> bool {
+    match (name, node) {
+        ("integer", Node::Primary(Literal::Number(value))) => value.fract() == 0.0,
+        (name, node) => type_name(node) == name,
+    }
+}
+
+struct Compiler<'a> {
+    root: &'a Node,
+    targets: BTreeMap<String, Subschema>,
+    /// `$ref` targets still to compile.
+    pending: Vec<String>,
+}
+
+impl Compiler<'_> {
+    fn compile(&mut self, node: &Node, path: String) -> Result<Subschema, SchemaError> {
+        let properties = match node {
+            Node::Primary(Literal::Bool(value)) => {
+                return Ok(Subschema {
+                    path,
+                    body: Body::Bool(*value),
+                })
+            }
+            Node::Object(_, properties, _) => properties,
+            _ => return Err(invalid(&path, "A schema must be an object or a boolean")),
+        };
+
+        let mut keywords = vec![];
+
+        for (name, _, value) in members(properties) {
+            let at = format!("{}/{}", path, escape_token(&name));
+
+            let keyword = match name.as_str() {
+                "type" => Keyword::Type(match value {
+                    Node::List(_, nodes, _) => nodes
+                        .iter()
+                        .map(|node| type_keyword(node, &at))
+                        .collect::<Result<_, _>>()?,
+                    node => vec![type_keyword(node, &at)?],
+                }),
+                "enum" => match value {
+                    Node::List(_, nodes, _) => Keyword::Enum(nodes.clone()),
+                    _ => return Err(invalid(&at, "'enum' must be an array")),
+                },
+                "const" => Keyword::Const(value.clone()),
+                "properties" => Keyword::Properties(self.named(value, &at)?),
+                "patternProperties" => Keyword::PatternProperties(
+                    self.named(value, &at)?
+                        .into_iter()
+                        .map(|(pattern, schema)| Ok((regex(&pattern, &schema.path)?, schema)))
+                        .collect::<Result<_, _>>()?,
+                ),
+                "additionalProperties" => {
+                    Keyword::AdditionalProperties(Box::new(self.compile(value, at)?))
+                }
+                "required" => Keyword::Required(match value {
+                    Node::List(_, nodes, _) => nodes
+                        .iter()
+                        .map(|node| match node {
+                            Node::Primary(Literal::String(name)) => Ok(name.clone()),
+                            _ => Err(invalid(&at, "'required' must be an array of strings")),
+                        })
+                        .collect::<Result<_, _>>()?,
+                    _ => return Err(invalid(&at, "'required' must be an array of strings")),
+                }),
+                "minProperties" => Keyword::MinProperties(count(value, &at)?),
+                "maxProperties" => Keyword::MaxProperties(count(value, &at)?),
+                "prefixItems" => Keyword::PrefixItems(self.list(value, &at)?),
+                "items" => Keyword::Items(Box::new(self.compile(value, at)?)),
+                "minItems" => Keyword::MinItems(count(value, &at)?),
+                "maxItems" => Keyword::MaxItems(count(value, &at)?),
+                "uniqueItems" => match value {
+                    Node::Primary(Literal::Bool(value)) => Keyword::UniqueItems(*value),
+                    _ => return Err(invalid(&at, "'uniqueItems' must be a boolean")),
+                },
+                "minimum" => Keyword::Minimum(number(value, &at)?),
+                "maximum" => Keyword::Maximum(number(value, &at)?),
+                "exclusiveMinimum" => Keyword::ExclusiveMinimum(number(value, &at)?),
+                "exclusiveMaximum" => Keyword::ExclusiveMaximum(number(value, &at)?),
+                "minLength" => Keyword::MinLength(count(value, &at)?),
+                "maxLength" => Keyword::MaxLength(count(value, &at)?),
+                "pattern" => match value {
+                    Node::Primary(Literal::String(pattern)) => {
+                        Keyword::Pattern(pattern.clone(), regex(pattern, &at)?)
+                    }
+                    _ => return Err(invalid(&at, "'pattern' must be a string")),
+                },
+                "allOf" => Keyword::AllOf(self.list(value, &at)?),
+                "anyOf" => Keyword::AnyOf(self.list(value, &at)?),
+                "oneOf" => Keyword::OneOf(self.list(value, &at)?),
+                "not" => Keyword::Not(Box::new(self.compile(value, at)?)),
+                "$ref" => Keyword::Ref(self.reference(value, &at)?),
+                _ => continue,
+            };
+
+            keywords.push(keyword);
+        }
+
+        Ok(Subschema {
+            path,
+            body: Body::Keywords(keywords),
+        })
+    }
+
+    /// The members of a keyword like `properties`, each a schema.
+    fn named(&mut self, node: &Node, path: &str) -> Result<Vec<(String, Subschema)>, SchemaError> {
+        let Node::Object(_, properties, _) = node else {
+            return Err(invalid(path, "Expected an object of schemas"));
+        };
+
+        members(properties)
+            .map(|(name, _, value)| {
+                let schema = self.compile(value, format!("{}/{}", path, escape_token(&name)))?;
+                Ok((name, schema))
+            })
+            .collect()
+    }
+
+    /// The elements of a keyword like `allOf`, each a schema.
+    fn list(&mut self, node: &Node, path: &str) -> Result<Vec<Subschema>, SchemaError> {
+        match node {
+            Node::List(_, nodes, _) if !nodes.is_empty() => nodes
+                .iter()
+                .enumerate()
+                .map(|(index, node)| self.compile(node, format!("{}/{}", path, index)))
+                .collect(),
+            _ => Err(invalid(path, "Expected a non-empty array of schemas")),
+        }
+    }
+
+    /// Resolves a `$ref` to a pointer into the root schema and queues it
+    /// for compilation.
+    fn reference(&mut self, node: &Node, path: &str) -> Result<String, SchemaError> {
+        let Node::Primary(Literal::String(reference)) = node else {
+            return Err(invalid(path, "'$ref' must be a string"));
+        };
+
+        let pointer = reference
+            .strip_prefix('#')
+            .filter(|fragment| fragment.is_empty() || fragment.starts_with('/'))
+            .ok_or_else(|| {
+                invalid(
+                    path,
+                    &format!(
+                        "Cannot resolve '{}', only '#' and '#/...' references are supported",
+                        reference
+                    ),
+                )
+            })?;
+        let pointer = percent_decode(pointer).ok_or_else(|| invalid(path, "Invalid $ref"))?;
+
+        if self.root.get_pointer(&pointer).is_err() {
+            return Err(invalid(
+                path,
+                &format!("'{}' does not point into the schema", reference),
+            ));
+        }
+
+        self.pending.push(pointer.clone());
+        Ok(pointer)
+    }
+}
+
+fn invalid(path: &str, message: &str) -> SchemaError {
+    SchemaError {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn type_keyword(node: &Node, path: &str) -> Result<String, SchemaError> {
+    match node {
+        Node::Primary(Literal::String(name))
+            if matches!(
+                name.as_str(),
+                "null" | "boolean" | "object" | "array" | "number" | "string" | "integer"
+            ) =>
+        {
+            Ok(name.clone())
+        }
+        _ => Err(invalid(path, "Unknown type")),
+    }
+}
+
+fn number(node: &Node, path: &str) -> Result<f64, SchemaError> {
+    match node {
+        Node::Primary(Literal::Number(value)) => Ok(*value),
+        _ => Err(invalid(path, "Expected a number")),
+    }
+}
+
+fn count(node: &Node, path: &str) -> Result<usize, SchemaError> {
+    match node {
+        Node::Primary(Literal::Number(value)) if *value >= 0.0 && value.fract() == 0.0 => {
+            Ok(*value as usize)
+        }
+        _ => Err(invalid(path, "Expected a non-negative integer")),
+    }
+}
+
+fn regex(pattern: &str, path: &str) -> Result<Regex, SchemaError> {
+    Regex::new(pattern)
+        .map(|regex| {
+            regex
+                .step_limit(PATTERN_STEP_LIMIT)
+                .steps_per_character(PATTERN_STEPS_PER_CHARACTER)
+        })
+        .map_err(|error| invalid(path, &format!("Invalid pattern: {}", error)))
+}
+
+/// Decodes `%XX` escapes in a URI fragment.
+fn percent_decode(fragment: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut rest = fragment.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod schema_tests {
+    use super::*;
+    use crate::parser_update::nodes::parse_str;
+
+    const SCHEMA: &str = r##"{
+        "type": "object",
+        "required": ["name", "port"],
+        "properties": {
+            "name": {"type": "string", "minLength": 1, "pattern": "^[a-z][a-z0-9-]*$"},
+            "port": {"$ref": "#/$defs/port"},
+            "mode": {"enum": ["dev", "prod"]},
+            "tags": {"type": "array", "items": {"type": "string"}, "uniqueItems": true},
+            "pair": {"prefixItems": [{"type": "string"}, {"type": "integer"}], "items": false}
+        },
+        "additionalProperties": false,
+        "$defs": {
+            "port": {"type": "integer", "minimum": 1, "exclusiveMaximum": 65536}
+        }
+    }"##;
+
+    fn errors(schema: &str, instance: &str) -> Vec<String> {
+        Schema::compile(&parse_str(schema).unwrap())
+            .unwrap()
+            .validate(&parse_str(instance).unwrap())
+            .iter()
+            .map(ValidationError::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let schema = Schema::compile(&parse_str(SCHEMA).unwrap()).unwrap();
+
+        assert!(schema.is_valid(
+            &parse_str(r#"{"name": "api-1", "port": 8080, "tags": ["a"], "pair": ["x", 1]}"#)
+                .unwrap()
+        ));
+        assert!(!schema.is_valid(&parse_str("[]").unwrap()));
+    }
+
+    #[test]
+    fn violations() {
+        assert_eq!(
+            errors(
+                SCHEMA,
+                r#"{"name": "Api", "port": 70000.5, "mode": "test", "tags": ["a", 1, "a"], "pair": ["x", 1, 2], "extra": 1}"#
+            ),
+            vec![
+                "/name: 'Api' does not match the pattern '^[a-z][a-z0-9-]*$' (#/properties/name/pattern)",
+                "/port: Expected less than 65536, found 70000.5 (#/$defs/port/exclusiveMaximum)",
+                "/port: Expected integer, found number (#/$defs/port/type)",
+                "/mode: Expected one of \"dev\", \"prod\", found \"test\" (#/properties/mode/enum)",
+                "/tags/1: Expected string, found number (#/properties/tags/items/type)",
+                "/tags: Item 2 is a duplicate of an earlier item (#/properties/tags/uniqueItems)",
+                "/pair/2: No value is allowed here (#/properties/pair/items)",
+                "/extra: No value is allowed here (#/additionalProperties)",
+            ]
+        );
+        assert_eq!(
+            errors(SCHEMA, r#"{"name": ""}"#),
+            vec![
+                "(root): Missing required property 'port' (#/required)",
+                "/name: Expected at least 1 characters, found 0 (#/properties/name/minLength)",
+                "/name: '' does not match the pattern '^[a-z][a-z0-9-]*$' (#/properties/name/pattern)",
+            ]
+        );
+    }
+
+    #[test]
+    fn combinators() {
+        let schema = r##"{
+            "oneOf": [{"type": "integer"}, {"minimum": 2}],
+            "not": {"const": 5},
+            "anyOf": [{"type": "number"}, {"type": "null"}]
+        }"##;
+
+        assert!(errors(schema, "1").is_empty());
+        assert_eq!(
+            errors(schema, "3"),
+            vec!["(root): Expected to match exactly one of the schemas, matched 2 (#/oneOf)"]
+        );
+        assert_eq!(
+            errors(schema, "5"),
+            vec![
+                "(root): Matches a schema it must not match (#/not)",
+                "(root): Expected to match exactly one of the schemas, matched 2 (#/oneOf)",
+            ]
+        );
+        assert_eq!(
+            errors(schema, "\"x\""),
+            vec!["(root): Does not match any of the schemas (#/anyOf)"]
+        );
+    }
+
+    #[test]
+    fn spans_and_compile_errors() {
+        let schema = Schema::compile(&parse_str(SCHEMA).unwrap()).unwrap();
+        let errors = schema.validate(&parse_str("{\n  \"name\": 1,\n  \"tags\": [2]\n}").unwrap());
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| (
+                    error.instance_path.as_str(),
+                    error.span.line,
+                    error.span.column
+                ))
+                .collect::<Vec<_>>(),
+            vec![("", 1, 1), ("/name", 2, 3), ("/tags/0", 3, 11)]
+        );
+
+        let compile = |schema: &str| {
+            Schema::compile(&parse_str(schema).unwrap())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            compile(r##"{"properties": {"a": {"$ref": "#/$defs/missing"}}}"##),
+            "'#/$defs/missing' does not point into the schema at #/properties/a/$ref"
+        );
+        assert_eq!(
+            compile(r#"{"pattern": "(?=a)"}"#),
+            "Invalid pattern: Unsupported group at position 2 at #/pattern"
+        );
+        assert!(Schema::compile(&parse_str(r##"{"$ref": "#"}"##).unwrap())
+            .unwrap()
+            .validate(&parse_str("1").unwrap())
+            .iter()
+            .any(|error| error.message == "Too many nested $ref"));
+    }
+
+    #[test]
+    fn long_patterns() {
+        let schema =
+            Schema::compile(&parse_str(r#"{"type": "string", "pattern": "^[a-z]*$"}"#).unwrap())
+                .unwrap();
+
+        let long = format!("\"{}\"", "a".repeat(100_000));
+        assert!(schema.is_valid(&parse_str(&long).unwrap()));
+
+        let longer = format!("\"{}\"", "a".repeat(1_000_000));
+        assert!(schema.is_valid(&parse_str(&longer).unwrap()));
+
+        let mismatch = format!("\"{}1\"", "a".repeat(1_000_000));
+        let errors = schema.validate(&parse_str(&mismatch).unwrap());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::SchemaViolation);
+
+        let costly =
+            Schema::compile(&parse_str(r#"{"pattern": "[a-z]{0,500}1"}"#).unwrap()).unwrap();
+        let errors = costly.validate(&parse_str(&long).unwrap());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::LimitExceeded);
+        assert_eq!(errors[0].schema_path, "/pattern");
+
+        for keyword in ["anyOf", "oneOf"] {
+            let schema = Schema::compile(
+                &parse_str(&format!(
+                    r#"{{"{}": [{{"type": "number"}}, {{"pattern": "[a-z]{{0,500}}1"}}]}}"#,
+                    keyword
+                ))
+                .unwrap(),
+            )
+            .unwrap();
+            let errors = schema.validate(&parse_str(&long).unwrap());
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].code, ErrorCode::LimitExceeded);
+        }
+
+        let not = Schema::compile(&parse_str(r#"{"not": {"pattern": "[a-z]{0,500}1"}}"#).unwrap())
+            .unwrap();
+        let errors = not.validate(&parse_str(&long).unwrap());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::LimitExceeded);
+    }
+
+    #[test]
+    fn diagnostics() {
+        let schema =
+            Schema::compile(&parse_str(r#"{"properties": {"a": {"type": "string"}}}"#).unwrap())
+                .unwrap();
+        let errors = schema.validate(&parse_str(r#"{"a": 5}"#).unwrap());
+        let diagnostic = Diagnostic::from(&errors[0]);
+
+        assert_eq!(diagnostic.code, ErrorCode::SchemaViolation);
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(
+            diagnostic.message,
+            "/a: Expected string, found number (#/properties/a/type)"
+        );
+        assert_eq!((diagnostic.span.line, diagnostic.span.column), (1, 2));
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt::Write;
+
+use super::{
+    printer::escape_string,
+    typegen::{Definition, Kind, Model, Type},
+};
+
+/// Emits TypeScript declarations for a [`Model`].
+///
+/// Structs become interfaces with `?` on optional members, string enums
+/// unions of string literals, other unions union types, and open places
+/// `unknown`. Every declaration is exported.
+pub struct TypeScriptGenerator;
+
+impl TypeScriptGenerator {
+    pub fn generate(&self, model: &Model) -> String {
+        let mut declarations = vec![];
+
+        if model.root != Type::Named(model.name.clone()) {
+            declarations.push(format!(
+                "export type {} = {};\n",
+                model.name,
+                self.type_of(&model.root)
+            ));
+        }
+
+        for definition in &model.definitions {
+            declarations.push(self.definition(definition));
+        }
+
+        declarations.join("\n")
+    }
+
+    fn definition(&self, definition: &Definition) -> String {
+        let mut output = String::new();
+
+        match &definition.kind {
+            Kind::Struct(fields) => {
+                let _ = writeln!(output, "export interface {} {{", definition.name);
+
+                for field in fields {
+                    let _ = writeln!(
+                        output,
+                        "  {}{}: {};",
+                        property_name(&field.key),
+                        if field.required { "" } else { "?" },
+                        self.type_of(&field.ty)
+                    );
+                }
+
+                output.push_str("}\n");
+            }
+            Kind::Enum(values) => {
+                let values = values
+                    .iter()
+                    .map(|value| escape_string(value))
+                    .collect::<Vec<_>>();
+
+                let _ = writeln!(
+                    output,
+                    "export type {} = {};",
+                    definition.name,
+                    values.join(" | ")
+                );
+            }
+            Kind::Union(types) => {
+                let types = types.iter().map(|ty| self.type_of(ty)).collect::<Vec<_>>();
+
+                let _ = writeln!(
+                    output,
+                    "export type {} = {};",
+                    definition.name,
+                    types.join(" | ")
+                );
+            }
+        }
+
+        output
+    }
+
+    fn type_of(&self, ty: &Type) -> String {
+        match ty {
+            Type::Any => "unknown".to_string(),
+            Type::Null => "null".to_string(),
+            Type::Bool => "boolean".to_string(),
+            Type::Integer | Type::Number => "number".to_string(),
+            Type::String => "string".to_string(),
+            Type::Array(ty) => match **ty {
+                Type::Nullable(_) => format!("({})[]", self.type_of(ty)),
+                _ => format!("{}[]", self.type_of(ty)),
+            },
+            Type::Map(ty) => format!("Record<string, {}>", self.type_of(ty)),
+            Type::Nullable(ty) => format!("{} | null", self.type_of(ty)),
+            Type::Named(name) => name.clone(),
+        }
+    }
+}
+
+/// `key` as an interface member name, quoted unless it is an identifier.
+fn property_name(key: &str) -> String {
+    let mut chars = key.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_' || first == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+
+    if identifier {
+        key.to_string()
+    } else {
+        escape_string(key)
+    }
+}
+
+#[cfg(test)]
+mod tsgen_tests {
+    use super::*;
+    use crate::parser_update::nodes::parse_str;
+
+    fn generate(schema: &str) -> String {
+        TypeScriptGenerator
+            .generate(&Model::from_schema(&parse_str(schema).unwrap(), "Root").unwrap())
+    }
+
+    #[test]
+    fn interfaces() {
+        assert_eq!(
+            generate(
+                r##"{
+                    "type": "object",
+                    "properties": {
+                        "id": {"type": "integer"},
+                        "first-name": {"type": "string"},
+                        "mode": {"enum": ["dev", "prod", null]},
+                        "next": {"$ref": "#"},
+                        "tags": {"type": "array", "items": {"type": ["string", "boolean", "null"]}},
+                        "labels": {"additionalProperties": {"type": "number"}},
+                        "extra": {}
+                    },
+                    "required": ["id", "mode"]
+                }"##
+            ),
+            r#"export interface Root {
+  extra?: unknown;
+  "first-name"?: string;
+  id: number;
+  labels?: Record<string, number>;
+  mode: Mode | null;
+  next?: Root;
+  tags?: (Tag | null)[];
+}
+
+export type Mode = "dev" | "prod";
+
+export type Tag = string | boolean;
+"#
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        assert_eq!(
+            generate(r#"{"type": "array", "items": {"type": "object", "properties": {"a": {}}}}"#),
+            "export type Root = RootItem[];\n\
+             \n\
+             export interface RootItem {\n  a?: unknown;\n}\n"
+        );
+    }
+
+    #[test]
+    fn samples() {
+        let samples = [r#"{"id": 1, "$ref": "x"}"#, r#"{"id": 2.5}"#]
+            .map(|sample| parse_str(sample).unwrap());
+
+        assert_eq!(
+            TypeScriptGenerator.generate(&Model::from_samples(&samples, "item")),
+            "export interface Item {\n  $ref?: string;\n  id: number;\n}\n"
+        );
+    }
+}
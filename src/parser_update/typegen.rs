@@ -0,0 +1,849 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{
+    infer::{fits_i64, infer_schema},
+    nodes::{Literal, Node},
+    pointer::escape_token,
+    schema::SchemaError,
+};
+
+/// Names the generated code refers to unqualified, so definitions must not
+/// shadow them.
+const RESERVED: [&str; 8] = [
+    "Box",
+    "Deserialize",
+    "Option",
+    "Record",
+    "Self",
+    "Serialize",
+    "String",
+    "Vec",
+];
+
+/// The type of a value in generated code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    /// Any JSON value, for places the schema leaves open.
+    Any,
+    Null,
+    Bool,
+    Integer,
+    Number,
+    String,
+    Array(Box<Type>),
+    /// An object with arbitrary keys that all hold the same type.
+    Map(Box<Type>),
+    /// `null` or the inner type.
+    Nullable(Box<Type>),
+    /// One of [`Model::definitions`], by name.
+    Named(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// The member name as it appears in JSON.
+    pub key: String,
+    pub ty: Type,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kind {
+    Struct(Vec<Field>),
+    /// A fixed set of strings.
+    Enum(Vec<String>),
+    /// A value of any of several types, told apart by their JSON kind.
+    /// Never holds `Type::Null` or `Type::Nullable`.
+    Union(Vec<Type>),
+}
+
+/// A named type to declare, with a PascalCase name made of ASCII letters
+/// and digits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub name: String,
+    pub kind: Kind,
+}
+
+/// The types needed to represent a document, shared by the code
+/// generators.
+///
+/// Objects with declared members become structs, objects without become
+/// maps, strings from a fixed set become enums and places that allow
+/// several kinds of value become unions. Structurally identical
+/// definitions are declared once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Model {
+    /// The name of the document type. It is the root definition if `root`
+    /// is `Type::Named` with this name, otherwise an alias for `root`.
+    pub name: String,
+    pub root: Type,
+    /// Outer definitions before the ones nested in them.
+    pub definitions: Vec<Definition>,
+}
+
+impl Model {
+    /// Builds the model for a JSON Schema.
+    ///
+    /// Covers `type`, `properties`, `required`, `additionalProperties`,
+    /// `items`, `enum`, `const`, `anyOf`, `oneOf`, a single `allOf` and
+    /// local `$ref`. Anything else the schema says about a place makes no
+    /// difference to its type, and places it leaves open are `Type::Any`.
+    pub fn from_schema(schema: &Node, name: &str) -> Result<Model, SchemaError> {
+        let mut builder = Builder {
+            root: schema,
+            definitions: vec![],
+            names: vec![],
+            refs: BTreeMap::new(),
+        };
+
+        let root = builder.resolve("", name, "")?;
+
+        // The root definition, if any, is the first one named.
+        let name = match &root {
+            Type::Named(root) if builder.names.first() == Some(root) => root.clone(),
+            _ => builder.reserve(name),
+        };
+
+        let order = builder.names;
+        let mut definitions = builder.definitions;
+        definitions
+            .sort_by_key(|definition| order.iter().position(|name| *name == definition.name));
+
+        Ok(Model {
+            name,
+            root,
+            definitions,
+        })
+    }
+
+    /// Builds the model for the schema inferred from `samples`.
+    pub fn from_samples(samples: &[Node], name: &str) -> Model {
+        Self::from_schema(&infer_schema(samples), name)
+            .expect("inferred schemas only use supported keywords")
+    }
+
+    pub fn definition(&self, name: &str) -> Option<&Definition> {
+        self.definitions
+            .iter()
+            .find(|definition| definition.name == name)
+    }
+
+    /// Whether a value of type `ty` holds a `name` directly rather than
+    /// behind an array or map, so a recursive definition needs
+    /// indirection to have a finite size.
+    pub fn contains(&self, ty: &Type, name: &str) -> bool {
+        self.contains_inner(ty, name, &mut BTreeSet::new())
+    }
+
+    fn contains_inner<'a>(
+        &'a self,
+        ty: &'a Type,
+        name: &str,
+        seen: &mut BTreeSet<&'a str>,
+    ) -> bool {
+        match ty {
+            Type::Nullable(ty) => self.contains_inner(ty, name, seen),
+            Type::Named(other) if other == name => true,
+            Type::Named(other) if seen.insert(other) => match self.definition(other) {
+                Some(Definition {
+                    kind: Kind::Struct(fields),
+                    ..
+                }) => fields
+                    .iter()
+                    .any(|field| self.contains_inner(&field.ty, name, seen)),
+                Some(Definition {
+                    kind: Kind::Union(types),
+                    ..
+                }) => types.iter().any(|ty| self.contains_inner(ty, name, seen)),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+struct Builder<'a> {
+    root: &'a Node,
+    definitions: Vec<Definition>,
+    /// Every name taken, in the order the definitions were first reached.
+    names: Vec<String>,
+    /// The type of every `$ref` target reached so far. A target still
+    /// being built maps to a placeholder name, so recursion terminates.
+    refs: BTreeMap<String, Type>,
+}
+
+impl Builder<'_> {
+    /// The type of the values `schema` accepts. `hint` names any
+    /// definition it needs.
+    fn build(&mut self, schema: &Node, hint: &str, path: String) -> Result<Type, SchemaError> {
+        let Node::Object(..) = schema else {
+            return Ok(Type::Any);
+        };
+
+        let keyword = |name: &str| schema.get_member(name);
+        let at = |name: &str| format!("{}/{}", path, name);
+
+        if let Some(reference) = keyword("$ref") {
+            return self.reference(reference, hint, &at("$ref"));
+        }
+
+        if let Some(Node::List(_, values, _)) = keyword("enum") {
+            return Ok(self.enumeration(values, hint));
+        }
+
+        if let Some(value) = keyword("const") {
+            return Ok(self.enumeration(std::slice::from_ref(value), hint));
+        }
+
+        for name in ["anyOf", "oneOf"] {
+            if let Some(Node::List(_, schemas, _)) = keyword(name) {
+                let variants = schemas.iter().filter(|schema| !is_null(schema)).count();
+                let reserved = (variants > 1).then(|| self.reserve(hint));
+                let types = schemas
+                    .iter()
+                    .enumerate()
+                    .map(|(index, schema)| {
+                        self.build(schema, hint, format!("{}/{}", at(name), index))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                return Ok(self.union(types, hint, reserved));
+            }
+        }
+
+        if let Some(Node::List(_, schemas, _)) = keyword("allOf") {
+            return match schemas.as_slice() {
+                [schema] => self.build(schema, hint, at("allOf/0")),
+                _ => Ok(Type::Any),
+            };
+        }
+
+        let names = match keyword("type") {
+            Some(Node::List(_, names, _)) => names.iter().collect(),
+            Some(name) => vec![name],
+            None if keyword("properties").is_some()
+                || keyword("additionalProperties").is_some() =>
+            {
+                return self.object(&keyword, hint, &path)
+            }
+            None if keyword("items").is_some() => return self.array(&keyword, hint, &path),
+            None => return Ok(Type::Any),
+        };
+
+        let variants = names.iter().filter(|name| !is_null(name)).count();
+        let reserved = (variants > 1).then(|| self.reserve(hint));
+        let mut types = vec![];
+
+        for name in names {
+            types.push(match name {
+                Node::Primary(Literal::String(name)) => match name.as_str() {
+                    "null" => Type::Null,
+                    "boolean" => Type::Bool,
+                    "integer" if fits_integer(&keyword) => Type::Integer,
+                    "integer" => Type::Number,
+                    "number" => Type::Number,
+                    "string" => Type::String,
+                    "array" => self.array(&keyword, hint, &path)?,
+                    "object" => self.object(&keyword, hint, &path)?,
+                    _ => return Err(invalid(&at("type"), "Unknown type")),
+                },
+                _ => return Err(invalid(&at("type"), "'type' must be a string")),
+            });
+        }
+
+        Ok(self.union(types, hint, reserved))
+    }
+
+    fn object<'n>(
+        &mut self,
+        keyword: &dyn Fn(&str) -> Option<&'n Node>,
+        hint: &str,
+        path: &str,
+    ) -> Result<Type, SchemaError> {
+        let members = match keyword("properties") {
+            Some(Node::Object(_, members, _)) if !members.is_empty() => members,
+            _ => {
+                return Ok(Type::Map(Box::new(match keyword("additionalProperties") {
+                    Some(schema) => {
+                        self.build(schema, hint, format!("{}/additionalProperties", path))?
+                    }
+                    None => Type::Any,
+                })))
+            }
+        };
+
+        let required = match keyword("required") {
+            Some(Node::List(_, names, _)) => names
+                .iter()
+                .filter_map(|name| match name {
+                    Node::Primary(Literal::String(name)) => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect::<BTreeSet<_>>(),
+            _ => BTreeSet::new(),
+        };
+
+        let name = self.reserve(hint);
+        let mut fields = vec![];
+
+        for member in members {
+            if let Node::Property(key, _, schema) = member {
+                let key = key.literal.to_string();
+                let ty = self.build(
+                    schema,
+                    &key,
+                    format!("{}/properties/{}", path, escape_token(&key)),
+                )?;
+
+                fields.push(Field {
+                    required: required.contains(key.as_str()),
+                    key,
+                    ty,
+                });
+            }
+        }
+
+        Ok(self.define(name, Kind::Struct(fields)))
+    }
+
+    fn array<'n>(
+        &mut self,
+        keyword: &dyn Fn(&str) -> Option<&'n Node>,
+        hint: &str,
+        path: &str,
+    ) -> Result<Type, SchemaError> {
+        Ok(Type::Array(Box::new(match keyword("items") {
+            Some(schema) => self.build(schema, &singular(hint), format!("{}/items", path))?,
+            None => Type::Any,
+        })))
+    }
+
+    /// The type of `enum` or `const` values. Strings, optionally with
+    /// `null`, become an enum; anything else is left open.
+    fn enumeration(&mut self, values: &[Node], hint: &str) -> Type {
+        let nullable = values
+            .iter()
+            .any(|value| matches!(value, Node::Primary(Literal::Null)));
+
+        let mut strings = vec![];
+
+        for value in values {
+            match value {
+                Node::Primary(Literal::String(value)) if !strings.contains(value) => {
+                    strings.push(value.clone())
+                }
+                Node::Primary(Literal::String(_) | Literal::Null) => {}
+                _ => return Type::Any,
+            }
+        }
+
+        if strings.is_empty() {
+            return Type::Null;
+        }
+
+        let name = self.reserve(hint);
+        let ty = self.define(name, Kind::Enum(strings));
+
+        if nullable {
+            Type::Nullable(Box::new(ty))
+        } else {
+            ty
+        }
+    }
+
+    /// The type accepting any of `types`. A union gets the `reserved`
+    /// name if there is one, which callers take before building the
+    /// variants so that the union is not named after one of them.
+    fn union(&mut self, types: Vec<Type>, hint: &str, reserved: Option<String>) -> Type {
+        let mut nullable = false;
+        let mut variants = vec![];
+
+        for ty in types {
+            let ty = match ty {
+                Type::Null => {
+                    nullable = true;
+                    continue;
+                }
+                Type::Nullable(ty) => {
+                    nullable = true;
+                    *ty
+                }
+                ty => ty,
+            };
+
+            if !variants.contains(&ty) {
+                variants.push(ty);
+            }
+        }
+
+        // Every integer is also a number.
+        if variants.contains(&Type::Number) {
+            variants.retain(|ty| *ty != Type::Integer);
+        }
+
+        if variants.len() < 2 || variants.contains(&Type::Any) {
+            if let Some(reserved) = &reserved {
+                self.names.retain(|name| name != reserved);
+            }
+        }
+
+        let ty = match variants.len() {
+            0 => return Type::Null,
+            1 => variants.remove(0),
+            _ if variants.contains(&Type::Any) => Type::Any,
+            _ => {
+                let name = reserved.unwrap_or_else(|| self.reserve(hint));
+                self.define(name, Kind::Union(variants))
+            }
+        };
+
+        if nullable && ty != Type::Any {
+            Type::Nullable(Box::new(ty))
+        } else {
+            ty
+        }
+    }
+
+    fn reference(&mut self, reference: &Node, hint: &str, path: &str) -> Result<Type, SchemaError> {
+        match reference {
+            Node::Primary(Literal::String(reference)) => match reference.strip_prefix('#') {
+                Some(pointer) => self.resolve(pointer, hint, path),
+                None => Err(invalid(path, "Only local references are supported")),
+            },
+            _ => Err(invalid(path, "'$ref' must be a string")),
+        }
+    }
+
+    /// The type of the schema at `pointer`, built once however often it is
+    /// referenced. Targets are named after themselves rather than the
+    /// member using them, except for the root.
+    fn resolve(&mut self, pointer: &str, hint: &str, path: &str) -> Result<Type, SchemaError> {
+        if let Some(ty) = self.refs.get(pointer) {
+            return Ok(ty.clone());
+        }
+
+        let target = self
+            .root
+            .get_pointer(pointer)
+            .map_err(|_| invalid(path, "Unresolvable reference"))?;
+
+        let hint = match pointer.rsplit('/').next() {
+            Some("") | None => hint.to_string(),
+            Some(last) => last.replace("~1", "/").replace("~0", "~"),
+        };
+
+        // Recursive references get a placeholder, which cannot clash with
+        // real names, until the target's type is known.
+        let placeholder = format!("\0{}", pointer);
+        self.refs
+            .insert(pointer.to_string(), Type::Named(placeholder.clone()));
+
+        let ty = self.build(target, &hint, pointer.to_string())?;
+
+        // A target that recurses without a definition to break the cycle,
+        // such as an array of itself, is left open.
+        let with = if mentions(&ty, &placeholder) {
+            Type::Any
+        } else {
+            ty
+        };
+
+        for definition in &mut self.definitions {
+            match &mut definition.kind {
+                Kind::Struct(fields) => {
+                    for field in fields {
+                        replace(&mut field.ty, &placeholder, &with);
+                    }
+                }
+                Kind::Union(types) => {
+                    for ty in types.iter_mut() {
+                        replace(ty, &placeholder, &with);
+
+                        if let Type::Nullable(inner) = ty {
+                            *ty = std::mem::replace(&mut **inner, Type::Any);
+                        }
+                    }
+                }
+                Kind::Enum(_) => {}
+            }
+        }
+
+        for ty in self.refs.values_mut() {
+            replace(ty, &placeholder, &with);
+        }
+        self.refs.insert(pointer.to_string(), with.clone());
+
+        Ok(with)
+    }
+
+    /// Takes a unique name for a definition based on `hint`.
+    fn reserve(&mut self, hint: &str) -> String {
+        let mut base = pascal_case(hint);
+
+        if RESERVED.contains(&base.as_str()) {
+            base.push_str("Type");
+        }
+
+        let mut name = base.clone();
+        let mut counter = 1;
+
+        while self.names.contains(&name) {
+            counter += 1;
+            name = format!("{}{}", base, counter);
+        }
+
+        self.names.push(name.clone());
+        name
+    }
+
+    /// Declares `kind` under `name`, unless an identical definition exists,
+    /// in which case `name` is released and that one is used.
+    fn define(&mut self, name: String, kind: Kind) -> Type {
+        if let Some(existing) = self
+            .definitions
+            .iter()
+            .find(|definition| definition.kind == kind)
+        {
+            let existing = existing.name.clone();
+            self.names.retain(|other| *other != name);
+            return Type::Named(existing);
+        }
+
+        self.definitions.push(Definition {
+            name: name.clone(),
+            kind,
+        });
+
+        Type::Named(name)
+    }
+}
+
+/// Whether the bounds of an integer schema, if any, allow only values that
+/// fit in 64 bits. Larger integers are treated as numbers.
+fn fits_integer<'n>(keyword: &dyn Fn(&str) -> Option<&'n Node>) -> bool {
+    ["minimum", "maximum", "exclusiveMinimum", "exclusiveMaximum"]
+        .into_iter()
+        .all(|name| match keyword(name) {
+            Some(Node::Primary(Literal::Number(bound))) => fits_i64(*bound),
+            _ => true,
+        })
+}
+
+/// Whether `node` is the type name `null` or a schema allowing only `null`,
+/// neither of which needs a union variant.
+fn is_null(node: &Node) -> bool {
+    match node {
+        Node::Primary(Literal::String(name)) => name == "null",
+        Node::Object(_, properties, _) => {
+            matches!(properties.as_slice(), [Node::Property(key, _, value)]
+                if key.literal.to_string() == "type" && is_null(value))
+        }
+        _ => false,
+    }
+}
+
+/// Whether `ty` refers to the definition `name`.
+fn mentions(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Array(ty) | Type::Map(ty) | Type::Nullable(ty) => mentions(ty, name),
+        Type::Named(other) => other == name,
+        _ => false,
+    }
+}
+
+/// Replaces every reference to the definition `name` in `ty`.
+fn replace(ty: &mut Type, name: &str, with: &Type) {
+    match ty {
+        Type::Array(ty) | Type::Map(ty) | Type::Nullable(ty) => replace(ty, name, with),
+        Type::Named(other) if other == name => *ty = with.clone(),
+        _ => {}
+    }
+}
+
+fn invalid(path: &str, message: &str) -> SchemaError {
+    SchemaError {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// Splits `text` into words at non-alphanumeric characters and at case
+/// changes, so `userID`, `user_id` and `user-id` all give `user`, `ID`.
+pub fn words(text: &str) -> Vec<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut words = vec![];
+    let mut word = String::new();
+
+    for (index, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        let previous = index.checked_sub(1).map(|index| chars[index]);
+        let next = chars.get(index + 1);
+
+        let boundary = c.is_ascii_uppercase()
+            && previous.is_some_and(|previous| {
+                previous.is_ascii_lowercase()
+                    || previous.is_ascii_digit()
+                    || previous.is_ascii_uppercase() && next.is_some_and(char::is_ascii_lowercase)
+            });
+
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+
+        word.push(c);
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// `text` as a PascalCase identifier, such as `UserId` for `user_id`.
+pub fn pascal_case(text: &str) -> String {
+    let name = words(text)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect::<String>();
+
+    match name.chars().next() {
+        None => "Type".to_string(),
+        Some(first) if first.is_ascii_digit() => format!("Type{}", name),
+        Some(_) => name,
+    }
+}
+
+/// A guess at the name for one element of a list called `name`.
+fn singular(name: &str) -> String {
+    let lower = name.to_ascii_lowercase();
+
+    if let Some(stem) = name.strip_suffix("ies").filter(|stem| !stem.is_empty()) {
+        format!("{}y", stem)
+    } else if ["sses", "shes", "ches", "xes"]
+        .iter()
+        .any(|suffix| lower.ends_with(suffix))
+    {
+        name[..name.len() - 2].to_string()
+    } else if lower.ends_with('s')
+        && !["ss", "us", "is"]
+            .iter()
+            .any(|suffix| lower.ends_with(suffix))
+    {
+        name[..name.len() - 1].to_string()
+    } else {
+        format!("{}Item", name)
+    }
+}
+
+#[cfg(test)]
+mod typegen_tests {
+    use super::*;
+    use crate::parser_update::nodes::parse_str;
+
+    fn model(schema: &str) -> Model {
+        Model::from_schema(&parse_str(schema).unwrap(), "Root").unwrap()
+    }
+
+    fn named(name: &str) -> Type {
+        Type::Named(name.to_string())
+    }
+
+    #[test]
+    fn samples() {
+        let samples = [
+            r#"{"id": 1, "mode": "dev", "tags": ["x"], "owner": {"id": 7}}"#,
+            r#"{"id": 2, "mode": "prod", "tags": [1], "owner": null}"#,
+            r#"{"id": 3, "mode": "dev", "tags": []}"#,
+        ]
+        .map(|sample| parse_str(sample).unwrap());
+        let model = Model::from_samples(&samples, "payload");
+
+        assert_eq!(model.name, "Payload");
+        assert_eq!(model.root, named("Payload"));
+        assert_eq!(
+            model
+                .definitions
+                .iter()
+                .map(|definition| definition.name.as_str())
+                .collect::<Vec<_>>(),
+            ["Payload", "Mode", "Owner", "Tag"]
+        );
+        assert_eq!(
+            model.definition("Payload").unwrap().kind,
+            Kind::Struct(vec![
+                Field {
+                    key: "id".to_string(),
+                    ty: Type::Integer,
+                    required: true,
+                },
+                Field {
+                    key: "mode".to_string(),
+                    ty: named("Mode"),
+                    required: true,
+                },
+                Field {
+                    key: "owner".to_string(),
+                    ty: Type::Nullable(Box::new(named("Owner"))),
+                    required: false,
+                },
+                Field {
+                    key: "tags".to_string(),
+                    ty: Type::Array(Box::new(named("Tag"))),
+                    required: true,
+                },
+            ])
+        );
+        assert_eq!(
+            model.definition("Mode").unwrap().kind,
+            Kind::Enum(vec!["dev".to_string(), "prod".to_string()])
+        );
+        assert_eq!(
+            model.definition("Tag").unwrap().kind,
+            Kind::Union(vec![Type::Integer, Type::String])
+        );
+    }
+
+    #[test]
+    fn references() {
+        let model = model(
+            r##"{
+                "$defs": {
+                    "Node": {
+                        "type": ["object", "null"],
+                        "properties": {"next": {"$ref": "#/$defs/Node"}}
+                    },
+                    "Nested": {"type": "array", "items": {"$ref": "#/$defs/Nested"}}
+                },
+                "properties": {
+                    "a": {"$ref": "#/$defs/Node"},
+                    "b": {"$ref": "#/$defs/Node"},
+                    "c": {"$ref": "#/$defs/Nested"},
+                    "d": {"$ref": "#"}
+                }
+            }"##,
+        );
+
+        let node = Type::Nullable(Box::new(named("Node")));
+        let Kind::Struct(fields) = &model.definition("Root").unwrap().kind else {
+            panic!("expected a struct");
+        };
+        assert_eq!(
+            fields.iter().map(|field| &field.ty).collect::<Vec<_>>(),
+            [&node, &node, &Type::Any, &named("Root")]
+        );
+        assert_eq!(
+            model.definition("Node").unwrap().kind,
+            Kind::Struct(vec![Field {
+                key: "next".to_string(),
+                ty: node.clone(),
+                required: false,
+            }])
+        );
+        assert!(model.contains(&node, "Node"));
+        assert!(!model.contains(&Type::Array(Box::new(node)), "Node"));
+
+        assert_eq!(
+            Model::from_schema(
+                &parse_str(r##"{"$ref": "#/$defs/Missing"}"##).unwrap(),
+                "Root"
+            )
+            .unwrap_err()
+            .path,
+            "/$ref"
+        );
+    }
+
+    #[test]
+    fn unions() {
+        let model = model(
+            r#"{
+                "type": "object",
+                "properties": {
+                    "shape": {"oneOf": [
+                        {"properties": {"r": {"type": "number"}}},
+                        {"type": "string"},
+                        {"type": "null"}
+                    ]},
+                    "count": {"type": ["integer", "number"]},
+                    "labels": {"additionalProperties": {"const": "on"}}
+                }
+            }"#,
+        );
+
+        assert_eq!(model.name, "Root");
+        assert_eq!(
+            model.definition("Shape").unwrap().kind,
+            Kind::Union(vec![named("Shape2"), Type::String])
+        );
+        let Kind::Struct(fields) = &model.definition("Root").unwrap().kind else {
+            panic!("expected a struct");
+        };
+        assert_eq!(
+            fields.iter().map(|field| &field.ty).collect::<Vec<_>>(),
+            [
+                &Type::Number,
+                &Type::Map(Box::new(named("Labels"))),
+                &Type::Nullable(Box::new(named("Shape")))
+            ]
+        );
+    }
+
+    #[test]
+    fn large_integers() {
+        let samples = [r#"{"a": 1, "b": 1}"#, r#"{"a": 2, "b": 1e19}"#]
+            .map(|sample| parse_str(sample).unwrap());
+        let sampled = Model::from_samples(&samples, "Root");
+
+        let Kind::Struct(fields) = &sampled.definition("Root").unwrap().kind else {
+            panic!("expected a struct");
+        };
+        assert_eq!(fields[0].ty, Type::Integer);
+        assert_eq!(fields[1].ty, Type::Number);
+
+        assert_eq!(
+            model(r#"{"type": "integer", "maximum": 18446744073709551615}"#).root,
+            Type::Number
+        );
+        assert_eq!(
+            model(r#"{"type": "integer", "minimum": 0}"#).root,
+            Type::Integer
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        let model =
+            model(r#"{"type": "array", "items": {"type": "object", "properties": {"id": {}}}}"#);
+
+        assert_eq!(model.name, "Root");
+        assert_eq!(model.root, Type::Array(Box::new(named("RootItem"))));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(words("userID"), ["user", "ID"]);
+        assert_eq!(words("HTTPServer2go"), ["HTTP", "Server2go"]);
+        assert_eq!(words("first-name_x"), ["first", "name", "x"]);
+        assert_eq!(pascal_case("user_id"), "UserId");
+        assert_eq!(pascal_case("2fa"), "Type2fa");
+        assert_eq!(pascal_case("-"), "Type");
+        assert_eq!(singular("entries"), "entry");
+        assert_eq!(singular("boxes"), "box");
+        assert_eq!(singular("tags"), "tag");
+        assert_eq!(singular("status"), "statusItem");
+    }
+}